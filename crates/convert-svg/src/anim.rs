@@ -0,0 +1,224 @@
+use crate::{invalid_input, Flags};
+use convert_svg::tiny_skia::Pixmap;
+use convert_svg::{options, parse_svg, render, rgba, Fit, Size};
+use std::io;
+
+enum Animation {
+    Apng,
+    Gif,
+}
+impl TryFrom<&str> for Animation {
+    type Error = io::Error;
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        Ok(match format {
+            "apng" | "png" => Self::Apng,
+            "gif" => Self::Gif,
+            _ => {
+                return Err(invalid_input(format!(
+                    "Unknown animation format: {}",
+                    format
+                )))
+            }
+        })
+    }
+}
+
+/// Replaces every `{{name}}` in the template with `value`.
+pub fn expand(template: &str, name: &str, value: &str) -> String {
+    template.replace(&format!("{{{{{}}}}}", name), value)
+}
+
+/// Parses `from:to:count` into `count` evenly spaced values, both ends included.
+fn parse_range(range: &str) -> io::Result<Box<[Box<str>]>> {
+    let error = || invalid_input(format!("Invalid range: {}", range));
+    let mut parts = range.split(':');
+    let (Some(from), Some(to), Some(count), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(error());
+    };
+    let from: f64 = from.parse().map_err(|_| error())?;
+    let to: f64 = to.parse().map_err(|_| error())?;
+    let count: u32 = count.parse().map_err(|_| error())?;
+    Ok(match count {
+        0 => return Err(error()),
+        1 => Box::new([from.to_string().into()]),
+        _ => (0..count)
+            .map(|i| {
+                (from + (to - from) * i as f64 / (count - 1) as f64)
+                    .to_string()
+                    .into()
+            })
+            .collect(),
+    })
+}
+
+/// Loads the frame sources, either one file per frame or a template swept across values.
+fn load(flags: &Flags, files: &[&str]) -> io::Result<Box<[String]>> {
+    let Some(template) = flags.get("template") else {
+        return files.iter().map(std::fs::read_to_string).collect();
+    };
+    if !files.is_empty() {
+        return Err(invalid_input("--template does not take frame files"));
+    }
+    let values: Box<[Box<str>]> = match (flags.get("values"), flags.get("range")) {
+        (Some(values), None) => values.split(',').map(Box::from).collect(),
+        (None, Some(range)) => parse_range(range)?,
+        _ => {
+            return Err(invalid_input(
+                "--template requires either --values or --range",
+            ))
+        }
+    };
+    let name = flags.get("param").copied().unwrap_or("t");
+    let template = std::fs::read_to_string(template)?;
+    Ok(values
+        .iter()
+        .map(|value| expand(&template, name, value))
+        .collect())
+}
+
+fn encode_apng(frames: &[Pixmap], delays: &[u16], plays: u32) -> io::Result<Box<[u8]>> {
+    use png::{BitDepth, BlendOp, ColorType, DisposeOp, Encoder};
+    let (width, height) = (frames[0].width(), frames[0].height());
+    let mut buffer = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut buffer, width, height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, plays)?;
+        let mut writer = encoder.write_header()?;
+        writer.set_blend_op(BlendOp::Source)?;
+        writer.set_dispose_op(DisposeOp::Background)?;
+        for (frame, &delay) in frames.iter().zip(delays) {
+            writer.set_frame_delay(delay, 1000)?;
+            writer.write_image_data(&rgba(frame))?;
+        }
+        writer.finish()?;
+    }
+    Ok(buffer.into_boxed_slice())
+}
+
+fn encode_gif(frames: &[Pixmap], delays: &[u16], plays: u32) -> io::Result<Box<[u8]>> {
+    use gif::{DisposalMethod, Encoder, Frame, Repeat};
+    let (Ok(width), Ok(height)) = (
+        u16::try_from(frames[0].width()),
+        u16::try_from(frames[0].height()),
+    ) else {
+        return Err(invalid_input("gif frames are limited to 65535px"));
+    };
+    let mut buffer = Vec::new();
+    {
+        let mut encoder =
+            Encoder::new(&mut buffer, width, height, &[]).map_err(io::Error::other)?;
+        // APNG counts every play but GIF only the repeats after the first, and a single play
+        // needs no loop extension at all
+        let repeat = match plays {
+            0 => Some(Repeat::Infinite),
+            1 => None,
+            n => Some(Repeat::Finite(u16::try_from(n - 1).unwrap_or(u16::MAX))),
+        };
+        if let Some(repeat) = repeat {
+            encoder.set_repeat(repeat).map_err(io::Error::other)?;
+        }
+        for (frame, &delay) in frames.iter().zip(delays) {
+            let mut data = rgba(frame);
+            let mut frame = Frame::from_rgba_speed(width, height, &mut data, 10);
+            // GIF delays are in hundredths of a second
+            frame.delay = delay.div_ceil(10);
+            frame.dispose = DisposalMethod::Background;
+            encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+    }
+    Ok(buffer.into_boxed_slice())
+}
+
+pub fn main(flags: &Flags, files: &[&str]) -> io::Result<Box<[u8]>> {
+    let format = match flags.get("format") {
+        Some(format) => Animation::try_from(*format)?,
+        None => Animation::Apng,
+    };
+    let size = match flags.get("size") {
+        Some(size) => Some(Size::try_from(*size)?),
+        None => None,
+    };
+    let fit = match flags.get("fit") {
+        Some(fit) => Fit::try_from(*fit)?,
+        None => Fit::default(),
+    };
+    let plays = match flags.get("loop") {
+        Some(plays) => plays.parse().map_err(invalid_input)?,
+        None => 0,
+    };
+
+    let sources = load(flags, files)?;
+    if sources.is_empty() {
+        return Err(invalid_input("No frames"));
+    }
+    let opt = options();
+    let trees = sources
+        .iter()
+        .map(|source| parse_svg(source, &opt))
+        .collect::<io::Result<Box<[_]>>>()?;
+    // Every frame shares the size of the first one unless a size is given
+    let size = size.unwrap_or_else(|| Size::of(&trees[0]));
+    let frames = trees
+        .iter()
+        .map(|tree| render(tree, Some(size), fit))
+        .collect::<io::Result<Box<[_]>>>()?;
+
+    // The last delay is repeated for the remaining frames
+    let delays: Box<[u16]> = match flags.get("delay") {
+        Some(list) => list
+            .split(',')
+            .map(|delay| delay.parse().map_err(invalid_input))
+            .collect::<io::Result<_>>()?,
+        None => Box::new([100]),
+    };
+    let delays: Box<[u16]> = (0..frames.len())
+        .map(|i| delays[i.min(delays.len() - 1)])
+        .collect();
+
+    match format {
+        Animation::Apng => encode_apng(&frames, &delays, plays),
+        Animation::Gif => encode_gif(&frames, &delays, plays),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames() -> Box<[Pixmap]> {
+        (0..2).map(|_| Pixmap::new(4, 4).unwrap()).collect()
+    }
+
+    fn gif_repeat(plays: u32) -> gif::Repeat {
+        let data = encode_gif(&frames(), &[100, 100], plays).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(&*data).unwrap();
+        while decoder.read_next_frame().unwrap().is_some() {}
+        decoder.repeat()
+    }
+
+    fn apng_plays(plays: u32) -> u32 {
+        let data = encode_apng(&frames(), &[100, 100], plays).unwrap();
+        let reader = png::Decoder::new(&*data).read_info().unwrap();
+        reader.info().animation_control.unwrap().num_plays
+    }
+
+    #[test]
+    fn gif_loop() {
+        assert_eq!(gif_repeat(0), gif::Repeat::Infinite);
+        // Without the extension decoders report a single play as `Finite(0)`
+        assert_eq!(gif_repeat(1), gif::Repeat::Finite(0));
+        assert_eq!(gif_repeat(2), gif::Repeat::Finite(1));
+        assert_eq!(gif_repeat(3), gif::Repeat::Finite(2));
+    }
+
+    #[test]
+    fn apng_loop() {
+        assert_eq!(apng_plays(0), 0);
+        assert_eq!(apng_plays(1), 1);
+        assert_eq!(apng_plays(2), 2);
+    }
+}
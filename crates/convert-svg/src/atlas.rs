@@ -0,0 +1,427 @@
+use crate::{invalid_input, Flags};
+use convert_svg::tiny_skia::{Pixmap, PixmapPaint, Transform};
+use convert_svg::usvg::{Indent, Tree, WriteOptions};
+use convert_svg::{encode_webp, escape_xml, options, parse_svg, render, Fit, Size};
+use json::{object, JsonValue};
+use std::collections::BTreeMap as Map;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+type Named<T> = (Box<str>, T);
+
+#[derive(Clone, Copy)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+impl From<Rect> for JsonValue {
+    fn from(rect: Rect) -> JsonValue {
+        object! { x: rect.x, y: rect.y, w: rect.width, h: rect.height }
+    }
+}
+
+struct Segment {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+/// Lowest `y` at which a box of `width` fits when placed at `skyline[i].x`.
+fn skyline_fit(skyline: &[Segment], i: usize, width: u32, limit: u32) -> Option<u32> {
+    let x = skyline[i].x;
+    if x + width > limit {
+        return None;
+    }
+    let mut y = 0;
+    let mut remaining = width;
+    for segment in &skyline[i..] {
+        y = y.max(segment.y);
+        if segment.width >= remaining {
+            return Some(y);
+        }
+        remaining -= segment.width;
+    }
+    None
+}
+
+/// Packs boxes of the given sizes using the skyline bottom-left heuristic,
+/// returning the sheet size and the placement of each box in input order.
+pub fn pack(sizes: &[(u32, u32)], padding: u32) -> (u32, u32, Box<[Rect]>) {
+    let area: u64 = sizes
+        .iter()
+        .map(|&(w, h)| (w + padding) as u64 * (h + padding) as u64)
+        .sum();
+    let widest = sizes.iter().map(|&(w, _)| w + padding).max().unwrap_or(0);
+    let limit = widest.max((area as f64).sqrt().ceil() as u32);
+
+    let mut order: Box<[usize]> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse((sizes[i].1, sizes[i].0)));
+
+    let mut skyline = vec![Segment {
+        x: 0,
+        y: 0,
+        width: limit,
+    }];
+    let mut rects = vec![
+        Rect {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        };
+        sizes.len()
+    ];
+    let (mut sheet_width, mut sheet_height) = (0, 0);
+    for i in order {
+        let (width, height) = sizes[i];
+        let (w, h) = (width + padding, height + padding);
+        let (index, y) = (0..skyline.len())
+            .filter_map(|j| Some((j, skyline_fit(&skyline, j, w, limit)?)))
+            .min_by_key(|&(j, y)| (y, skyline[j].x))
+            .expect("the widest box fits the sheet");
+        let x = skyline[index].x;
+
+        skyline.insert(
+            index,
+            Segment {
+                x,
+                y: y + h,
+                width: w,
+            },
+        );
+        let end = x + w;
+        while let Some(next) = skyline.get_mut(index + 1) {
+            if next.x >= end {
+                break;
+            }
+            let overlap = end - next.x;
+            if next.width <= overlap {
+                skyline.remove(index + 1);
+            } else {
+                next.x += overlap;
+                next.width -= overlap;
+                break;
+            }
+        }
+        skyline.dedup_by(|next, prev| {
+            let merge = next.y == prev.y;
+            if merge {
+                prev.width += next.width;
+            }
+            merge
+        });
+
+        rects[i] = Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        sheet_width = sheet_width.max(x + width);
+        sheet_height = sheet_height.max(y + height);
+    }
+    (sheet_width, sheet_height, rects.into_boxed_slice())
+}
+
+/// Combines the trees into one SVG of `<symbol>` elements keyed by name.
+///
+/// Ids inside each symbol are prefixed with its [`css_class`], so names must not collide there.
+pub fn sprite(trees: &[Named<Tree>]) -> String {
+    let mut output = String::from(concat!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" "#,
+        r#"xmlns:xlink="http://www.w3.org/1999/xlink" style="display:none">"#,
+    ));
+    for (name, tree) in trees {
+        let opt = WriteOptions {
+            id_prefix: Some(format!("{}-", css_class(name))),
+            indent: Indent::None,
+            ..Default::default()
+        };
+        let svg = tree.to_string(&opt);
+        let start = svg
+            .find("<svg")
+            .and_then(|i| svg[i..].find('>').map(|j| i + j + 1));
+        let end = svg.rfind("</svg>");
+        let body = match (start, end) {
+            (Some(start), Some(end)) if start <= end => &svg[start..end],
+            _ => "",
+        };
+        let size = tree.size();
+        let _ = write!(
+            output,
+            r#"<symbol id="{}" viewBox="0 0 {} {}">{}</symbol>"#,
+            escape_xml(name),
+            size.width(),
+            size.height(),
+            body
+        );
+    }
+    output.push_str("</svg>");
+    output
+}
+
+/// The name with every character not allowed in a CSS class replaced by `-`.
+pub fn css_class(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '-',
+        })
+        .collect()
+}
+
+/// Fails when two names map to the same [`css_class`].
+pub fn check_classes<'a>(names: impl IntoIterator<Item = &'a str>) -> io::Result<()> {
+    let mut classes = Map::new();
+    for name in names {
+        if let Some(other) = classes.insert(css_class(name), name) {
+            let message = format!(
+                "Names {} and {} both become class {}",
+                other,
+                name,
+                css_class(name)
+            );
+            return Err(invalid_input(message));
+        }
+    }
+    Ok(())
+}
+
+pub fn css(prefix: &str, url: &str, items: &Map<Box<str>, Rect>) -> String {
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "[class^=\"{}\"] {{ display: inline-block; background: url(\"{}\") no-repeat; }}",
+        prefix, url
+    );
+    for (name, rect) in items {
+        let _ = writeln!(
+            output,
+            ".{}{} {{ width: {}px; height: {}px; background-position: {}px {}px; }}",
+            prefix,
+            css_class(name),
+            rect.width,
+            rect.height,
+            -(rect.x as i64),
+            -(rect.y as i64)
+        );
+    }
+    output
+}
+
+enum Sheet {
+    Png,
+    Webp,
+    Svg,
+}
+impl TryFrom<&str> for Sheet {
+    type Error = io::Error;
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        Ok(match format {
+            "png" => Self::Png,
+            "webp" => Self::Webp,
+            "svg" => Self::Svg,
+            _ => return Err(invalid_input(format!("Unknown atlas format: {}", format))),
+        })
+    }
+}
+impl Sheet {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Webp => "webp",
+            Self::Svg => "svg",
+        }
+    }
+}
+
+fn load(files: &[&str]) -> io::Result<Box<[Named<Tree>]>> {
+    let opt = options();
+    let mut names = std::collections::BTreeSet::new();
+    files
+        .iter()
+        .map(|file| {
+            let path = Path::new(file);
+            let name: Box<str> = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| invalid_input(format!("Invalid file name: {}", file)))?
+                .into();
+            if !names.insert(name.clone()) {
+                return Err(invalid_input(format!("Duplicate name: {}", name)));
+            }
+            Ok((name, parse_svg(std::fs::read(path)?, &opt)?))
+        })
+        .collect()
+}
+
+pub fn main(flags: &Flags, files: &[&str]) -> io::Result<Box<[u8]>> {
+    let sheet = match flags.get("format") {
+        Some(format) => Sheet::try_from(*format)?,
+        None => Sheet::Png,
+    };
+    let sizes: Box<[Option<Size>]> = match flags.get("size") {
+        Some(list) => Size::parse_list(list)?.iter().copied().map(Some).collect(),
+        None => Box::new([None]),
+    };
+    let fit = match flags.get("fit") {
+        Some(fit) => Fit::try_from(*fit)?,
+        None => Fit::default(),
+    };
+    let padding = match flags.get("padding") {
+        Some(padding) => padding.parse().map_err(invalid_input)?,
+        None => 1,
+    };
+    let trees = load(files)?;
+
+    if let Sheet::Svg = sheet {
+        // Symbols keep their own viewBox and scale wherever they are used
+        for flag in ["css", "size", "fit"] {
+            if flags.contains_key(flag) {
+                let message = format!("--{} is not supported for svg sprites", flag);
+                return Err(invalid_input(message));
+            }
+        }
+        check_classes(trees.iter().map(|(name, _)| &**name))?;
+        if let Some(path) = flags.get("index") {
+            let items: Map<&str, JsonValue> = trees
+                .iter()
+                .map(|(name, tree)| {
+                    let size = tree.size();
+                    (&**name, object! { w: size.width(), h: size.height() })
+                })
+                .collect();
+            std::fs::write(path, json::stringify(items))?;
+        }
+        return Ok(sprite(&trees).into_bytes().into_boxed_slice());
+    }
+
+    let mut pixmaps: Vec<Named<Pixmap>> = Vec::new();
+    for (name, tree) in &trees {
+        for &size in &sizes {
+            let name = match size {
+                Some(size) if sizes.len() > 1 => format!("{}@{}", name, size).into(),
+                _ => name.clone(),
+            };
+            pixmaps.push((name, render(tree, size, fit)?));
+        }
+    }
+    let boxes: Box<[(u32, u32)]> = pixmaps
+        .iter()
+        .map(|(_, pixmap)| (pixmap.width(), pixmap.height()))
+        .collect();
+    let (width, height, rects) = pack(&boxes, padding);
+
+    let mut output = Pixmap::new(width.max(1), height.max(1))
+        .ok_or(io::ErrorKind::InvalidInput)
+        .map_err(io::Error::from)?;
+    for ((_, pixmap), rect) in pixmaps.iter().zip(&rects) {
+        let paint = PixmapPaint::default();
+        let (x, y) = (rect.x as i32, rect.y as i32);
+        output.draw_pixmap(x, y, pixmap.as_ref(), &paint, Transform::identity(), None);
+    }
+    let items: Map<Box<str>, Rect> = pixmaps
+        .into_iter()
+        .map(|(name, _)| name)
+        .zip(rects.iter().copied())
+        .collect();
+
+    // Everything that can fail runs before the first file is written
+    if flags.contains_key("css") {
+        check_classes(items.keys().map(|name| &**name))?;
+    }
+    let image = match sheet {
+        Sheet::Png => output.encode_png()?.into_boxed_slice(),
+        _ => encode_webp(&output)?,
+    };
+
+    let default_url = format!("atlas.{}", sheet.extension());
+    let url = flags.get("url").copied().unwrap_or(&default_url);
+    if let Some(path) = flags.get("index") {
+        let json = object! {
+            image: url,
+            width: output.width(),
+            height: output.height(),
+            items: items.clone(),
+        };
+        std::fs::write(path, json::stringify(json))?;
+    }
+    if let Some(path) = flags.get("css") {
+        let prefix = flags.get("prefix").copied().unwrap_or("icon-");
+        std::fs::write(path, css(prefix, url, &items))?;
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: &Rect, b: &Rect) -> bool {
+        a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+    }
+
+    #[test]
+    fn pack_without_overlap() {
+        let sizes = [(32, 32), (16, 16), (16, 16), (64, 24), (8, 40), (16, 16)];
+        let (width, height, rects) = pack(&sizes, 1);
+        for (rect, &(w, h)) in rects.iter().zip(&sizes) {
+            assert_eq!((rect.width, rect.height), (w, h));
+            assert!(rect.x + rect.width <= width && rect.y + rect.height <= height);
+        }
+        for (i, a) in rects.iter().enumerate() {
+            for b in &rects[i + 1..] {
+                assert!(!overlaps(a, b));
+            }
+        }
+        let area: u32 = sizes.iter().map(|&(w, h)| (w + 1) * (h + 1)).sum();
+        assert!(width * height <= area * 2);
+    }
+
+    #[test]
+    fn pack_edge_cases() {
+        let (width, height, rects) = pack(&[], 1);
+        assert_eq!((width, height, rects.len()), (0, 0, 0));
+
+        let (width, height, rects) = pack(&[(10, 20)], 4);
+        assert_eq!((width, height), (10, 20));
+        assert_eq!((rects[0].x, rects[0].y), (0, 0));
+
+        // Four equal squares fill a 2x2 grid
+        let (width, height, _) = pack(&[(8, 8); 4], 0);
+        assert_eq!((width, height), (16, 16));
+    }
+
+    #[test]
+    fn nothing_written_on_collision() {
+        let dir = std::env::temp_dir().join(format!("convert-svg-atlas-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="8"><rect width="8" height="8"/></svg>"#;
+        let (a, b) = (dir.join("a-b.svg"), dir.join("a.b.svg"));
+        std::fs::write(&a, svg).unwrap();
+        std::fs::write(&b, svg).unwrap();
+        let (index, css) = (dir.join("atlas.json"), dir.join("atlas.css"));
+        let paths =
+            [a, b, index.clone(), css.clone()].map(|path| path.to_string_lossy().into_owned());
+        let flags: Flags = [("index", &*paths[2]), ("css", &*paths[3])].into();
+        // `a-b` and `a.b` share a class
+        assert!(main(&flags, &[&paths[0], &paths[1]]).is_err());
+        assert!(!index.exists() && !css.exists());
+
+        assert!(main(&flags, &[&paths[0]]).is_ok());
+        assert!(index.exists() && css.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn class_collisions() {
+        assert_eq!(css_class("a@16"), "a-16");
+        assert!(check_classes(["a@16", "a@32", "b"]).is_ok());
+        assert!(check_classes(["a@16", "a-16"]).is_err());
+        assert_eq!(escape_xml(r#"a"<&>'"#), "a&quot;&lt;&amp;&gt;&apos;");
+    }
+}
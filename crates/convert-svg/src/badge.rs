@@ -0,0 +1,324 @@
+use crate::tiny_skia::{Mask, MaskType, Pixmap, PixmapPaint, Transform};
+use crate::usvg::Options;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{encode_ico, options, Size};
+use crate::{escape_xml, invalid_input, parse_svg, render, Fit};
+use std::fmt::Write;
+use std::io;
+
+#[derive(Clone, Copy, Default)]
+pub enum Shape {
+    #[default]
+    Circle,
+    Rounded,
+}
+impl TryFrom<&str> for Shape {
+    type Error = io::Error;
+    fn try_from(shape: &str) -> Result<Self, Self::Error> {
+        Ok(match shape {
+            "circle" => Self::Circle,
+            "rounded" => Self::Rounded,
+            _ => return Err(invalid_input(format!("Unknown shape: {}", shape))),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub enum Anchor {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+impl TryFrom<&str> for Anchor {
+    type Error = io::Error;
+    fn try_from(anchor: &str) -> Result<Self, Self::Error> {
+        Ok(match anchor {
+            "top-left" => Self::TopLeft,
+            "top-right" => Self::TopRight,
+            "bottom-left" => Self::BottomLeft,
+            "bottom-right" => Self::BottomRight,
+            _ => return Err(invalid_input(format!("Unknown anchor: {}", anchor))),
+        })
+    }
+}
+
+pub struct Badge {
+    /// Short text or number, an empty string draws a plain dot.
+    pub text: String,
+    pub shape: Shape,
+    pub anchor: Anchor,
+    /// Fill color, any SVG color.
+    pub fill: String,
+    /// Text color, any SVG color.
+    pub color: String,
+    /// Height of the badge relative to the icon.
+    pub scale: f32,
+    /// Width of the transparent ring cut around the badge, relative to its height.
+    pub ring: f32,
+}
+impl Default for Badge {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            shape: Shape::Circle,
+            anchor: Anchor::TopRight,
+            fill: "#e53935".into(),
+            color: "#ffffff".into(),
+            scale: 0.5,
+            ring: 0.125,
+        }
+    }
+}
+
+/// Text smaller than this many pixels is dropped, leaving a plain dot.
+const MIN_FONT_SIZE: f32 = 6.0;
+
+struct Bounds {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    font_size: Option<f32>,
+}
+
+impl Badge {
+    /// Rejects a badge taller than the icon or a negative ring.
+    pub fn check(&self) -> io::Result<()> {
+        if !(self.scale.is_finite() && self.scale > 0.0 && self.scale <= 1.0) {
+            let message = format!("Scale must be in (0, 1]: {}", self.scale);
+            return Err(invalid_input(message));
+        }
+        if !(self.ring.is_finite() && self.ring >= 0.0) {
+            let message = format!("Ring must not be negative: {}", self.ring);
+            return Err(invalid_input(message));
+        }
+        Ok(())
+    }
+
+    fn bounds(&self, width: u32, height: u32) -> Bounds {
+        let (icon_width, icon_height) = (width as f32, height as f32);
+        let height = icon_width.min(icon_height) * self.scale;
+        let font_size =
+            Some(height * 0.7).filter(|&size| !self.text.is_empty() && size >= MIN_FONT_SIZE);
+        let width = match (self.shape, font_size) {
+            (Shape::Rounded, Some(size)) => {
+                let chars = self.text.chars().count() as f32;
+                (chars * size * 0.6 + height * 0.5)
+                    .max(height)
+                    .min(icon_width)
+            }
+            _ => height,
+        };
+        let x = match self.anchor {
+            Anchor::TopLeft | Anchor::BottomLeft => 0.0,
+            Anchor::TopRight | Anchor::BottomRight => icon_width - width,
+        };
+        let y = match self.anchor {
+            Anchor::TopLeft | Anchor::TopRight => 0.0,
+            Anchor::BottomLeft | Anchor::BottomRight => icon_height - height,
+        };
+        Bounds {
+            x,
+            y,
+            width,
+            height,
+            font_size,
+        }
+    }
+
+    /// The badge outline grown by `grow` on every side.
+    fn shape(&self, bounds: &Bounds, grow: f32, fill: &str) -> String {
+        let (x, y) = (bounds.x - grow, bounds.y - grow);
+        let (width, height) = (bounds.width + grow * 2.0, bounds.height + grow * 2.0);
+        let fill = escape_xml(fill);
+        match self.shape {
+            Shape::Circle => format!(
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" fill="{}"/>"#,
+                x + width / 2.0,
+                y + height / 2.0,
+                width / 2.0,
+                height / 2.0,
+                fill
+            ),
+            Shape::Rounded => format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" fill="{}"/>"#,
+                x,
+                y,
+                width,
+                height,
+                bounds.height / 4.0 + grow,
+                fill
+            ),
+        }
+    }
+
+    /// Cuts the ring out of the pixmap and draws the badge on top of it.
+    pub fn draw(&self, pixmap: &mut Pixmap, opt: &Options) -> io::Result<()> {
+        self.check()?;
+        let (width, height) = (pixmap.width(), pixmap.height());
+        let bounds = self.bounds(width, height);
+        let svg = |body: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">{}</svg>"#,
+                width, height, body
+            )
+        };
+
+        if self.ring > 0.0 {
+            let grow = bounds.height * self.ring;
+            let cutout = render(
+                &parse_svg(svg(&self.shape(&bounds, grow, "#000")), opt)?,
+                None,
+                Fit::Fill,
+            )?;
+            let mut mask = Mask::from_pixmap(cutout.as_ref(), MaskType::Alpha);
+            mask.invert();
+            pixmap.apply_mask(&mask);
+        }
+
+        let mut body = self.shape(&bounds, 0.0, &self.fill);
+        if let Some(font_size) = bounds.font_size {
+            let _ = write!(
+                body,
+                r#"<text x="{}" y="{}" font-size="{}" font-family="Segoe UI, Arial, DejaVu Sans, sans-serif" font-weight="bold" text-anchor="middle" fill="{}">{}</text>"#,
+                bounds.x + bounds.width / 2.0,
+                bounds.y + bounds.height / 2.0 + font_size * 0.35,
+                font_size,
+                escape_xml(&self.color),
+                escape_xml(&self.text)
+            );
+        }
+        let overlay = render(&parse_svg(svg(&body), opt)?, None, Fit::Fill)?;
+        let paint = PixmapPaint::default();
+        pixmap.draw_pixmap(0, 0, overlay.as_ref(), &paint, Transform::identity(), None);
+        Ok(())
+    }
+}
+
+/// Renders the icon at every size with the badge composited on each of them.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn convert_svg_to_ico_with_badge<S: AsRef<[u8]>>(
+    input: S,
+    sizes: &[Size],
+    fit: Fit,
+    badge: &Badge,
+) -> io::Result<Box<[u8]>> {
+    let opt = options();
+    let tree = parse_svg(input, &opt)?;
+    let sizes: Box<[Option<Size>]> = match sizes {
+        [] => Box::new([None]),
+        _ => sizes.iter().copied().map(Some).collect(),
+    };
+    let pixmaps = sizes
+        .iter()
+        .map(|&size| {
+            let mut pixmap = render(&tree, size, fit)?;
+            badge.draw(&mut pixmap, &opt)?;
+            Ok(pixmap)
+        })
+        .collect::<io::Result<Box<[_]>>>()?;
+    encode_ico(&pixmaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiny_skia::Color;
+
+    #[test]
+    fn check_limits() {
+        assert!(Badge::default().check().is_ok());
+        for scale in [2.0, 1.01, 0.0, -0.5, f32::NAN, f32::INFINITY] {
+            let badge = Badge {
+                scale,
+                ..Default::default()
+            };
+            assert!(badge.check().is_err(), "{}", scale);
+        }
+        let badge = Badge {
+            scale: 1.0,
+            ring: 0.0,
+            ..Default::default()
+        };
+        assert!(badge.check().is_ok());
+        for ring in [-0.1, f32::NAN, f32::INFINITY] {
+            let badge = Badge {
+                ring,
+                ..Default::default()
+            };
+            assert!(badge.check().is_err(), "{}", ring);
+        }
+        assert_eq!(
+            Badge {
+                scale: 2.0,
+                ..Default::default()
+            }
+            .check()
+            .unwrap_err()
+            .to_string(),
+            "Scale must be in (0, 1]: 2"
+        );
+    }
+
+    #[test]
+    fn bounds() {
+        let dot = Badge::default().bounds(32, 32);
+        assert_eq!(
+            (dot.x, dot.y, dot.width, dot.height),
+            (16.0, 0.0, 16.0, 16.0)
+        );
+        assert_eq!(dot.font_size, None);
+
+        let badge = Badge {
+            text: "12".into(),
+            shape: Shape::Rounded,
+            anchor: Anchor::BottomLeft,
+            ..Default::default()
+        };
+        let bounds = badge.bounds(64, 32);
+        assert_eq!((bounds.x, bounds.y, bounds.height), (0.0, 16.0, 16.0));
+        assert!(bounds.width > 16.0 && bounds.width < 64.0);
+        assert!(bounds.font_size.is_some());
+
+        // A long text at full scale stays within the icon
+        let badge = Badge {
+            text: "99999".into(),
+            shape: Shape::Rounded,
+            scale: 1.0,
+            ..Default::default()
+        };
+        let bounds = badge.bounds(32, 32);
+        assert_eq!((bounds.x, bounds.width, bounds.height), (0.0, 32.0, 32.0));
+
+        // Text too small to read is dropped
+        let badge = Badge {
+            text: "1".into(),
+            ..Default::default()
+        };
+        assert_eq!(badge.bounds(16, 16).font_size, None);
+    }
+
+    #[test]
+    fn draw() {
+        let opt = Options::default();
+        let mut pixmap = Pixmap::new(32, 32).unwrap();
+        pixmap.fill(Color::BLACK);
+        Badge::default().draw(&mut pixmap, &opt).unwrap();
+        let pixel = |x, y| pixmap.pixel(x, y).unwrap();
+        // The badge, the ring cut around it and the untouched icon
+        let center = pixel(24, 8);
+        assert_eq!((center.red(), center.alpha()), (0xe5, 0xff));
+        assert!(pixel(24, 17).alpha() < 0x40);
+        assert_eq!(pixel(4, 28), Color::BLACK.premultiply().to_color_u8());
+
+        let badge = Badge {
+            scale: 2.0,
+            shape: Shape::Rounded,
+            text: "12".into(),
+            ..Default::default()
+        };
+        assert!(badge.draw(&mut pixmap, &opt).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree};
+use std::io;
+
+pub mod badge;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use resvg::{tiny_skia, usvg};
+
+pub(crate) fn invalid_input<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+    error: E,
+) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+/// Escapes text for an XML attribute value or text.
+pub fn escape_xml(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&apos;"),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// Parser options with the system fonts loaded.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn options() -> Options<'static> {
+    let mut opt: Options = Default::default();
+    opt.fontdb_mut().load_system_fonts();
+    opt
+}
+
+/// Loads a font file, the first font loaded also becomes the generic `sans-serif` family.
+pub fn add_font(opt: &mut Options, data: Vec<u8>) {
+    let fontdb = opt.fontdb_mut();
+    let first = fontdb.is_empty();
+    fontdb.load_font_data(data);
+    let family = fontdb
+        .faces()
+        .next()
+        .and_then(|face| face.families.first())
+        .map(|(family, _)| family.clone());
+    if let (true, Some(family)) = (first, family) {
+        fontdb.set_sans_serif_family(family);
+    }
+}
+
+/// Parser options with only the given font files loaded, for targets without system fonts.
+pub fn options_with_fonts<I: IntoIterator<Item = Vec<u8>>>(fonts: I) -> Options<'static> {
+    let mut opt: Options = Default::default();
+    for font in fonts {
+        add_font(&mut opt, font);
+    }
+    opt
+}
+
+pub fn parse_svg<S: AsRef<[u8]>>(input: S, opt: &Options) -> io::Result<Tree> {
+    Tree::from_data(input.as_ref(), opt).map_err(io::Error::other)
+}
+
+#[derive(Clone, Copy, Default)]
+pub enum Fit {
+    #[default]
+    Contain,
+    Cover,
+    Fill,
+}
+impl TryFrom<&str> for Fit {
+    type Error = io::Error;
+    fn try_from(fit: &str) -> Result<Self, Self::Error> {
+        Ok(match fit {
+            "contain" => Self::Contain,
+            "cover" => Self::Cover,
+            "fill" => Self::Fill,
+            _ => return Err(invalid_input(format!("Unknown fit: {}", fit))),
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+impl TryFrom<&str> for Size {
+    type Error = io::Error;
+    fn try_from(size: &str) -> Result<Self, Self::Error> {
+        let parse = |n: &str| match n.parse::<u32>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(invalid_input(format!("Invalid size: {}", size))),
+        };
+        Ok(match size.split_once('x') {
+            Some((width, height)) => Self {
+                width: parse(width)?,
+                height: parse(height)?,
+            },
+            None => {
+                let n = parse(size)?;
+                Self {
+                    width: n,
+                    height: n,
+                }
+            }
+        })
+    }
+}
+impl std::fmt::Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.width == self.height {
+            write!(f, "{}", self.width)
+        } else {
+            write!(f, "{}x{}", self.width, self.height)
+        }
+    }
+}
+impl Size {
+    /// Intrinsic size of the tree, rounded to whole pixels.
+    pub fn of(tree: &Tree) -> Self {
+        let size = tree.size().to_int_size();
+        Self {
+            width: size.width(),
+            height: size.height(),
+        }
+    }
+    pub fn parse_list(list: &str) -> io::Result<Box<[Self]>> {
+        list.split(',').map(Self::try_from).collect()
+    }
+}
+
+/// Renders the tree into a pixmap of `size`, or its intrinsic size when `None`.
+pub fn render(tree: &Tree, size: Option<Size>, fit: Fit) -> io::Result<Pixmap> {
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let size = size.unwrap_or_else(|| Size::of(tree));
+    let (sx, sy) = (size.width as f32 / width, size.height as f32 / height);
+    let transform = match fit {
+        Fit::Fill => Transform::from_scale(sx, sy),
+        Fit::Contain | Fit::Cover => {
+            let scale = match fit {
+                Fit::Contain => sx.min(sy),
+                _ => sx.max(sy),
+            };
+            let tx = (size.width as f32 - width * scale) / 2.0;
+            let ty = (size.height as f32 - height * scale) / 2.0;
+            Transform::from_row(scale, 0.0, 0.0, scale, tx, ty)
+        }
+    };
+    let mut pixmap = Pixmap::new(size.width, size.height)
+        .ok_or(io::ErrorKind::InvalidInput)
+        .map_err(io::Error::from)?;
+
+    resvg::render(tree, transform, &mut pixmap.as_mut());
+
+    Ok(pixmap)
+}
+
+/// Unpremultiplied RGBA bytes of the pixmap.
+pub fn rgba(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+pub fn encode_webp(pixmap: &Pixmap) -> io::Result<Box<[u8]>> {
+    use image_webp::{ColorType, WebPEncoder};
+    let mut buffer = Vec::new();
+    WebPEncoder::new(&mut buffer)
+        .encode(
+            &rgba(pixmap),
+            pixmap.width(),
+            pixmap.height(),
+            ColorType::Rgba8,
+        )
+        .map_err(io::Error::other)?;
+    Ok(buffer.into_boxed_slice())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn convert_svg_to_png<S: AsRef<[u8]>>(
+    input: S,
+    size: Option<Size>,
+    fit: Fit,
+) -> io::Result<Box<[u8]>> {
+    let tree = parse_svg(input, &options())?;
+    let pixmap = render(&tree, size, fit)?;
+    Ok(pixmap.encode_png()?.into_boxed_slice())
+}
+
+pub fn encode_ico(pixmaps: &[Pixmap]) -> io::Result<Box<[u8]>> {
+    let mut icon = ico::IconDir::new(ico::ResourceType::Icon);
+    for pixmap in pixmaps {
+        let image = ico::IconImage::from_rgba_data(pixmap.width(), pixmap.height(), rgba(pixmap));
+        icon.add_entry(ico::IconDirEntry::encode(&image)?);
+    }
+
+    Ok({
+        let mut buffer = Vec::new();
+        icon.write(&mut buffer)?;
+        buffer.into_boxed_slice()
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn convert_svg_to_ico<S: AsRef<[u8]>>(
+    input: S,
+    sizes: &[Size],
+    fit: Fit,
+) -> io::Result<Box<[u8]>> {
+    let tree = parse_svg(input, &options())?;
+    let pixmaps = match sizes {
+        [] => Box::new([render(&tree, None, fit)?]),
+        _ => sizes
+            .iter()
+            .map(|&size| render(&tree, Some(size), fit))
+            .collect::<io::Result<Box<[_]>>>()?,
+    };
+    encode_ico(&pixmaps)
+}
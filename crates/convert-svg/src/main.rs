@@ -1,42 +1,40 @@
+use convert_svg::badge::{convert_svg_to_ico_with_badge, Anchor, Badge, Shape};
+use convert_svg::{convert_svg_to_ico, convert_svg_to_png, Fit, Size};
+use std::collections::BTreeMap as Map;
 use std::io;
 use std::io::{Read, Write};
 
-fn convert_svg_to_png<S: AsRef<[u8]>>(input: S) -> io::Result<Box<[u8]>> {
-    use resvg::tiny_skia::{Pixmap, Transform};
-    use resvg::usvg::{Options, Tree};
-    let tree = {
-        let mut opt: Options = Default::default();
-        opt.fontdb_mut().load_system_fonts();
-        Tree::from_data(input.as_ref(), &opt).map_err(io::Error::other)?
-    };
-    let transform: Transform = Default::default();
-    let size = tree.size().to_int_size();
-    let mut pixmap = Pixmap::new(size.width(), size.height())
-        .ok_or(io::ErrorKind::InvalidInput)
-        .map_err(io::Error::from)?;
-
-    resvg::render(&tree, transform, &mut pixmap.as_mut());
+mod anim;
+mod atlas;
 
-    Ok(pixmap.encode_png()?.into_boxed_slice())
+fn invalid_input<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
 }
 
-fn convert_png_to_ico<S: AsRef<[u8]>>(input: S) -> io::Result<Box<[u8]>> {
-    let mut icon = ico::IconDir::new(ico::ResourceType::Icon);
-    icon.add_entry({
-        let image = ico::IconImage::read_png(input.as_ref())?;
-        ico::IconDirEntry::encode(&image)?
-    });
+type Flags<'a> = Map<&'a str, &'a str>;
 
-    Ok({
-        let mut buffer = Vec::new();
-        icon.write(&mut buffer)?;
-        buffer.into_boxed_slice()
-    })
+/// Splits `--name value` pairs from positional arguments.
+fn parse_flags(args: &[Box<str>]) -> io::Result<(Flags<'_>, Box<[&str]>)> {
+    let mut flags = Map::new();
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(name) = arg.strip_prefix("--") {
+            let Some(value) = iter.next() else {
+                return Err(invalid_input(format!("Missing value for --{}", name)));
+            };
+            flags.insert(name, &**value);
+        } else {
+            rest.push(&**arg);
+        }
+    }
+    Ok((flags, rest.into_boxed_slice()))
 }
 
 enum Format {
     Png,
     Ico,
+    Badge,
 }
 impl TryFrom<&str> for Format {
     type Error = io::Error;
@@ -44,39 +42,121 @@ impl TryFrom<&str> for Format {
         Ok(match format {
             "png" => Self::Png,
             "ico" => Self::Ico,
+            "badge" => Self::Badge,
             _ => return Err(io::ErrorKind::InvalidInput.into()),
         })
     }
 }
 impl Format {
-    fn convert<R: Read>(&self, mut input: R) -> io::Result<Box<[u8]>> {
+    fn convert<R: Read>(&self, mut input: R, flags: &Flags) -> io::Result<Box<[u8]>> {
         let data = {
             let mut buffer = Vec::new();
             input.read_to_end(&mut buffer)?;
             buffer.into_boxed_slice()
         };
+        let sizes = match flags.get("size") {
+            Some(list) => Size::parse_list(list)?,
+            None => Box::new([]),
+        };
+        let fit = match flags.get("fit") {
+            Some(fit) => Fit::try_from(*fit)?,
+            None => Fit::default(),
+        };
         Ok(match self {
-            Self::Png => convert_svg_to_png(data)?,
-            Self::Ico => convert_png_to_ico(convert_svg_to_png(data)?)?,
+            Self::Png => match *sizes {
+                [] => convert_svg_to_png(data, None, fit)?,
+                [size] => convert_svg_to_png(data, Some(size), fit)?,
+                _ => return Err(invalid_input("png accepts a single size")),
+            },
+            Self::Ico => convert_svg_to_ico(data, &sizes, fit)?,
+            Self::Badge => {
+                let sizes = match *sizes {
+                    [] => Size::parse_list(BADGE_SIZES)?,
+                    _ => sizes,
+                };
+                convert_svg_to_ico_with_badge(data, &sizes, fit, &parse_badge(flags)?)?
+            }
         })
     }
 }
 
+const BADGE_SIZES: &str = "16,24,32,48,256";
+
+fn parse_badge(flags: &Flags) -> io::Result<Badge> {
+    let mut badge = Badge::default();
+    if let Some(text) = flags.get("text") {
+        badge.text = text.to_string();
+    }
+    if let Some(shape) = flags.get("shape") {
+        badge.shape = Shape::try_from(*shape)?;
+    }
+    if let Some(anchor) = flags.get("anchor") {
+        badge.anchor = Anchor::try_from(*anchor)?;
+    }
+    if let Some(fill) = flags.get("fill") {
+        badge.fill = fill.to_string();
+    }
+    if let Some(color) = flags.get("color") {
+        badge.color = color.to_string();
+    }
+    if let Some(scale) = flags.get("scale") {
+        badge.scale = scale.parse().map_err(invalid_input)?;
+    }
+    if let Some(ring) = flags.get("ring") {
+        badge.ring = ring.parse().map_err(invalid_input)?;
+    }
+    badge.check()?;
+    Ok(badge)
+}
+
 fn help<D: std::fmt::Display>(arg0: D) -> ! {
-    eprintln!("Usage:\n\t$ cat <in-svg> | {} <png|ico> > <output>", arg0);
+    let size = "[--size <n|wxh>[,...]] [--fit <contain|cover|fill>]";
+    eprintln!("Usage:");
+    eprintln!("\t$ cat <in-svg> | {} <png|ico> {} > <output>", arg0, size);
+    eprintln!(
+        "\t$ cat <in-svg> | {} badge {} [--text <text>] [--shape <circle|rounded>] [--anchor <top-left|top-right|bottom-left|bottom-right>] [--fill <color>] [--color <color>] [--scale <n>] [--ring <n>] > <output>",
+        arg0, size
+    );
+    eprintln!(
+        "\t$ {} atlas {} [--padding <n>] [--format <png|webp|svg>] [--index <json>] [--css <css>] [--url <href>] [--prefix <class>] <in-svg...> > <output>",
+        arg0, size
+    );
+    eprintln!(
+        "\t$ {} anim [--size <n|wxh>] [--fit <contain|cover|fill>] [--format <apng|gif>] [--delay <ms>[,...]] [--loop <n>] <in-svg...> > <output>",
+        arg0
+    );
+    eprintln!(
+        "\t$ {} anim ... --template <in-svg> [--param <name>] <--values <v>[,...] | --range <from:to:count>> > <output>",
+        arg0
+    );
     std::process::exit(-1)
 }
 
 fn main() -> io::Result<()> {
     let args: Box<[Box<str>]> = std::env::args().map(String::into_boxed_str).collect();
-    let Some(args) = args.as_array::<2>() else {
+    if args.len() < 2 {
         help(&args[0]);
-    };
+    }
+    let (flags, rest) = parse_flags(&args[2..])?;
 
     let stdin = io::stdin().lock();
     let mut stdout = io::stdout().lock();
 
-    let result = Format::try_from(&*args[1])?.convert(stdin)?;
+    let result = match &*args[1] {
+        "atlas" => {
+            if rest.is_empty() {
+                help(&args[0]);
+            }
+            atlas::main(&flags, &rest)?
+        }
+        "anim" => anim::main(&flags, &rest)?,
+        format => {
+            let Ok(format) = Format::try_from(format) else {
+                help(&args[0]);
+            };
+            format.convert(stdin, &flags)?
+        }
+    };
     stdout.write_all(&result)?;
 
     Ok(())
@@ -0,0 +1,123 @@
+use crate::badge::{Anchor, Badge, Shape};
+use crate::tiny_skia::Pixmap;
+use crate::usvg::Options;
+use crate::{add_font, encode_ico, encode_webp, options_with_fonts, parse_svg, render, Fit, Size};
+use std::io;
+use wasm_bindgen::prelude::*;
+
+fn js_error(error: io::Error) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+fn size(width: Option<u32>, height: Option<u32>) -> Option<Size> {
+    match (width, height) {
+        (Some(width), Some(height)) => Some(Size { width, height }),
+        (Some(n), None) | (None, Some(n)) => Some(Size {
+            width: n,
+            height: n,
+        }),
+        (None, None) => None,
+    }
+}
+
+fn fit(fit: Option<String>) -> Result<Fit, JsError> {
+    match fit {
+        Some(fit) => Fit::try_from(&*fit).map_err(js_error),
+        None => Ok(Fit::default()),
+    }
+}
+
+/// SVG renderer for the WebUI, using only the fonts added from JS.
+#[wasm_bindgen]
+pub struct Renderer {
+    opt: Options<'static>,
+}
+
+impl Renderer {
+    fn render(
+        &self,
+        svg: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+        fit: Option<String>,
+    ) -> Result<Pixmap, JsError> {
+        let fit = self::fit(fit)?;
+        let tree = parse_svg(svg, &self.opt).map_err(js_error)?;
+        render(&tree, size(width, height), fit).map_err(js_error)
+    }
+}
+
+#[wasm_bindgen]
+impl Renderer {
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Renderer {
+        Renderer {
+            opt: options_with_fonts([]),
+        }
+    }
+
+    /// Adds a TTF/OTF/TTC font, the first one added becomes `sans-serif`.
+    #[wasm_bindgen(js_name = addFont)]
+    pub fn add_font(&mut self, data: Vec<u8>) {
+        add_font(&mut self.opt, data);
+    }
+
+    #[wasm_bindgen(js_name = renderPng)]
+    pub fn render_png(
+        &self,
+        svg: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+        fit: Option<String>,
+    ) -> Result<Vec<u8>, JsError> {
+        let pixmap = self.render(svg, width, height, fit)?;
+        pixmap.encode_png().map_err(|err| js_error(err.into()))
+    }
+
+    #[wasm_bindgen(js_name = renderWebp)]
+    pub fn render_webp(
+        &self,
+        svg: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+        fit: Option<String>,
+    ) -> Result<Vec<u8>, JsError> {
+        let pixmap = self.render(svg, width, height, fit)?;
+        Ok(encode_webp(&pixmap).map_err(js_error)?.into_vec())
+    }
+
+    /// Renders an ICO with one square image per size, with an optional badge text.
+    #[wasm_bindgen(js_name = renderIco)]
+    pub fn render_ico(
+        &self,
+        svg: &str,
+        sizes: Vec<u32>,
+        fit: Option<String>,
+        badge: Option<String>,
+    ) -> Result<Vec<u8>, JsError> {
+        if sizes.is_empty() {
+            return Err(JsError::new("renderIco needs at least one size"));
+        }
+        let fit = self::fit(fit)?;
+        let tree = parse_svg(svg, &self.opt).map_err(js_error)?;
+        let badge = badge.map(|text| Badge {
+            text,
+            shape: Shape::Rounded,
+            anchor: Anchor::TopRight,
+            ..Default::default()
+        });
+        let pixmaps = sizes
+            .into_iter()
+            .map(|n| {
+                let mut pixmap = render(&tree, size(Some(n), None), fit)?;
+                if let Some(badge) = &badge {
+                    badge.draw(&mut pixmap, &self.opt)?;
+                }
+                Ok(pixmap)
+            })
+            .collect::<io::Result<Box<[_]>>>()
+            .map_err(js_error)?;
+        Ok(encode_ico(&pixmaps).map_err(js_error)?.into_vec())
+    }
+}
@@ -0,0 +1,81 @@
+//! The JS API, run under Node by `wasm-bindgen-test-runner`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use convert_svg::wasm::Renderer;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// Tuffy, a public domain font.
+const FONT: &[u8] = include_bytes!("fonts/Tuffy.ttf");
+
+const SQUARE: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 20">
+<rect width="10" height="20" fill="#fb7299"/></svg>"##;
+const TEXT: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 64 32">
+<text x="4" y="24" font-family="sans-serif" font-size="24">Ab</text></svg>"##;
+
+fn png_size(png: &[u8]) -> (u32, u32) {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let field = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+    (field(16), field(20))
+}
+
+/// The width and height of each image in the ICO directory, where 0 stands for 256.
+fn ico_sizes(ico: &[u8]) -> Vec<(u32, u32)> {
+    assert_eq!(&ico[..4], &[0, 0, 1, 0]);
+    let count = u16::from_le_bytes([ico[4], ico[5]]) as usize;
+    let size = |n: u8| if n == 0 { 256 } else { n as u32 };
+    (0..count)
+        .map(|i| (size(ico[6 + i * 16]), size(ico[7 + i * 16])))
+        .collect()
+}
+
+/// Whether any pixel of the PNG differs from the first one.
+fn has_ink(png: &[u8]) -> bool {
+    let mut reader = png::Decoder::new(png).read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut data).unwrap();
+    data.chunks(4).any(|pixel| pixel != &data[..4])
+}
+
+#[wasm_bindgen_test]
+fn render_png() {
+    let renderer = Renderer::new();
+    let png = renderer.render_png(SQUARE, None, None, None).unwrap();
+    assert_eq!(png_size(&png), (10, 20));
+    let png = renderer.render_png(SQUARE, Some(32), None, None).unwrap();
+    assert_eq!(png_size(&png), (32, 32));
+    let png = renderer
+        .render_png(SQUARE, Some(40), Some(10), Some("cover".into()))
+        .unwrap();
+    assert_eq!(png_size(&png), (40, 10));
+
+    assert!(renderer.render_png("<svg", None, None, None).is_err());
+    assert!(renderer
+        .render_png(SQUARE, None, None, Some("stretch".into()))
+        .is_err());
+}
+
+#[wasm_bindgen_test]
+fn render_ico() {
+    let renderer = Renderer::new();
+    let ico = renderer
+        .render_ico(SQUARE, vec![16, 32, 256], None, None)
+        .unwrap();
+    assert_eq!(ico_sizes(&ico), [(16, 16), (32, 32), (256, 256)]);
+    assert!(renderer.render_ico(SQUARE, vec![], None, None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn add_font() {
+    // Without fonts there is nothing to draw the text with
+    let mut renderer = Renderer::new();
+    let png = renderer.render_png(TEXT, None, None, None).unwrap();
+    assert!(!has_ink(&png));
+
+    renderer.add_font(FONT.to_vec());
+    let png = renderer.render_png(TEXT, None, None, None).unwrap();
+    assert!(has_ink(&png));
+    let ico = renderer
+        .render_ico(TEXT, vec![32], None, Some("9".into()))
+        .unwrap();
+    assert_eq!(ico_sizes(&ico), [(32, 32)]);
+}
@@ -0,0 +1,123 @@
+use crate::lnk::{ShellLink, ShowCommand};
+use crate::registry::{Hive, Key, Registry, RegistryMut, Value};
+use json::{object, JsonValue};
+use std::io;
+use std::path::Path;
+
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+const NAME: &str = "Metadata Fetcher";
+/// Argument of the run script that starts the app minimized to the tray, without a notification.
+pub const TRAY_ARG: &str = "--tray";
+
+#[derive(Clone, Copy, Default)]
+pub enum Method {
+    /// A shortcut in the Startup folder.
+    #[default]
+    Startup,
+    /// A value under the `HKCU\...\Run` key.
+    Run,
+}
+impl TryFrom<&str> for Method {
+    type Error = io::Error;
+    fn try_from(method: &str) -> Result<Self, Self::Error> {
+        Ok(match method {
+            "startup" => Self::Startup,
+            "run" => Self::Run,
+            _ => {
+                let message = format!("Unknown autostart method: {}", method);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+        })
+    }
+}
+
+/// An active autostart mechanism, `location` is a file or a registry value.
+pub struct Entry {
+    pub method: &'static str,
+    pub location: String,
+    pub command: Option<String>,
+    pub target: Option<String>,
+}
+impl From<Entry> for JsonValue {
+    fn from(entry: Entry) -> JsonValue {
+        let exists = entry
+            .target
+            .as_deref()
+            .map(|target| Path::new(target).exists());
+        object! {
+            method: entry.method,
+            location: entry.location,
+            command: entry.command,
+            target: entry.target,
+            targetExists: exists,
+        }
+    }
+}
+
+fn startup_link(startup: &Path) -> std::path::PathBuf {
+    startup.join(format!("{}.lnk", NAME))
+}
+
+/// Enables one mechanism and removes the other, `script` starts the app in the tray.
+pub fn enable<R: RegistryMut>(
+    reg: &mut R,
+    startup: Option<&Path>,
+    method: Method,
+    script: &str,
+) -> io::Result<()> {
+    disable(reg, startup)?;
+    match method {
+        Method::Startup => {
+            let startup = startup
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No Startup folder"))?;
+            let mut link = ShellLink::new(script);
+            link.arguments = Some(TRAY_ARG.to_string());
+            link.working_dir = script
+                .rsplit_once(['\\', '/'])
+                .map(|(dir, _)| dir.to_string());
+            link.name = Some(NAME.to_string());
+            // The console of the script closes once the tray is up
+            link.show_command = ShowCommand::Minimized;
+            link.save(startup_link(startup))
+        }
+        Method::Run => {
+            let command = format!("cmd.exe /c start \"\" /min \"{}\" {}", script, TRAY_ARG);
+            reg.set_value(Hive::CurrentUser, RUN_KEY, NAME, Value::String(command))
+        }
+    }
+}
+
+pub fn disable<R: RegistryMut>(reg: &mut R, startup: Option<&Path>) -> io::Result<()> {
+    if let Some(startup) = startup {
+        match std::fs::remove_file(startup_link(startup)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    reg.delete_value(Hive::CurrentUser, RUN_KEY, NAME)
+}
+
+pub fn status<R: Registry>(reg: &R, startup: Option<&Path>) -> Vec<Entry> {
+    let link = startup.map(startup_link).and_then(|path| {
+        let link = ShellLink::load(&path).ok()?;
+        Some(Entry {
+            method: "startup",
+            location: path.to_string_lossy().into_owned(),
+            command: None,
+            target: link.target(),
+        })
+    });
+    let run = reg
+        .open(Hive::CurrentUser, RUN_KEY)
+        .and_then(|key| key.get_value::<String>(NAME))
+        .ok()
+        .map(|command| Entry {
+            method: "run",
+            location: format!(r"HKEY_CURRENT_USER\{}\{}", RUN_KEY, NAME),
+            target: shell_words::split(&command)
+                .ok()
+                .and_then(|words| words.into_iter().rfind(|word| word != TRAY_ARG)),
+            command: Some(command),
+        });
+    link.into_iter().chain(run).collect()
+}
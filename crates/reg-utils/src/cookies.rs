@@ -0,0 +1,133 @@
+use crate::sqlite;
+use json::{object, JsonValue};
+use rusqlite::Connection;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Default)]
+pub enum Format {
+    #[default]
+    Json,
+    /// The `cookies.txt` format read by curl, wget and yt-dlp.
+    Netscape,
+}
+impl TryFrom<&str> for Format {
+    type Error = io::Error;
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        Ok(match format {
+            "json" => Self::Json,
+            "netscape" => Self::Netscape,
+            _ => {
+                let message = format!("Unknown cookie format: {}", format);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+        })
+    }
+}
+
+pub struct Cookie {
+    /// The domain, with a leading `.` when subdomains get the cookie too.
+    pub host: String,
+    pub name: String,
+    pub value: String,
+    pub path: String,
+    /// Seconds since the Unix epoch.
+    pub expiry: i64,
+    pub secure: bool,
+    pub http_only: bool,
+}
+impl From<&Cookie> for JsonValue {
+    fn from(cookie: &Cookie) -> JsonValue {
+        object! {
+            domain: cookie.host.as_str(),
+            name: cookie.name.as_str(),
+            value: cookie.value.as_str(),
+            path: cookie.path.as_str(),
+            expires: cookie.expiry,
+            secure: cookie.secure,
+            httpOnly: cookie.http_only,
+        }
+    }
+}
+impl Cookie {
+    /// Whether the browser sends the cookie to `domain`.
+    pub fn matches(&self, domain: &str) -> bool {
+        match self.host.strip_prefix('.') {
+            Some(host) => {
+                domain == host
+                    || domain
+                        .strip_suffix(host)
+                        .is_some_and(|rest| rest.ends_with('.'))
+            }
+            None => domain == self.host,
+        }
+    }
+}
+
+/// Formats cookies as a Netscape cookie file, marking HttpOnly ones the way curl does.
+pub fn to_netscape(cookies: &[Cookie]) -> String {
+    let mut text = "# Netscape HTTP Cookie File\n".to_string();
+    for cookie in cookies {
+        let flag = |value: bool| if value { "TRUE" } else { "FALSE" };
+        let prefix = if cookie.http_only { "#HttpOnly_" } else { "" };
+        text.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            prefix,
+            cookie.host,
+            flag(cookie.host.starts_with('.')),
+            cookie.path,
+            flag(cookie.secure),
+            cookie.expiry,
+            cookie.name,
+            cookie.value,
+        ));
+    }
+    text
+}
+
+/// Reads the unexpired cookies of the default container from a `cookies.sqlite` database.
+fn query(db: &Connection) -> rusqlite::Result<Vec<Cookie>> {
+    let mut statement = db.prepare(
+        "SELECT host, name, value, path, expiry, isSecure, isHttpOnly FROM moz_cookies
+         WHERE originAttributes = '' ORDER BY host, path, name",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok(Cookie {
+            host: row.get(0)?,
+            name: row.get(1)?,
+            value: row.get(2)?,
+            path: row.get(3)?,
+            expiry: row.get(4)?,
+            secure: row.get(5)?,
+            http_only: row.get(6)?,
+        })
+    })?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64);
+    let mut cookies = Vec::new();
+    for cookie in rows {
+        let mut cookie = cookie?;
+        // Newer versions store milliseconds
+        if cookie.expiry > 100_000_000_000 {
+            cookie.expiry /= 1000;
+        }
+        if cookie.expiry > now {
+            cookies.push(cookie);
+        }
+    }
+    Ok(cookies)
+}
+
+/// Reads the cookies Firefox sends to `domain` from the profile directory `profile`.
+///
+/// The database is read from a copy, so this works while Firefox is running.
+pub fn read(profile: &Path, domain: &str) -> io::Result<Vec<Cookie>> {
+    let cookies = sqlite::read_copy(profile, "cookies.sqlite", query)?;
+    let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+    Ok(cookies
+        .into_iter()
+        .filter(|cookie| cookie.matches(&domain))
+        .collect())
+}
@@ -0,0 +1,66 @@
+use crate::registry::{Hive, Key, Registry, RegistryMut, Value};
+use json::JsonValue;
+use std::io;
+
+/// Name of the verb key under each `shell` key.
+const VERB: &str = "MetadataFetcher";
+
+/// Where the verb is added: folders, files perceived as video, and Internet Shortcuts.
+const TARGETS: &[(&str, &str)] = &[
+    ("directory", r"Directory\shell"),
+    ("video", r"SystemFileAssociations\video\shell"),
+    ("url", r"InternetShortcut\shell"),
+];
+
+pub struct Verb {
+    pub label: String,
+    /// Command line run for each selected item, `%1` is replaced with its path.
+    pub command: String,
+    pub icon: Option<String>,
+}
+
+fn verb_path(shell: &str) -> String {
+    format!(r"Software\Classes\{}\{}", shell, VERB)
+}
+
+pub fn install<R: RegistryMut>(reg: &mut R, verb: &Verb) -> io::Result<()> {
+    for (_, shell) in TARGETS {
+        let path = verb_path(shell);
+        let string = |value: &str| Value::String(value.to_string());
+        reg.delete_tree(Hive::CurrentUser, &path)?;
+        reg.set_value(Hive::CurrentUser, &path, "", string(&verb.label))?;
+        // Lifts the limit of 15 selected items
+        reg.set_value(
+            Hive::CurrentUser,
+            &path,
+            "MultiSelectModel",
+            string("Player"),
+        )?;
+        if let Some(icon) = &verb.icon {
+            reg.set_value(Hive::CurrentUser, &path, "Icon", string(icon))?;
+        }
+        let path = format!(r"{}\command", path);
+        reg.set_value(Hive::CurrentUser, &path, "", string(&verb.command))?;
+    }
+    Ok(())
+}
+
+pub fn uninstall<R: RegistryMut>(reg: &mut R) -> io::Result<()> {
+    TARGETS
+        .iter()
+        .try_for_each(|(_, shell)| reg.delete_tree(Hive::CurrentUser, &verb_path(shell)))
+}
+
+/// The command of the verb for each kind of item, `null` where it is not installed.
+pub fn status<R: Registry>(reg: &R) -> JsonValue {
+    let mut json = JsonValue::new_object();
+    for (name, shell) in TARGETS {
+        let path = format!(r"{}\command", verb_path(shell));
+        let command: Option<String> = reg
+            .open(Hive::CurrentUser, &path)
+            .and_then(|key| key.get_value(""))
+            .ok();
+        json[*name] = command.into();
+    }
+    json
+}
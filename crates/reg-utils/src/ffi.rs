@@ -0,0 +1,151 @@
+//! C ABI for loading the library in process, e.g. with `Deno.dlopen` or `bun:ffi`.
+//!
+//! Strings are passed in as UTF-8 pointer and length pairs, like the tray library takes them.
+//! Every function returns the JSON envelope `reg-utils` prints, as a NUL-terminated UTF-8
+//! buffer which the caller releases with [`reg_utils_free`].
+
+use crate::output::{self, Error};
+use json::JsonValue;
+use std::ffi::{c_char, CString};
+
+fn to_buffer(result: Result<JsonValue, Error>) -> *mut c_char {
+    // JSON escapes NUL characters, so the text never contains one
+    let text = output::envelope(&result).dump();
+    CString::new(text).unwrap_or_default().into_raw()
+}
+
+/// # Safety
+///
+/// `ptr` must point to `len` readable bytes, or be null with `len` 0.
+unsafe fn from_raw_parts<'a>(ptr: *const u8, len: usize) -> Result<&'a str, Error> {
+    if ptr.is_null() {
+        return Ok("");
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    std::str::from_utf8(bytes).map_err(|err| Error::bad_arguments(err.to_string()))
+}
+
+fn run(command: &str, params: Vec<String>) -> *mut c_char {
+    let mut args = vec!["reg-utils".to_string(), command.to_string()];
+    args.extend(params);
+    to_buffer(crate::run(&mut args))
+}
+
+/// Runs any command, `args_ptr` holds a JSON array of its arguments, e.g. `["Desktop"]` for
+/// `known-folder`.
+///
+/// # Safety
+///
+/// `args_ptr` must point to `args_len` readable bytes and `command_ptr` to `command_len`.
+#[no_mangle]
+pub unsafe extern "C" fn reg_utils_run(
+    command_ptr: *const u8,
+    command_len: usize,
+    args_ptr: *const u8,
+    args_len: usize,
+) -> *mut c_char {
+    let parsed = unsafe { from_raw_parts(command_ptr, command_len) }.and_then(|command| {
+        let args = unsafe { from_raw_parts(args_ptr, args_len) }?;
+        let args = match args.trim() {
+            "" => JsonValue::new_array(),
+            args => json::parse(args).map_err(|err| Error::bad_arguments(err.to_string()))?,
+        };
+        let args: Option<Vec<String>> = args
+            .members()
+            .map(|arg| arg.as_str().map(str::to_string))
+            .collect();
+        let args = args.ok_or_else(|| Error::bad_arguments("Arguments must be strings"))?;
+        Ok((command, args))
+    });
+    match parsed {
+        Ok((command, args)) => run(command, args),
+        Err(err) => to_buffer(Err(err)),
+    }
+}
+
+/// The registered browsers keyed by ProgID, with the default one under `$default`.
+#[no_mangle]
+pub extern "C" fn reg_utils_browsers() -> *mut c_char {
+    run("browser", Vec::new())
+}
+
+/// The command line of the default browser.
+#[no_mangle]
+pub extern "C" fn reg_utils_default_browser() -> *mut c_char {
+    run("default", Vec::new())
+}
+
+/// Installed software, `filter_ptr` holds `{ name?, publisher?, all? }` as for `software`.
+///
+/// # Safety
+///
+/// `filter_ptr` must point to `filter_len` readable bytes, or be null with `filter_len` 0.
+#[no_mangle]
+pub unsafe extern "C" fn reg_utils_software(
+    filter_ptr: *const u8,
+    filter_len: usize,
+) -> *mut c_char {
+    let filter =
+        unsafe { from_raw_parts(filter_ptr, filter_len) }.and_then(|filter| match filter.trim() {
+            "" => Ok(JsonValue::new_object()),
+            filter => json::parse(filter).map_err(|err| Error::bad_arguments(err.to_string())),
+        });
+    let filter = match filter {
+        Ok(filter) => filter,
+        Err(err) => return to_buffer(Err(err)),
+    };
+    let mut args = Vec::new();
+    for key in ["name", "publisher"] {
+        if let Some(pattern) = filter[key].as_str() {
+            args.extend([format!("--{}", key), pattern.to_string()]);
+        }
+    }
+    if filter["all"].as_bool() == Some(true) {
+        args.push("--all".to_string());
+    }
+    run("software", args)
+}
+
+/// The path of a known folder, e.g. `Desktop`.
+///
+/// # Safety
+///
+/// `name_ptr` must point to `name_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn reg_utils_known_folder(
+    name_ptr: *const u8,
+    name_len: usize,
+) -> *mut c_char {
+    match unsafe { from_raw_parts(name_ptr, name_len) } {
+        Ok(name) => run("known-folder", vec![name.to_string()]),
+        Err(err) => to_buffer(Err(err)),
+    }
+}
+
+/// Writes a `.lnk` file, `options_ptr` holds the JSON options of the `shortcut` command.
+///
+/// # Safety
+///
+/// `options_ptr` must point to `options_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn reg_utils_create_shortcut(
+    options_ptr: *const u8,
+    options_len: usize,
+) -> *mut c_char {
+    match unsafe { from_raw_parts(options_ptr, options_len) } {
+        Ok(options) => run("shortcut", vec![options.to_string()]),
+        Err(err) => to_buffer(Err(err)),
+    }
+}
+
+/// Releases a buffer returned by the functions above.
+///
+/// # Safety
+///
+/// `ptr` must come from one of these functions and not be used afterwards, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn reg_utils_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{profile, sqlite, BrowserInfo};
+use json::{object, JsonValue};
+use regex::Regex;
+use rusqlite::Connection;
+use std::collections::BTreeMap as Map;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// URLs of the content pages of the supported sites, by the site names `--site` takes.
+pub const SITES: &[(&str, &str)] = &[
+    (
+        "bilibili",
+        r"^https?://((www|m)\.)?bilibili\.com/(video|bangumi/play)/|^https?://b23\.tv/",
+    ),
+    (
+        "youtube",
+        r"^https?://((www|m|music)\.)?youtube\.com/(watch\?|shorts/|live/)|^https?://youtu\.be/",
+    ),
+    (
+        "niconico",
+        r"^https?://((www|sp)\.)?nicovideo\.jp/watch/|^https?://nico\.ms/",
+    ),
+    ("pixiv", r"^https?://(www\.)?pixiv\.net/(en/)?artworks/"),
+];
+
+/// Seconds between 1601-01-01, where Chromium timestamps start, and the Unix epoch.
+const WINDOWS_EPOCH: i64 = 11_644_473_600;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Source {
+    History,
+    Bookmark,
+}
+impl Source {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::History => "history",
+            Self::Bookmark => "bookmark",
+        }
+    }
+}
+
+pub struct Item {
+    pub url: String,
+    pub title: Option<String>,
+    /// Seconds since the Unix epoch of the last visit or of adding the bookmark.
+    pub time: i64,
+    pub source: Source,
+}
+impl From<&Item> for JsonValue {
+    fn from(item: &Item) -> JsonValue {
+        object! {
+            url: item.url.as_str(),
+            title: item.title.as_deref(),
+            time: item.time,
+            source: item.source.as_str(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Filter {
+    /// Seconds since the Unix epoch, inclusive.
+    pub since: Option<i64>,
+    /// Seconds since the Unix epoch, exclusive.
+    pub until: Option<i64>,
+    /// Any of them matches, none matches everything.
+    pub patterns: Vec<Regex>,
+    /// Only items from this source.
+    pub source: Option<Source>,
+}
+impl Filter {
+    pub fn matches(&self, item: &Item) -> bool {
+        self.since.is_none_or(|since| item.time >= since)
+            && self.until.is_none_or(|until| item.time < until)
+            && self.source.is_none_or(|source| item.source == source)
+            && (self.patterns.is_empty() || self.patterns.iter().any(|re| re.is_match(&item.url)))
+    }
+}
+
+/// The pattern of a site in [`SITES`].
+pub fn site_pattern(site: &str) -> io::Result<Regex> {
+    let pattern = SITES
+        .iter()
+        .find(|(name, _)| *name == site)
+        .map(|(_, re)| re);
+    match pattern {
+        Some(pattern) => Regex::new(pattern).map_err(io::Error::other),
+        None => {
+            let message = format!("Unknown site: {}", site);
+            Err(io::Error::new(io::ErrorKind::InvalidInput, message))
+        }
+    }
+}
+
+/// Days since the Unix epoch of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Parses a time given as Unix seconds, a UTC date `2024-05-01`, or an age before `now` such as
+/// `7d`, `12h`, `30m` or `2w`.
+pub fn parse_time(text: &str, now: i64) -> io::Result<i64> {
+    let invalid = || {
+        let message = format!("Invalid time: {}", text);
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    };
+    if let Ok(seconds) = text.parse::<i64>() {
+        return Ok(seconds);
+    }
+    let unit = match text.chars().last() {
+        Some('s') => Some(1),
+        Some('m') => Some(60),
+        Some('h') => Some(3600),
+        Some('d') => Some(86_400),
+        Some('w') => Some(604_800),
+        _ => None,
+    };
+    if let Some(unit) = unit {
+        let count: i64 = text[..text.len() - 1].parse().map_err(|_| invalid())?;
+        return count
+            .checked_mul(unit)
+            .and_then(|age| now.checked_sub(age))
+            .ok_or_else(invalid);
+    }
+    let parts: Vec<i64> = text
+        .split('-')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<io::Result<_>>()?;
+    match parts[..] {
+        [year @ 1..=9999, month @ 1..=12, day @ 1..=31] => {
+            Ok(days_from_civil(year, month, day) * 86_400)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn chromium_time(microseconds: i64) -> i64 {
+    microseconds / 1_000_000 - WINDOWS_EPOCH
+}
+
+fn chromium_bookmarks(node: &JsonValue, items: &mut Vec<Item>) {
+    match node["type"].as_str() {
+        Some("url") => {
+            let time = node["date_added"]
+                .as_str()
+                .and_then(|time| time.parse().ok());
+            if let (Some(url), Some(time)) = (node["url"].as_str(), time) {
+                items.push(Item {
+                    url: url.to_string(),
+                    title: node["name"].as_str().map(str::to_string),
+                    time: chromium_time(time),
+                    source: Source::Bookmark,
+                });
+            }
+        }
+        _ => {
+            for child in node["children"].members() {
+                chromium_bookmarks(child, items);
+            }
+        }
+    }
+}
+
+/// Reads `History` and `Bookmarks` of a Chromium profile directory, skipping missing ones.
+pub fn chromium(profile: &Path) -> io::Result<Vec<Item>> {
+    let history = sqlite::read_copy(profile, "History", |db: &Connection| {
+        let mut statement = db.prepare(
+            "SELECT url, title, last_visit_time FROM urls WHERE last_visit_time > 0
+             ORDER BY last_visit_time",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok(Item {
+                url: row.get(0)?,
+                title: row
+                    .get::<_, Option<String>>(1)?
+                    .filter(|title| !title.is_empty()),
+                time: chromium_time(row.get(2)?),
+                source: Source::History,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<Item>>>()
+    });
+    let mut items = match history {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        history => history?,
+    };
+    match fs::read_to_string(profile.join("Bookmarks")) {
+        Ok(text) => {
+            let bookmarks = json::parse(&text)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            for (_, root) in bookmarks["roots"].entries() {
+                chromium_bookmarks(root, &mut items);
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    Ok(items)
+}
+
+/// Reads the history and bookmarks in `places.sqlite` of a Firefox profile directory.
+pub fn firefox(profile: &Path) -> io::Result<Vec<Item>> {
+    sqlite::read_copy(profile, "places.sqlite", |db: &Connection| {
+        let mut items = Vec::new();
+        // Times are microseconds since the Unix epoch, and bookmarks of type 1 are URLs
+        let queries = [
+            (
+                "SELECT url, title, last_visit_date FROM moz_places WHERE last_visit_date > 0
+                 ORDER BY last_visit_date",
+                Source::History,
+            ),
+            (
+                "SELECT p.url, b.title, b.dateAdded FROM moz_bookmarks b
+                 JOIN moz_places p ON b.fk = p.id WHERE b.type = 1 ORDER BY b.dateAdded",
+                Source::Bookmark,
+            ),
+        ];
+        for (sql, source) in queries {
+            let mut statement = db.prepare(sql)?;
+            let rows = statement.query_map([], |row| {
+                Ok(Item {
+                    url: row.get(0)?,
+                    title: row
+                        .get::<_, Option<String>>(1)?
+                        .filter(|title| !title.is_empty()),
+                    time: row.get::<_, i64>(2)? / 1_000_000,
+                    source,
+                })
+            })?;
+            for item in rows {
+                items.push(item?);
+            }
+        }
+        Ok(items)
+    })
+}
+
+/// Reads a Firefox profile directory when it has `places.sqlite`, a Chromium one otherwise.
+pub fn read(profile: &Path) -> io::Result<Vec<Item>> {
+    match profile.join("places.sqlite").is_file() {
+        true => firefox(profile),
+        false => chromium(profile),
+    }
+}
+
+/// A profile directory to read, with the browser and profile it was found for.
+pub struct Location {
+    pub browser: Option<String>,
+    pub profile: Option<String>,
+    pub path: PathBuf,
+}
+impl Location {
+    pub fn new(browser: Option<String>, profile: Option<String>, path: PathBuf) -> Self {
+        Self {
+            browser,
+            profile,
+            path,
+        }
+    }
+}
+
+/// The profiles of the installed browsers, only of `browser_id` and matching `profile_name`
+/// when they are given.
+pub fn profiles(
+    browsers: Map<String, BrowserInfo>,
+    browser_id: Option<&str>,
+    profile_name: Option<&str>,
+) -> Vec<Location> {
+    let mut locations: Vec<Location> = Vec::new();
+    for (id, info) in browsers {
+        if browser_id.is_some_and(|browser_id| browser_id != id) {
+            continue;
+        }
+        let words = info.words.unwrap_or_default();
+        for profile in profile::find(&id, &words) {
+            let selected = profile_name.is_none_or(|name| profile.matches(name));
+            // Several ProgIDs of one browser share its profiles
+            if selected
+                && locations
+                    .iter()
+                    .all(|location| location.path != profile.path)
+            {
+                let location = Location::new(Some(id.clone()), Some(profile.name), profile.path);
+                locations.push(location);
+            }
+        }
+    }
+    locations
+}
+
+/// The latest visit or bookmark of every URL matching `filter`, newest first.
+///
+/// Profiles that cannot be read are skipped, or fail the whole call when `strict`.
+pub fn collect(
+    filter: &Filter,
+    locations: &[Location],
+    strict: bool,
+) -> io::Result<Vec<JsonValue>> {
+    let mut found = Vec::new();
+    for location in locations {
+        match read(&location.path) {
+            Ok(items) => found.push((location, items)),
+            Err(err) if strict => return Err(err),
+            Err(_) => {}
+        }
+    }
+    Ok(latest(filter, found))
+}
+
+fn latest<'a>(
+    filter: &Filter,
+    found: impl IntoIterator<Item = (&'a Location, Vec<Item>)>,
+) -> Vec<JsonValue> {
+    let mut latest: Map<String, (i64, JsonValue)> = Map::new();
+    for (location, items) in found {
+        for item in items.iter().filter(|item| filter.matches(item)) {
+            if latest
+                .get(&item.url)
+                .is_some_and(|(time, _)| *time >= item.time)
+            {
+                continue;
+            }
+            let mut json = JsonValue::from(item);
+            json["browser"] = location.browser.clone().into();
+            json["profile"] = location.profile.clone().into();
+            latest.insert(item.url.clone(), (item.time, json));
+        }
+    }
+    let mut items: Vec<(i64, JsonValue)> = latest.into_values().collect();
+    items.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    items.into_iter().map(|(_, json)| json).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(url: &str, time: i64, source: Source) -> Item {
+        Item {
+            url: url.to_string(),
+            title: None,
+            time,
+            source,
+        }
+    }
+
+    fn location(browser: &str) -> Location {
+        Location::new(Some(browser.to_string()), None, PathBuf::new())
+    }
+
+    #[test]
+    fn latest_per_url() {
+        let (chrome, firefox) = (location("chrome"), location("firefox"));
+        let found = [
+            (
+                &chrome,
+                vec![
+                    item("https://a/", 10, Source::History),
+                    item("https://b/", 30, Source::History),
+                    item("https://c/", 20, Source::Bookmark),
+                ],
+            ),
+            (
+                &firefox,
+                vec![
+                    item("https://a/", 40, Source::Bookmark),
+                    item("https://b/", 30, Source::Bookmark),
+                    item("https://c/", 5, Source::History),
+                ],
+            ),
+        ];
+        let items = latest(&Filter::default(), found);
+        let summary: Vec<(&str, i64, &str, &str)> = items
+            .iter()
+            .map(|json| {
+                let field = |key: &str| json[key].as_str().unwrap();
+                (
+                    field("url"),
+                    json["time"].as_i64().unwrap(),
+                    field("source"),
+                    field("browser"),
+                )
+            })
+            .collect();
+        // Newest first, the first profile keeps a URL seen at the same time
+        assert_eq!(
+            summary,
+            [
+                ("https://a/", 40, "bookmark", "firefox"),
+                ("https://b/", 30, "history", "chrome"),
+                ("https://c/", 20, "bookmark", "chrome"),
+            ]
+        );
+        assert_eq!(items[0]["profile"], JsonValue::Null);
+    }
+
+    #[test]
+    fn latest_after_filter() {
+        let chrome = location("chrome");
+        let items = vec![
+            item("https://a/", 10, Source::History),
+            item("https://a/", 50, Source::Bookmark),
+            item("https://b/", 20, Source::History),
+        ];
+        // A newer item filtered out does not hide an older one that matches
+        let filter = Filter {
+            until: Some(30),
+            ..Default::default()
+        };
+        let items = latest(&filter, [(&chrome, items)]);
+        let urls: Vec<(&str, i64)> = items
+            .iter()
+            .map(|json| {
+                (
+                    json["url"].as_str().unwrap(),
+                    json["time"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(urls, [("https://b/", 20), ("https://a/", 10)]);
+    }
+
+    #[test]
+    fn collect_strict() {
+        let dir = std::env::temp_dir().join(format!("reg-utils-collect-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Bookmarks"), "{").unwrap();
+        let broken = [Location::new(None, None, dir.clone())];
+        // Found profiles that cannot be read are skipped, explicit ones fail
+        assert!(collect(&Filter::default(), &broken, false)
+            .unwrap()
+            .is_empty());
+        let err = collect(&Filter::default(), &broken, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,218 @@
+use crate::output::Error;
+use crate::BrowserInfo;
+use json::{object, JsonValue};
+use std::io;
+use std::process::Command;
+
+/// Words standing for the URL: registry placeholders and the field codes of desktop entries.
+const PLACEHOLDERS: &[&str] = &["%1", "%L", "%l", "%*", "%u", "%U", "%f", "%F"];
+
+/// Words that only make sense right before the URL, dropped when other options are added.
+const URL_MARKERS: &[&str] = &["--single-argument", "-osint", "-url"];
+
+/// The Chromium switch selecting a profile, which Edge commands often carry already.
+pub(crate) const PROFILE_DIRECTORY: &str = "--profile-directory=";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Family {
+    Chromium,
+    Edge,
+    Firefox,
+    Other,
+}
+/// The ID and the base names of the words before the URL, lowercased for keyword matching.
+pub(crate) fn program_names(id: &str, words: &[String]) -> Vec<String> {
+    std::iter::once(id)
+        .chain(
+            words
+                .iter()
+                .take_while(|word| !is_placeholder(word))
+                .map(String::as_str),
+        )
+        .map(|word| {
+            let name = word.rsplit(['/', '\\']).next().unwrap_or(word);
+            name.to_ascii_lowercase()
+        })
+        .collect()
+}
+
+impl Family {
+    /// Guesses the family from the ProgID or desktop file ID and the program names in the command.
+    pub fn classify(id: &str, words: &[String]) -> Self {
+        let names = program_names(id, words);
+        let any = |keys: &[&str]| {
+            names
+                .iter()
+                .any(|name| keys.iter().any(|key| name.contains(key)))
+        };
+        if any(&["msedge", "microsoft-edge", "com.microsoft.edge"]) {
+            Self::Edge
+        } else if any(&["firefox", "mozilla", "librewolf", "waterfox", "floorp"]) {
+            Self::Firefox
+        } else if any(&["chrome", "chromium", "brave", "vivaldi", "opera", "thorium"]) {
+            Self::Chromium
+        } else {
+            Self::Other
+        }
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Chromium => "chromium",
+            Self::Edge => "edge",
+            Self::Firefox => "firefox",
+            Self::Other => "other",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct OpenOptions<'a> {
+    /// A standalone window without tabs or toolbars, where the browser has one.
+    pub app_window: bool,
+    pub new_window: bool,
+    pub private: bool,
+    /// The arguments selecting a profile, see [`crate::profile::Profile::args`].
+    pub profile: &'a [String],
+}
+
+fn is_placeholder(word: &str) -> bool {
+    PLACEHOLDERS.contains(&word)
+}
+
+/// The arguments to put where the URL goes, for the options the family supports.
+fn url_args(family: Family, url: &str, options: &OpenOptions) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    let mut flag = |flag: &str| args.push(flag.to_string());
+    match family {
+        Family::Chromium | Family::Edge => {
+            if options.private {
+                flag(match family {
+                    Family::Edge => "--inprivate",
+                    _ => "--incognito",
+                });
+            }
+            if options.app_window {
+                args.push(format!("--app={}", url));
+                return args;
+            }
+            if options.new_window {
+                flag("--new-window");
+            }
+            // The rest of the command line is the URL, whatever it starts with
+            flag("--single-argument");
+        }
+        // Firefox has no app mode, a new window comes closest
+        Family::Firefox if options.private => flag("-private-window"),
+        Family::Firefox if options.app_window || options.new_window => flag("-new-window"),
+        Family::Firefox => flag("-url"),
+        Family::Other => {}
+    }
+    args.push(url.to_string());
+    args
+}
+
+/// Builds the command line opening `url` from a browser's words.
+///
+/// Without options the command keeps its registered form with the placeholder replaced, or the
+/// URL appended when there is none. With options the profile arguments and the family's flags
+/// are put in its place.
+pub fn build_args(
+    words: &[String],
+    family: Family,
+    url: &str,
+    options: &OpenOptions,
+) -> Vec<String> {
+    let plain = !(options.app_window || options.new_window || options.private)
+        && options.profile.is_empty();
+    let position = words.iter().position(|word| is_placeholder(word));
+    if plain || family == Family::Other {
+        let mut args: Vec<String> = words
+            .iter()
+            .map(|word| match is_placeholder(word) {
+                true => url.to_string(),
+                false => word.replace("%1", url),
+            })
+            .collect();
+        let substituted = words
+            .iter()
+            .any(|word| word.contains("%1") || is_placeholder(word));
+        if !substituted {
+            args.push(url.to_string());
+        }
+        return args;
+    }
+    let (before, after) = match position {
+        Some(i) => (&words[..i], &words[i + 1..]),
+        None => (words, &[][..]),
+    };
+    let mut args: Vec<String> = before
+        .iter()
+        .filter(|word| !URL_MARKERS.contains(&word.as_str()))
+        // A registered profile gives way to the chosen one
+        .filter(|word| options.profile.is_empty() || !word.starts_with(PROFILE_DIRECTORY))
+        .cloned()
+        .collect();
+    args.extend_from_slice(options.profile);
+    args.extend(url_args(family, url, options));
+    args.extend(after.iter().filter(|word| !is_placeholder(word)).cloned());
+    args
+}
+
+/// Where the URL goes when no browser is known.
+fn fallback_words() -> Vec<String> {
+    let program = if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    vec![program.to_string(), "%1".to_string()]
+}
+
+/// A URL the browser cannot mistake for an option.
+pub fn check_url(url: &str) -> Result<(), Error> {
+    let scheme = url.split_once(':').map(|(scheme, _)| scheme);
+    match scheme {
+        Some(scheme) if crate::protocol::check_scheme(scheme).is_ok() => Ok(()),
+        _ => Err(Error::bad_arguments(format!(
+            "Not an absolute URL: {}",
+            url
+        ))),
+    }
+}
+
+/// Opens `url` with `browser`, or the system handler when it is `None`, unless `dry_run`.
+pub fn open(
+    url: &str,
+    browser: Option<(&str, &BrowserInfo)>,
+    options: &OpenOptions,
+    dry_run: bool,
+) -> Result<JsonValue, Error> {
+    check_url(url)?;
+    let words = browser
+        .and_then(|(_, info)| info.words.clone())
+        .filter(|words| !words.is_empty())
+        .unwrap_or_else(fallback_words);
+    let id = browser.map(|(id, _)| id);
+    let family = Family::classify(id.unwrap_or_default(), &words);
+    let args = build_args(&words, family, url, options);
+    let pid = match dry_run {
+        true => None,
+        false => {
+            let (program, rest) = args
+                .split_first()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+            let mut child = Command::new(program).args(rest).spawn()?;
+            let pid = child.id();
+            // Reaps the launcher, which browsers often exit right after handing the URL over
+            std::thread::spawn(move || child.wait());
+            Some(pid)
+        }
+    };
+    Ok(object! {
+        browser: id,
+        name: browser.map(|(_, info)| info.name.as_str()),
+        family: family.as_str(),
+        args: args,
+        pid: pid,
+    })
+}
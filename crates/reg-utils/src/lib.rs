@@ -0,0 +1,629 @@
+//! Registry, shell and desktop integration queries behind the `reg-utils` command line.
+//!
+//! [`run`] answers any command with the `data` of its JSON envelope, the functions below cover
+//! the common queries directly and [`ffi`] exposes them through a C ABI.
+
+#[cfg(windows)]
+#[macro_use]
+extern crate mashup;
+use json::{object, JsonValue};
+use std::collections::BTreeMap as Map;
+use std::io;
+
+pub mod autostart;
+pub mod cookies;
+pub mod explorer;
+pub mod ffi;
+pub mod history;
+pub mod launch;
+pub mod lnk;
+pub mod locate;
+pub mod output;
+pub mod profile;
+pub mod protocol;
+pub mod proxy;
+pub mod regfile;
+pub mod registry;
+pub mod rpc;
+pub mod shortcut;
+mod sqlite;
+pub mod system;
+pub mod urlfile;
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+use windows as platform;
+#[cfg(not(windows))]
+pub mod xdg;
+#[cfg(not(windows))]
+use xdg as platform;
+
+use explorer::Verb;
+use locate::{Locator, ToolInfo};
+use output::Error;
+pub use platform::{known_folder, KNOWN_FOLDER_ID_LIST};
+use protocol::{Handler, ProtocolStatus};
+use regfile::MemoryRegistry;
+
+pub struct BrowserInfo {
+    pub name: String,
+    pub command: String,
+    /// The command split into arguments, with `%1` standing for the URL.
+    pub words: Option<Vec<String>>,
+}
+impl BrowserInfo {
+    pub fn new(name: String, command: String) -> Self {
+        let words = shell_words::split(&command).ok();
+        Self {
+            name,
+            command,
+            words,
+        }
+    }
+}
+impl From<BrowserInfo> for JsonValue {
+    fn from(info: BrowserInfo) -> JsonValue {
+        object! {
+            name: info.name,
+            command: info.command,
+            words: info.words,
+        }
+    }
+}
+
+pub struct SoftwareInfo {
+    pub name: String,
+    pub version: Option<String>,
+    pub path: Option<String>,
+    pub publisher: Option<String>,
+    /// `YYYY-MM-DD` when the registry holds a `YYYYMMDD` date, otherwise as found.
+    pub install_date: Option<String>,
+    /// In KiB.
+    pub estimated_size: Option<u32>,
+    pub uninstall_string: Option<String>,
+    pub display_icon: Option<String>,
+    /// `x64` or `x86` for machine-wide entries, unknown for per-user ones.
+    pub architecture: Option<&'static str>,
+    pub system_component: bool,
+}
+impl From<SoftwareInfo> for JsonValue {
+    fn from(info: SoftwareInfo) -> JsonValue {
+        object! {
+            name: info.name,
+            version: info.version,
+            path: info.path,
+            publisher: info.publisher,
+            installDate: info.install_date,
+            estimatedSize: info.estimated_size,
+            uninstallString: info.uninstall_string,
+            displayIcon: info.display_icon,
+            architecture: info.architecture,
+            systemComponent: info.system_component,
+        }
+    }
+}
+
+/// Which entries `software` keeps, names and publishers are matched case-insensitively.
+#[derive(Default)]
+pub struct SoftwareFilter {
+    pub name: Option<regex::Regex>,
+    pub publisher: Option<regex::Regex>,
+    /// Keep entries marked `SystemComponent`, which Windows hides from its own list.
+    pub system: bool,
+}
+impl SoftwareFilter {
+    pub fn matches(&self, info: &SoftwareInfo) -> bool {
+        let test = |regex: &Option<regex::Regex>, text: Option<&str>| match regex {
+            Some(regex) => text.is_some_and(|text| regex.is_match(text)),
+            None => true,
+        };
+        (self.system || !info.system_component)
+            && test(&self.name, Some(&info.name))
+            && test(&self.publisher, info.publisher.as_deref())
+    }
+}
+
+/// Removes `--name <value>` from the arguments and returns the value.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<Option<String>> {
+    let i = args.iter().position(|arg| arg == name)?;
+    let value = args.get(i + 1).cloned();
+    args.drain(i..(i + 2).min(args.len()));
+    Some(value)
+}
+/// Removes `--name` from the arguments and returns whether it was there.
+fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    let i = args.iter().position(|arg| arg == name);
+    i.map(|i| args.remove(i)).is_some()
+}
+
+fn parse_regex(pattern: &str) -> io::Result<regex::Regex> {
+    regex::RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// The platform module this build uses.
+pub const PLATFORM: &str = if cfg!(windows) { "windows" } else { "xdg" };
+
+/// Every command and the platforms where it does more than report `unsupported` or nothing.
+pub const COMMANDS: &[(&str, &[&str])] = &[
+    ("browser", &["windows", "xdg"]),
+    ("default", &["windows", "xdg"]),
+    ("software", &["windows"]),
+    ("locate", &["windows", "xdg"]),
+    ("shortcut", &["windows", "xdg"]),
+    ("shortcut-info", &["windows", "xdg"]),
+    ("shortcut-check", &["windows"]),
+    ("url-shortcut", &["windows", "xdg"]),
+    ("url-info", &["windows", "xdg"]),
+    ("protocol", &["windows", "xdg"]),
+    ("context-menu", &["windows"]),
+    ("send-to", &["windows"]),
+    ("autostart", &["windows", "xdg"]),
+    ("known-folder", &["windows", "xdg"]),
+    ("open", &["windows", "xdg"]),
+    ("cookies", &["windows", "xdg"]),
+    ("history", &["windows", "xdg"]),
+    ("proxy", &["windows", "xdg"]),
+    ("system", &["windows", "xdg"]),
+];
+
+/// Registry queries go to the fixture loaded with `--reg <file>` when one is given.
+pub fn collect_webbrowser_info(reg: Option<&MemoryRegistry>) -> Map<String, BrowserInfo> {
+    match reg {
+        Some(reg) => registry::collect_webbrowser_info(reg),
+        None => platform::collect_webbrowser_info(),
+    }
+}
+fn get_default_webbrowser_id(reg: Option<&MemoryRegistry>) -> io::Result<String> {
+    match reg {
+        Some(reg) => registry::get_default_webbrowser_id(reg),
+        None => platform::get_default_webbrowser_id(),
+    }
+}
+pub fn get_default_webbrowser(reg: Option<&MemoryRegistry>) -> io::Result<BrowserInfo> {
+    match reg {
+        Some(reg) => registry::get_default_webbrowser(reg),
+        None => platform::get_default_webbrowser(),
+    }
+}
+pub fn get_installed_software(
+    reg: Option<&MemoryRegistry>,
+    filter: &SoftwareFilter,
+) -> Map<String, SoftwareInfo> {
+    match reg {
+        Some(reg) => registry::get_installed_software(reg, filter),
+        None => platform::get_installed_software(filter),
+    }
+}
+pub fn get_proxy(reg: Option<&MemoryRegistry>) -> proxy::Proxy {
+    match reg {
+        Some(reg) => proxy::from_registry(reg),
+        None => platform::proxy(),
+    }
+}
+pub fn get_system_info(reg: Option<&MemoryRegistry>) -> system::SystemInfo {
+    match reg {
+        Some(reg) => system::from_registry(reg),
+        None => platform::system_info(),
+    }
+}
+fn locate(reg: Option<&MemoryRegistry>, locator: &Locator, name: &str) -> Vec<ToolInfo> {
+    match reg {
+        Some(reg) => locate::locate(locator, Some(reg), name),
+        None => platform::locate(locator, name),
+    }
+}
+
+/// The install root, reg-utils itself lives in its `dist` directory.
+fn install_dir() -> io::Result<std::path::PathBuf> {
+    let exe = std::env::current_exe()?;
+    let root = exe.parent().and_then(std::path::Path::parent);
+    Ok(root.ok_or(io::ErrorKind::NotFound)?.to_path_buf())
+}
+
+/// The script that starts the app, which default command lines run.
+fn run_script(install: &std::path::Path) -> String {
+    let run = install.join(if cfg!(windows) { "run.bat" } else { "run.sh" });
+    run.to_string_lossy().into_owned()
+}
+fn default_icon(install: &std::path::Path) -> String {
+    let icon = install.join("dist").join("favicon.ico");
+    icon.to_string_lossy().into_owned()
+}
+
+fn install_context_menu(reg: Option<&mut MemoryRegistry>, verb: &Verb) -> io::Result<()> {
+    match reg {
+        Some(reg) => explorer::install(reg, verb),
+        None => platform::install_context_menu(verb),
+    }
+}
+fn uninstall_context_menu(reg: Option<&mut MemoryRegistry>) -> io::Result<()> {
+    match reg {
+        Some(reg) => explorer::uninstall(reg),
+        None => platform::uninstall_context_menu(),
+    }
+}
+fn context_menu_status(reg: Option<&MemoryRegistry>) -> io::Result<JsonValue> {
+    match reg {
+        Some(reg) => Ok(explorer::status(reg)),
+        None => platform::context_menu_status(),
+    }
+}
+
+fn register_protocol(reg: Option<&mut MemoryRegistry>, handler: &Handler) -> io::Result<()> {
+    match reg {
+        Some(reg) => protocol::register(reg, handler, &install_dir()?.to_string_lossy()),
+        None => platform::register_protocol(handler),
+    }
+}
+fn unregister_protocol(reg: Option<&mut MemoryRegistry>, scheme: &str) -> io::Result<()> {
+    match reg {
+        Some(reg) => protocol::unregister(reg, scheme, &install_dir()?.to_string_lossy()),
+        None => platform::unregister_protocol(scheme),
+    }
+}
+fn protocol_status(reg: Option<&MemoryRegistry>, scheme: &str) -> ProtocolStatus {
+    match reg {
+        Some(reg) => protocol::status(reg, scheme),
+        None => platform::protocol_status(scheme),
+    }
+}
+
+/// Like `take_flag`, but a flag without its value is an error.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, Error> {
+    match take_flag(args, name) {
+        Some(Some(value)) => Ok(Some(value)),
+        Some(None) => Err(Error::bad_arguments(format!("Missing value for {}", name))),
+        None => Ok(None),
+    }
+}
+/// The positional argument at `i`, `what` names it in the error.
+fn take_arg<'a>(args: &'a [String], i: usize, what: &str) -> Result<&'a str, Error> {
+    match args.get(i) {
+        Some(arg) => Ok(arg),
+        None => Err(Error::bad_arguments(format!("Missing {}", what))),
+    }
+}
+fn unknown_action(action: &str) -> Error {
+    Error::bad_arguments(format!("Unknown action: {}", action))
+}
+fn parse_options(arg: &str) -> io::Result<JsonValue> {
+    json::parse(arg).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Runs the command in `args[1]` and returns the `data` of its envelope.
+pub fn run(args: &mut Vec<String>) -> Result<JsonValue, Error> {
+    let mut registry = match take_option(args, "--reg")? {
+        Some(path) => Some(MemoryRegistry::load(path)?),
+        None => None,
+    };
+    let reg = registry.as_ref();
+    let data = match take_arg(args, 1, "command")? {
+        "browser" => {
+            let map = collect_webbrowser_info(reg);
+            let default_id = get_default_webbrowser_id(reg).ok();
+            let mut json = JsonValue::new_object();
+            for (id, info) in map {
+                let words = info.words.clone().unwrap_or_default();
+                let profiles: Vec<JsonValue> = profile::find(&id, &words)
+                    .iter()
+                    .map(JsonValue::from)
+                    .collect();
+                json[id.as_str()] = JsonValue::from(info);
+                json[id.as_str()]["profiles"] = profiles.into();
+            }
+            json["$default"] = JsonValue::from(default_id);
+            json
+        }
+        "software" => {
+            let mut filter = SoftwareFilter {
+                system: take_switch(args, "--all"),
+                ..Default::default()
+            };
+            for (flag, regex) in [
+                ("--name", &mut filter.name),
+                ("--publisher", &mut filter.publisher),
+            ] {
+                if let Some(pattern) = take_option(args, flag)? {
+                    *regex = Some(parse_regex(&pattern)?);
+                }
+            }
+            JsonValue::from(get_installed_software(reg, &filter))
+        }
+        "locate" => {
+            let locator = Locator::from_env();
+            let names: Vec<&str> = match &args[2..] {
+                [] => locate::known_tools().collect(),
+                names => names.iter().map(String::as_str).collect(),
+            };
+            let map: Map<&str, Vec<ToolInfo>> = names
+                .into_iter()
+                .map(|name| (name, locate(reg, &locator, name)))
+                .collect();
+            JsonValue::from(map)
+        }
+        "default" => {
+            let info = get_default_webbrowser(reg).ok();
+            let (command, words) = match info {
+                Some(info) => (Some(info.command), info.words),
+                None => (None, None),
+            };
+            object! { command: command, words: words }
+        }
+        "shortcut" => {
+            let data = parse_options(take_arg(args, 2, "shortcut options")?)?;
+            shortcut::create(&data)?
+        }
+        "protocol" => {
+            let command = take_option(args, "--command")?;
+            let name = take_option(args, "--name")?;
+            let icon = take_option(args, "--icon")?;
+            let action = take_arg(args, 2, "action")?;
+            let scheme = protocol::check_scheme(take_arg(args, 3, "scheme")?)?;
+            match action {
+                "register" => {
+                    let install = install_dir()?;
+                    let handler = Handler {
+                        scheme: scheme.clone(),
+                        command: command
+                            .unwrap_or_else(|| format!("\"{}\" \"%1\"", run_script(&install))),
+                        name: name.unwrap_or_else(|| "Metadata Fetcher".to_string()),
+                        icon: icon.or_else(|| Some(default_icon(&install))),
+                    };
+                    register_protocol(registry.as_mut(), &handler)?;
+                }
+                "unregister" => unregister_protocol(registry.as_mut(), &scheme)?,
+                "status" => {}
+                action => return Err(unknown_action(action)),
+            }
+            JsonValue::from(protocol_status(registry.as_ref(), &scheme))
+        }
+        "context-menu" => {
+            let command = take_option(args, "--command")?;
+            let label = take_option(args, "--label")?;
+            let icon = take_option(args, "--icon")?;
+            match take_arg(args, 2, "action")? {
+                "install" => {
+                    let install = install_dir()?;
+                    let verb = Verb {
+                        label: label.unwrap_or_else(|| "Metadata Fetcher".to_string()),
+                        command: command
+                            .unwrap_or_else(|| format!("\"{}\" \"%1\"", run_script(&install))),
+                        icon: icon.or_else(|| Some(default_icon(&install))),
+                    };
+                    install_context_menu(registry.as_mut(), &verb)?;
+                }
+                "uninstall" => uninstall_context_menu(registry.as_mut())?,
+                "status" => {}
+                action => return Err(unknown_action(action)),
+            }
+            context_menu_status(registry.as_ref())?
+        }
+        "autostart" => {
+            let method = match take_option(args, "--method")? {
+                Some(method) => autostart::Method::try_from(&*method)?,
+                None => autostart::Method::default(),
+            };
+            // The Startup folder used with a `--reg` fixture
+            let startup = match take_option(args, "--dir")? {
+                Some(dir) => Some(std::path::PathBuf::from(dir)),
+                None => known_folder("Startup").ok(),
+            };
+            let action = take_arg(args, 2, "action")?;
+            let script = run_script(&install_dir()?);
+            let startup = startup.as_deref();
+            match (action, registry.as_mut()) {
+                ("enable", Some(reg)) => autostart::enable(reg, startup, method, &script)?,
+                ("enable", None) => platform::enable_autostart(method, &script)?,
+                ("disable", Some(reg)) => autostart::disable(reg, startup)?,
+                ("disable", None) => platform::disable_autostart()?,
+                ("status", _) => {}
+                (action, _) => return Err(unknown_action(action)),
+            }
+            let entries = match registry.as_ref() {
+                Some(reg) => autostart::status(reg, startup),
+                None => platform::autostart_status(),
+            };
+            object! {
+                enabled: !entries.is_empty(),
+                method: entries.first().map(|entry| entry.method),
+                entries: entries,
+            }
+        }
+        "send-to" => {
+            let dir = match take_option(args, "--dir")? {
+                Some(dir) => std::path::PathBuf::from(dir),
+                None => known_folder("SendTo")?,
+            };
+            let action = take_arg(args, 2, "action")?;
+            let install = install_dir()?;
+            let save_path = dir.join("Metadata Fetcher.lnk");
+            let save_path = save_path.to_string_lossy();
+            match action {
+                "install" => {
+                    let script = run_script(&install);
+                    let icon = default_icon(&install);
+                    shortcut::create(&shortcut::send_to(&script, &icon, &save_path))?;
+                }
+                "uninstall" => match std::fs::remove_file(&*save_path) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                    _ => {}
+                },
+                "status" => {}
+                action => return Err(unknown_action(action)),
+            }
+            let link = lnk::ShellLink::load(&*save_path).ok();
+            let target = link.as_ref().and_then(lnk::ShellLink::target);
+            object! {
+                path: &*save_path,
+                installed: link.is_some(),
+                target: target.clone(),
+                stale: target.is_some_and(|target| !std::path::Path::new(&target).exists()),
+            }
+        }
+        "shortcut-check" => {
+            let fix = take_switch(args, "--fix");
+            let install = match take_option(args, "--install")? {
+                Some(install) => install,
+                None => install_dir()?.to_string_lossy().into_owned(),
+            };
+            let mut folders: Vec<(String, String)> = shortcut::LINK_FOLDERS
+                .iter()
+                .filter_map(|&name| {
+                    let path = known_folder(name).ok()?;
+                    Some((name.to_string(), path.to_string_lossy().into_owned()))
+                })
+                .collect();
+            while let Some(dir) = take_option(args, "--dir")? {
+                folders.push(("".to_string(), dir));
+            }
+            JsonValue::from(shortcut::check(&folders, &install, fix))
+        }
+        "url-shortcut" => {
+            let data = parse_options(take_arg(args, 2, "url-shortcut options")?)?;
+            urlfile::create(&data)?
+        }
+        "url-info" => {
+            let shortcut = urlfile::InternetShortcut::load(take_arg(args, 2, "file")?)?;
+            JsonValue::from(&shortcut)
+        }
+        "shortcut-info" => {
+            let link = lnk::ShellLink::load(take_arg(args, 2, "file")?)?;
+            JsonValue::from(&link)
+        }
+        "known-folder" => {
+            if take_switch(args, "--all") {
+                let mut obj = JsonValue::new_object();
+                for &name in KNOWN_FOLDER_ID_LIST {
+                    let path = known_folder(name).ok();
+                    obj[name] = path.map(|path| path.to_string_lossy().into_owned()).into();
+                }
+                return Ok(obj);
+            }
+            let path = known_folder(take_arg(args, 2, "folder name")?)?;
+            JsonValue::from(path.to_string_lossy().into_owned())
+        }
+        "open" => {
+            let id = take_option(args, "--browser")?;
+            let profile_name = take_option(args, "--profile")?;
+            let mut options = launch::OpenOptions {
+                app_window: take_switch(args, "--app-window"),
+                new_window: take_switch(args, "--new-window"),
+                private: take_switch(args, "--private"),
+                ..Default::default()
+            };
+            let dry_run = take_switch(args, "--dry-run");
+            let url = take_arg(args, 2, "URL")?;
+            let browsers = collect_webbrowser_info(reg);
+            let browser = match id {
+                Some(id) => {
+                    let browser = browsers.get_key_value(&id).ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::NotFound, format!("Unknown browser: {}", id))
+                    })?;
+                    Some(browser)
+                }
+                // The system handler opens the URL when the default is unknown
+                None => get_default_webbrowser_id(reg)
+                    .ok()
+                    .and_then(|id| browsers.get_key_value(&id)),
+            };
+            let browser = browser.map(|(id, info)| (id.as_str(), info));
+            let profile_args = match (profile_name, browser) {
+                (Some(query), Some((id, info))) => {
+                    let words = info.words.as_deref().unwrap_or_default();
+                    let profiles = profile::find(id, words);
+                    let profile = profiles.into_iter().find(|profile| profile.matches(&query));
+                    let profile = profile.ok_or_else(|| {
+                        let message = format!("Unknown profile of {}: {}", id, query);
+                        io::Error::new(io::ErrorKind::NotFound, message)
+                    })?;
+                    profile.args
+                }
+                (Some(_), None) => {
+                    let message = "No browser to select a profile of";
+                    return Err(io::Error::new(io::ErrorKind::NotFound, message).into());
+                }
+                (None, _) => Vec::new(),
+            };
+            options.profile = &profile_args;
+            launch::open(url, browser, &options, dry_run)?
+        }
+        "cookies" => {
+            let missing = |name: &str| Error::bad_arguments(format!("Missing {}", name));
+            let profile = take_option(args, "--profile")?.ok_or_else(|| missing("--profile"))?;
+            let domain = take_option(args, "--domain")?.ok_or_else(|| missing("--domain"))?;
+            let format = match take_option(args, "--format")? {
+                Some(format) => cookies::Format::try_from(&*format)?,
+                None => cookies::Format::default(),
+            };
+            let cookies = cookies::read(std::path::Path::new(&profile), &domain)?;
+            match format {
+                cookies::Format::Json => {
+                    let cookies: Vec<JsonValue> = cookies.iter().map(JsonValue::from).collect();
+                    JsonValue::from(cookies)
+                }
+                cookies::Format::Netscape => JsonValue::from(cookies::to_netscape(&cookies)),
+            }
+        }
+        "history" => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs() as i64);
+            let mut filter = history::Filter::default();
+            if let Some(since) = take_option(args, "--since")? {
+                filter.since = Some(history::parse_time(&since, now)?);
+            }
+            if let Some(until) = take_option(args, "--until")? {
+                filter.until = Some(history::parse_time(&until, now)?);
+            }
+            filter.source = match take_option(args, "--source")?.as_deref() {
+                Some("history") => Some(history::Source::History),
+                // The spelling of `source` in the output, or its plural
+                Some("bookmark" | "bookmarks") => Some(history::Source::Bookmark),
+                Some(source) => {
+                    let message = format!("Unknown source: {}", source);
+                    return Err(Error::bad_arguments(message));
+                }
+                None => None,
+            };
+            while let Some(site) = take_option(args, "--site")? {
+                filter.patterns.push(history::site_pattern(&site)?);
+            }
+            while let Some(pattern) = take_option(args, "--pattern")? {
+                filter.patterns.push(parse_regex(&pattern)?);
+            }
+            if filter.patterns.is_empty() {
+                for (site, _) in history::SITES {
+                    filter.patterns.push(history::site_pattern(site)?);
+                }
+            }
+            let browser_id = take_option(args, "--browser")?;
+            let profile_name = take_option(args, "--profile")?;
+            let mut locations = Vec::new();
+            while let Some(dir) = take_option(args, "--dir")? {
+                locations.push(history::Location::new(None, None, dir.into()));
+            }
+            // Explicit directories must be readable, found profiles are skipped when they are not
+            let explicit = !locations.is_empty();
+            if !explicit {
+                locations = history::profiles(
+                    collect_webbrowser_info(reg),
+                    browser_id.as_deref(),
+                    profile_name.as_deref(),
+                );
+            }
+            let items = history::collect(&filter, &locations, explicit)?;
+            JsonValue::from(items)
+        }
+        "proxy" => JsonValue::from(&get_proxy(reg)),
+        "system" => JsonValue::from(&get_system_info(reg)),
+        command => {
+            let message = format!("Unknown command: {}", command);
+            return Err(Error::bad_arguments(message));
+        }
+    };
+    Ok(data)
+}
@@ -0,0 +1,751 @@
+//! Shell link (`.lnk`) decoding after [MS-SHLLINK], independent of the Windows shell.
+//!
+//! [MS-SHLLINK]: https://learn.microsoft.com/openspecs/windows_protocols/ms-shllink
+use json::{object, JsonValue};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+const HEADER_SIZE: u32 = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+pub const HAS_LINK_TARGET_ID_LIST: u32 = 0x0001;
+pub const HAS_LINK_INFO: u32 = 0x0002;
+pub const HAS_NAME: u32 = 0x0004;
+pub const HAS_RELATIVE_PATH: u32 = 0x0008;
+pub const HAS_WORKING_DIR: u32 = 0x0010;
+pub const HAS_ARGUMENTS: u32 = 0x0020;
+pub const HAS_ICON_LOCATION: u32 = 0x0040;
+pub const IS_UNICODE: u32 = 0x0080;
+pub const RUN_AS_USER: u32 = 0x2000;
+
+/// `WNNC_NET_LANMAN`, the provider of SMB shares.
+const NET_PROVIDER_LANMAN: u32 = 0x0002_0000;
+const ENVIRONMENT_BLOCK: u32 = 0xA000_0001;
+const ICON_ENVIRONMENT_BLOCK: u32 = 0xA000_0007;
+
+fn invalid_input<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+fn invalid_data<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ShowCommand {
+    #[default]
+    Normal,
+    Maximized,
+    Minimized,
+}
+impl ShowCommand {
+    pub fn from_raw(raw: u32) -> Self {
+        match raw {
+            3 => Self::Maximized,
+            7 => Self::Minimized,
+            _ => Self::Normal,
+        }
+    }
+    pub fn raw(&self) -> u32 {
+        match self {
+            Self::Normal => 1,
+            Self::Maximized => 3,
+            Self::Minimized => 7,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Maximized => "maximized",
+            Self::Minimized => "minimized",
+        }
+    }
+}
+impl TryFrom<&str> for ShowCommand {
+    type Error = io::Error;
+    fn try_from(show: &str) -> Result<Self, Self::Error> {
+        Ok(match show {
+            "normal" => Self::Normal,
+            "maximized" => Self::Maximized,
+            "minimized" => Self::Minimized,
+            _ => return Err(invalid_input(format!("Unknown show command: {}", show))),
+        })
+    }
+}
+
+/// Formats a `HotKeyFlags` value like `Ctrl+Alt+F`, `None` when unset or unknown.
+pub fn format_hotkey(hotkey: u16) -> Option<String> {
+    let (key, modifiers) = ((hotkey & 0xFF) as u8, (hotkey >> 8) as u8);
+    let key = match key {
+        0x30..=0x39 | 0x41..=0x5A => (key as char).to_string(),
+        0x70..=0x87 => format!("F{}", key - 0x6F),
+        0x90 => "NumLock".into(),
+        0x91 => "ScrollLock".into(),
+        _ => return None,
+    };
+    let mut parts: Vec<String> = [(2, "Ctrl"), (4, "Alt"), (1, "Shift")]
+        .iter()
+        .filter(|(bit, _)| modifiers & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect();
+    parts.push(key);
+    Some(parts.join("+"))
+}
+
+/// Parses a hotkey like `Ctrl+Alt+F` into `HotKeyFlags`, the inverse of [`format_hotkey`].
+pub fn parse_hotkey(hotkey: &str) -> io::Result<u16> {
+    let error = || invalid_input(format!("Invalid hotkey: {}", hotkey));
+    let mut parts: Vec<&str> = hotkey.split('+').map(str::trim).collect();
+    let key = parts.pop().ok_or_else(error)?;
+    let key = match key.to_ascii_uppercase().as_str() {
+        key if key.len() == 1 && key.as_bytes()[0].is_ascii_alphanumeric() => key.as_bytes()[0],
+        "NUMLOCK" => 0x90,
+        "SCROLLLOCK" => 0x91,
+        key => match key.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+            Some(n @ 1..=24) => 0x6F + n,
+            _ => return Err(error()),
+        },
+    };
+    let mut modifiers = 0u8;
+    for part in parts {
+        modifiers |= match part.to_ascii_lowercase().as_str() {
+            "shift" => 1,
+            "ctrl" | "control" => 2,
+            "alt" => 4,
+            _ => return Err(error()),
+        };
+    }
+    Ok((modifiers as u16) << 8 | key as u16)
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct LinkInfo {
+    pub drive_type: Option<u32>,
+    pub drive_serial: Option<u32>,
+    pub volume_label: Option<String>,
+    pub local_base_path: Option<String>,
+    pub net_name: Option<String>,
+    pub device_name: Option<String>,
+    pub common_path_suffix: Option<String>,
+}
+impl LinkInfo {
+    /// The full target path, local or on a network share.
+    pub fn path(&self) -> Option<String> {
+        let suffix = self.common_path_suffix.as_deref().unwrap_or_default();
+        let base = self
+            .local_base_path
+            .as_deref()
+            .or(self.net_name.as_deref())?;
+        Some(match (base.ends_with('\\'), suffix.is_empty()) {
+            (_, true) => base.to_string(),
+            (true, false) => format!("{}{}", base, suffix),
+            (false, false) => format!("{}\\{}", base, suffix),
+        })
+    }
+}
+impl From<LinkInfo> for JsonValue {
+    fn from(info: LinkInfo) -> JsonValue {
+        let drive_type = info.drive_type.map(|drive_type| match drive_type {
+            1 => "no-root-dir",
+            2 => "removable",
+            3 => "fixed",
+            4 => "remote",
+            5 => "cdrom",
+            6 => "ramdisk",
+            _ => "unknown",
+        });
+        object! {
+            path: info.path(),
+            driveType: drive_type,
+            driveSerial: info.drive_serial.map(|serial| format!("{:08X}", serial)),
+            volumeLabel: info.volume_label,
+            localBasePath: info.local_base_path,
+            netName: info.net_name,
+            deviceName: info.device_name,
+            commonPathSuffix: info.common_path_suffix,
+        }
+    }
+}
+
+/// `FILETIME` of a system time, 0 when unknown.
+fn filetime(time: io::Result<SystemTime>) -> u64 {
+    let since = |time: SystemTime| time.duration_since(UNIX_EPOCH).ok();
+    let Some(since) = time.ok().and_then(since) else {
+        return 0;
+    };
+    (since.as_secs() + 11_644_473_600) * 10_000_000 + since.subsec_nanos() as u64 / 100
+}
+
+fn utf16z(string: &str) -> Vec<u8> {
+    string
+        .encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+/// The ANSI copy of a string, for readers that ignore the Unicode one.
+fn ansiz(string: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = string
+        .chars()
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .collect();
+    bytes.push(0);
+    bytes
+}
+
+/// A file entry shell item with a version 3 `0xBEEF0004` extension holding the long name,
+/// `None` when the name does not fit its 16-bit sizes.
+fn file_entry_item(name: &str, directory: bool) -> Option<Vec<u8>> {
+    let mut item = vec![0, 0, if directory { 0x31 } else { 0x32 }, 0];
+    item.extend([0; 8]);
+    item.extend((if directory { 0x10u16 } else { 0x20 }).to_le_bytes());
+    item.extend(ansiz(name));
+    if item.len() % 2 == 1 {
+        item.push(0);
+    }
+    let ext_start = item.len();
+    let mut ext = Vec::new();
+    ext.extend([0, 0]);
+    ext.extend(3u16.to_le_bytes());
+    ext.extend(0xBEEF_0004u32.to_le_bytes());
+    ext.extend([0; 8]);
+    ext.extend(0x14u16.to_le_bytes());
+    ext.extend(0u16.to_le_bytes());
+    ext.extend(utf16z(name));
+    ext.extend(u16::try_from(ext_start).ok()?.to_le_bytes());
+    let ext_len = u16::try_from(ext.len()).ok()?;
+    ext[..2].copy_from_slice(&ext_len.to_le_bytes());
+    item.extend(ext);
+    let len = u16::try_from(item.len()).ok()?;
+    item[..2].copy_from_slice(&len.to_le_bytes());
+    Some(item)
+}
+
+/// Shell items for an absolute `X:\...` path: My Computer, the drive, then one per component.
+fn id_list_items(path: &str, directory: bool) -> Option<Vec<Vec<u8>>> {
+    let (drive, rest) = path.split_once(":\\")?;
+    if drive.len() != 1 || !drive.as_bytes()[0].is_ascii_alphabetic() {
+        return None;
+    }
+    let mut root = vec![20, 0, 0x1F, 0x50];
+    // {20D04FE0-3AEA-1069-A2D8-08002B30309D}
+    root.extend([
+        0xE0, 0x4F, 0xD0, 0x20, 0xEA, 0x3A, 0x69, 0x10, 0xA2, 0xD8, 0x08, 0x00, 0x2B, 0x30, 0x30,
+        0x9D,
+    ]);
+    let mut volume = vec![25, 0, 0x2F];
+    volume.extend(format!("{}:\\", drive.to_ascii_uppercase()).bytes());
+    volume.resize(25, 0);
+    let names: Vec<&str> = rest.split('\\').filter(|name| !name.is_empty()).collect();
+    let mut items = vec![root, volume];
+    for (i, name) in names.iter().enumerate() {
+        items.push(file_entry_item(name, directory || i + 1 < names.len())?);
+    }
+    Some(items)
+}
+
+impl LinkInfo {
+    /// The `LinkInfo` of a local `X:\...` path or a `\\server\share\...` path.
+    pub fn for_path(path: &str) -> Option<Self> {
+        if let Some(unc) = path.strip_prefix("\\\\") {
+            let mut parts = unc.splitn(3, '\\');
+            let (server, share) = (parts.next()?, parts.next()?);
+            return Some(Self {
+                net_name: Some(format!("\\\\{}\\{}", server, share)),
+                common_path_suffix: parts.next().map(str::to_string),
+                ..Default::default()
+            });
+        }
+        id_list_items(path, false)?;
+        Some(Self {
+            drive_type: Some(3),
+            drive_serial: Some(0),
+            local_base_path: Some(path.to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        const HEADER: usize = 0x24;
+        let mut flags = 0u32;
+        let mut body = Vec::new();
+        let mut offsets = [0u32; 7];
+        let push = |body: &mut Vec<u8>, bytes: Vec<u8>| {
+            let offset = (HEADER + body.len()) as u32;
+            body.extend(bytes);
+            offset
+        };
+        if let Some(base) = &self.local_base_path {
+            flags |= 1;
+            let label = self.volume_label.as_deref().unwrap_or_default();
+            let mut volume = Vec::new();
+            volume.extend(0u32.to_le_bytes());
+            volume.extend(self.drive_type.unwrap_or(3).to_le_bytes());
+            volume.extend(self.drive_serial.unwrap_or_default().to_le_bytes());
+            volume.extend(0x10u32.to_le_bytes());
+            volume.extend(ansiz(label));
+            let len = volume.len() as u32;
+            volume[..4].copy_from_slice(&len.to_le_bytes());
+            offsets[0] = push(&mut body, volume);
+            offsets[1] = push(&mut body, ansiz(base));
+        }
+        if let Some(net_name) = &self.net_name {
+            flags |= 2;
+            let net_name = ansiz(net_name);
+            let device_name = self.device_name.as_deref().map(ansiz);
+            let mut network = Vec::new();
+            network.extend(0u32.to_le_bytes());
+            // ValidNetType, and ValidDevice with a device name
+            network.extend((2 | device_name.is_some() as u32).to_le_bytes());
+            network.extend(0x14u32.to_le_bytes());
+            let device_offset = match device_name {
+                Some(_) => 0x14 + net_name.len() as u32,
+                None => 0,
+            };
+            network.extend(device_offset.to_le_bytes());
+            network.extend(NET_PROVIDER_LANMAN.to_le_bytes());
+            network.extend(net_name);
+            network.extend(device_name.unwrap_or_default());
+            let len = network.len() as u32;
+            network[..4].copy_from_slice(&len.to_le_bytes());
+            offsets[2] = push(&mut body, network);
+        }
+        let suffix = self.common_path_suffix.as_deref().unwrap_or_default();
+        offsets[3] = push(&mut body, ansiz(suffix));
+        if let Some(base) = &self.local_base_path {
+            offsets[4] = push(&mut body, utf16z(base));
+        }
+        offsets[5] = push(&mut body, utf16z(suffix));
+
+        let mut bytes = Vec::new();
+        bytes.extend(((HEADER + body.len()) as u32).to_le_bytes());
+        bytes.extend((HEADER as u32).to_le_bytes());
+        bytes.extend(flags.to_le_bytes());
+        for offset in &offsets[..6] {
+            bytes.extend(offset.to_le_bytes());
+        }
+        bytes.extend(body);
+        bytes
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ShellLink {
+    pub flags: u32,
+    pub file_attributes: u32,
+    /// `FILETIME`s, in 100ns since 1601.
+    pub creation_time: u64,
+    pub access_time: u64,
+    pub write_time: u64,
+    pub file_size: u32,
+    pub icon_index: i32,
+    pub show_command: ShowCommand,
+    pub hotkey: u16,
+    /// The raw shell items of the `LinkTargetIDList`.
+    pub id_list: Option<Vec<Vec<u8>>>,
+    pub link_info: Option<LinkInfo>,
+    pub name: Option<String>,
+    pub relative_path: Option<String>,
+    pub working_dir: Option<String>,
+    pub arguments: Option<String>,
+    pub icon_location: Option<String>,
+    /// The raw `ExtraData` blocks, without the terminal block.
+    pub extra_data: Vec<Vec<u8>>,
+}
+
+/// Little-endian reads that fail instead of panicking on truncated files.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or_else(|| invalid_data("Truncated shell link"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    /// A `StringData` entry: a character count followed by UTF-16 or ANSI characters.
+    fn string_data(&mut self, unicode: bool) -> io::Result<String> {
+        let count = self.u16()? as usize;
+        Ok(match unicode {
+            true => decode_utf16(self.take(count * 2)?),
+            false => decode_ansi(self.take(count)?),
+        })
+    }
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&wide)
+}
+/// ANSI strings use the code page of the machine that wrote them, UTF-8 covers the ASCII ones.
+fn decode_ansi(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+/// A NUL-terminated string starting at `offset`.
+fn c_string_at(bytes: &[u8], offset: usize) -> Option<String> {
+    let bytes = bytes.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0)?;
+    Some(decode_ansi(&bytes[..end]))
+}
+fn c_wstring_at(bytes: &[u8], offset: usize) -> Option<String> {
+    let bytes = bytes.get(offset..)?;
+    let end = bytes.chunks_exact(2).position(|pair| pair == [0, 0])?;
+    Some(decode_utf16(&bytes[..end * 2]))
+}
+fn non_empty(string: Option<String>) -> Option<String> {
+    string.filter(|string| !string.is_empty())
+}
+
+fn parse_link_info(bytes: &[u8]) -> io::Result<LinkInfo> {
+    let field = |offset| u32_at(bytes, offset).ok_or_else(|| invalid_data("Truncated LinkInfo"));
+    let header_size = field(4)?;
+    let flags = field(8)?;
+    let offset = |offset| field(offset).map(|value| value as usize);
+    let unicode = header_size >= 0x24;
+    let mut info = LinkInfo::default();
+    if flags & 1 != 0 {
+        let volume = bytes.get(offset(12)?..).unwrap_or_default();
+        info.drive_type = u32_at(volume, 4);
+        info.drive_serial = u32_at(volume, 8);
+        info.volume_label = match u32_at(volume, 12) {
+            Some(0x14) => u32_at(volume, 16).and_then(|at| c_wstring_at(volume, at as usize)),
+            Some(at) => c_string_at(volume, at as usize),
+            None => None,
+        };
+        info.local_base_path = match unicode {
+            true => c_wstring_at(bytes, offset(0x1C)?),
+            false => None,
+        }
+        .or_else(|| c_string_at(bytes, offset(16).ok()?));
+    }
+    if flags & 2 != 0 {
+        let network = bytes.get(offset(20)?..).unwrap_or_default();
+        let net_name = u32_at(network, 8).unwrap_or_default();
+        let device_name = u32_at(network, 12).unwrap_or_default();
+        let device_valid = u32_at(network, 4).unwrap_or_default() & 1 != 0;
+        if net_name > 0x14 {
+            info.net_name = u32_at(network, 20).and_then(|at| c_wstring_at(network, at as usize));
+            if device_valid {
+                info.device_name =
+                    u32_at(network, 24).and_then(|at| c_wstring_at(network, at as usize));
+            }
+        } else {
+            info.net_name = c_string_at(network, net_name as usize);
+            if device_valid {
+                info.device_name = c_string_at(network, device_name as usize);
+            }
+        }
+    }
+    info.common_path_suffix = match unicode {
+        true => c_wstring_at(bytes, offset(0x20)?),
+        false => None,
+    }
+    .or_else(|| c_string_at(bytes, offset(24).ok()?));
+    info.volume_label = non_empty(info.volume_label);
+    info.common_path_suffix = non_empty(info.common_path_suffix);
+    Ok(info)
+}
+
+/// The name of a file entry shell item, the long name from its `0xBEEF0004` extension if any.
+fn file_entry_name(item: &[u8]) -> Option<String> {
+    // size(2) type(1) unknown(1) file size(4) modified(4) attributes(2) short name
+    let short_end = 14 + item.get(14..)?.iter().position(|&b| b == 0)?;
+    let short = decode_ansi(&item[14..short_end]);
+    let ext = (short_end + 2) & !1;
+    let long = (|| {
+        let version = u16_at(item, ext + 2)?;
+        (u32_at(item, ext + 4)? == 0xBEEF_0004 && version >= 3).then_some(())?;
+        let mut at = ext + 18;
+        if version >= 7 {
+            at += 18;
+        }
+        at += 2;
+        if version >= 9 {
+            at += 4;
+        }
+        if version >= 8 {
+            at += 4;
+        }
+        non_empty(c_wstring_at(item, at))
+    })();
+    long.or(non_empty(Some(short)))
+}
+
+/// Best-effort path of the `LinkTargetIDList`, for links to the file system.
+pub fn id_list_path(items: &[Vec<u8>]) -> Option<String> {
+    let mut path = String::new();
+    for item in items {
+        let kind = *item.get(2)?;
+        match kind & 0x70 {
+            // Root folder (My Computer, ...)
+            0x10 => {}
+            0x20 => path = c_string_at(item, 3)?,
+            0x30 => {
+                if !path.is_empty() && !path.ends_with('\\') {
+                    path.push('\\');
+                }
+                path.push_str(&file_entry_name(item)?);
+            }
+            _ => return None,
+        }
+    }
+    non_empty(Some(path))
+}
+
+impl ShellLink {
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.u32()? != HEADER_SIZE || reader.take(16)? != LINK_CLSID {
+            return Err(invalid_data("Not a shell link"));
+        }
+        let mut link = ShellLink {
+            flags: reader.u32()?,
+            file_attributes: reader.u32()?,
+            creation_time: reader.u64()?,
+            access_time: reader.u64()?,
+            write_time: reader.u64()?,
+            file_size: reader.u32()?,
+            icon_index: reader.u32()? as i32,
+            show_command: ShowCommand::from_raw(reader.u32()?),
+            hotkey: reader.u16()?,
+            ..Default::default()
+        };
+        reader.take(10)?;
+
+        if link.flags & HAS_LINK_TARGET_ID_LIST != 0 {
+            let size = reader.u16()? as usize;
+            let mut list = Reader {
+                bytes: reader.take(size)?,
+                pos: 0,
+            };
+            let mut items = Vec::new();
+            loop {
+                let size = list.u16()? as usize;
+                if size == 0 {
+                    break;
+                }
+                list.pos -= 2;
+                items.push(list.take(size.max(2))?.to_vec());
+            }
+            link.id_list = Some(items);
+        }
+        if link.flags & HAS_LINK_INFO != 0 {
+            let size =
+                u32_at(bytes, reader.pos).ok_or_else(|| invalid_data("Truncated LinkInfo"))?;
+            link.link_info = Some(parse_link_info(reader.take(size as usize)?)?);
+        }
+
+        let unicode = link.flags & IS_UNICODE != 0;
+        let strings = [
+            (HAS_NAME, &mut link.name),
+            (HAS_RELATIVE_PATH, &mut link.relative_path),
+            (HAS_WORKING_DIR, &mut link.working_dir),
+            (HAS_ARGUMENTS, &mut link.arguments),
+            (HAS_ICON_LOCATION, &mut link.icon_location),
+        ];
+        for (flag, string) in strings {
+            if link.flags & flag != 0 {
+                *string = Some(reader.string_data(unicode)?);
+            }
+        }
+
+        // Extra data is optional and often cut short by third-party writers
+        while let Ok(size) = reader.u32() {
+            if size < 4 {
+                break;
+            }
+            reader.pos -= 4;
+            match reader.take(size as usize) {
+                Ok(block) => link.extra_data.push(block.to_vec()),
+                Err(_) => break,
+            }
+        }
+        Ok(link)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// A link to `target`, with the attributes, size and times of the file when it exists.
+    pub fn new(target: &str) -> Self {
+        let meta = fs::metadata(target).ok();
+        let directory = meta.as_ref().is_some_and(fs::Metadata::is_dir);
+        let time = |time: fn(&fs::Metadata) -> io::Result<SystemTime>| {
+            meta.as_ref().map_or(0, |meta| filetime(time(meta)))
+        };
+        ShellLink {
+            flags: IS_UNICODE,
+            file_attributes: if directory { 0x10 } else { 0x20 },
+            creation_time: time(fs::Metadata::created),
+            access_time: time(fs::Metadata::accessed),
+            write_time: time(fs::Metadata::modified),
+            file_size: meta.as_ref().map_or(0, |meta| meta.len() as u32),
+            id_list: id_list_items(target, directory),
+            link_info: LinkInfo::for_path(target),
+            ..Default::default()
+        }
+    }
+
+    /// Points the link at a new target, keeping everything else.
+    pub fn set_target(&mut self, target: &str) {
+        let link = Self::new(target);
+        self.id_list = link.id_list;
+        self.link_info = link.link_info;
+        self.relative_path = None;
+        // The environment block would take precedence over the new target
+        self.extra_data
+            .retain(|block| u32_at(block, 4) != Some(ENVIRONMENT_BLOCK));
+    }
+
+    /// Encodes the link, always with Unicode strings, failing on lengths the format cannot hold.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let strings = [
+            (HAS_NAME, &self.name),
+            (HAS_RELATIVE_PATH, &self.relative_path),
+            (HAS_WORKING_DIR, &self.working_dir),
+            (HAS_ARGUMENTS, &self.arguments),
+            (HAS_ICON_LOCATION, &self.icon_location),
+        ];
+        let mut flags = self.flags & !(HAS_LINK_TARGET_ID_LIST | HAS_LINK_INFO) | IS_UNICODE;
+        for (flag, string) in &strings {
+            flags = match string {
+                Some(_) => flags | flag,
+                None => flags & !flag,
+            };
+        }
+        if self.id_list.is_some() {
+            flags |= HAS_LINK_TARGET_ID_LIST;
+        }
+        if self.link_info.is_some() {
+            flags |= HAS_LINK_INFO;
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend(HEADER_SIZE.to_le_bytes());
+        bytes.extend(LINK_CLSID);
+        bytes.extend(flags.to_le_bytes());
+        bytes.extend(self.file_attributes.to_le_bytes());
+        bytes.extend(self.creation_time.to_le_bytes());
+        bytes.extend(self.access_time.to_le_bytes());
+        bytes.extend(self.write_time.to_le_bytes());
+        bytes.extend(self.file_size.to_le_bytes());
+        bytes.extend(self.icon_index.to_le_bytes());
+        bytes.extend(self.show_command.raw().to_le_bytes());
+        bytes.extend(self.hotkey.to_le_bytes());
+        bytes.extend([0; 10]);
+        if let Some(items) = &self.id_list {
+            let size: usize = items.iter().map(Vec::len).sum::<usize>() + 2;
+            let size = u16::try_from(size)
+                .map_err(|_| invalid_input(format!("ID list of {} bytes is too long", size)))?;
+            bytes.extend(size.to_le_bytes());
+            items.iter().for_each(|item| bytes.extend(item));
+            bytes.extend([0, 0]);
+        }
+        if let Some(info) = &self.link_info {
+            bytes.extend(info.to_bytes());
+        }
+        for string in strings.iter().filter_map(|(_, string)| string.as_ref()) {
+            let wide: Vec<u16> = string.encode_utf16().collect();
+            let count = u16::try_from(wide.len()).map_err(|_| {
+                invalid_input(format!("String of {} characters is too long", wide.len()))
+            })?;
+            bytes.extend(count.to_le_bytes());
+            wide.iter().for_each(|c| bytes.extend(c.to_le_bytes()));
+        }
+        self.extra_data.iter().for_each(|block| bytes.extend(block));
+        bytes.extend([0; 4]);
+        Ok(bytes)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_bytes()?)
+    }
+
+    fn extra_block(&self, signature: u32) -> Option<&[u8]> {
+        self.extra_data
+            .iter()
+            .find(|block| u32_at(block, 4) == Some(signature))
+            .map(Vec::as_slice)
+    }
+    /// The unexpanded `%VAR%` path of an `EnvironmentVariableDataBlock` style block.
+    fn environment_path(&self, signature: u32) -> Option<String> {
+        let block = self.extra_block(signature)?;
+        non_empty(c_wstring_at(block.get(..788)?, 268)).or(non_empty(c_string_at(block, 8)))
+    }
+    /// The target as written by `IShellLink::SetPath` with environment variables.
+    pub fn environment_target(&self) -> Option<String> {
+        self.environment_path(ENVIRONMENT_BLOCK)
+    }
+    pub fn environment_icon(&self) -> Option<String> {
+        self.environment_path(ICON_ENVIRONMENT_BLOCK)
+    }
+
+    pub fn id_list_path(&self) -> Option<String> {
+        id_list_path(self.id_list.as_deref()?)
+    }
+
+    /// The target path the shell would resolve, from the first structure that names one.
+    pub fn target(&self) -> Option<String> {
+        (self.link_info.as_ref().and_then(LinkInfo::path))
+            .or_else(|| self.id_list_path())
+            .or_else(|| self.environment_target())
+            .or_else(|| self.relative_path.clone())
+    }
+}
+
+impl From<&ShellLink> for JsonValue {
+    fn from(link: &ShellLink) -> JsonValue {
+        object! {
+            target: link.target(),
+            arguments: link.arguments.clone(),
+            workingDir: link.working_dir.clone(),
+            relativePath: link.relative_path.clone(),
+            description: link.name.clone(),
+            iconLocation: link.icon_location.clone().or_else(|| link.environment_icon()),
+            iconIndex: link.icon_index,
+            showCommand: link.show_command.as_str(),
+            hotkey: format_hotkey(link.hotkey),
+            runAsAdmin: link.flags & RUN_AS_USER != 0,
+            fileSize: link.file_size,
+            fileAttributes: link.file_attributes,
+            idList: link.id_list.as_ref().map(|items| object! {
+                path: id_list_path(items),
+                items: items.len(),
+            }),
+            linkInfo: link.link_info.clone(),
+            environmentTarget: link.environment_target(),
+        }
+    }
+}
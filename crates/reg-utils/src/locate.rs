@@ -0,0 +1,278 @@
+use crate::registry::{self, Hive, Key, Registry};
+use crate::SoftwareFilter;
+use json::{object, JsonValue};
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{env, fs, thread};
+
+/// Known tools: the name asked for, its executable and the arguments printing its version.
+static TOOLS: &[(&str, &str, &[&str])] = &[
+    ("bbdown", "BBDown", &["--version"]),
+    ("ffmpeg", "ffmpeg", &["-version"]),
+    ("ffprobe", "ffprobe", &["-version"]),
+    ("yt-dlp", "yt-dlp", &["--version"]),
+    ("aria2c", "aria2c", &["--version"]),
+    ("deno", "deno", &["--version"]),
+    ("bun", "bun", &["--version"]),
+    ("node", "node", &["--version"]),
+];
+
+/// A tool that has not answered `--version` by then is killed and reported without a version.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct ToolInfo {
+    pub path: PathBuf,
+    /// Where it was found: `path`, `app-paths`, `uninstall` or `home`.
+    pub source: &'static str,
+    /// The first version number in the output, e.g. `6.1.1` or `2024.08.06`.
+    pub version: Option<String>,
+    /// The first non-empty line of the version output.
+    pub version_text: Option<String>,
+}
+impl From<ToolInfo> for JsonValue {
+    fn from(info: ToolInfo) -> JsonValue {
+        object! {
+            path: info.path.to_string_lossy().into_owned(),
+            source: info.source,
+            version: info.version,
+            versionText: info.version_text,
+        }
+    }
+}
+
+/// Where tools are searched for, resolved once so lookups can be pointed at fixture trees.
+pub struct Locator {
+    pub path: Vec<PathBuf>,
+    /// Extensions tried after the bare name, from `PATHEXT` on Windows.
+    pub extensions: Vec<String>,
+    /// Per-user install directories of deno, bun, scoop and winget.
+    pub home_dirs: Vec<PathBuf>,
+}
+
+impl Locator {
+    pub fn from_env() -> Self {
+        let var = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+        let home = var("HOME")
+            .or_else(|| var("USERPROFILE"))
+            .map(PathBuf::from);
+        let extensions = match cfg!(windows) {
+            true => var("PATHEXT")
+                .unwrap_or_else(|| OsString::from(".COM;.EXE;.BAT;.CMD"))
+                .to_string_lossy()
+                .split(';')
+                .filter(|ext| !ext.is_empty())
+                .map(str::to_lowercase)
+                .collect(),
+            false => Vec::new(),
+        };
+        let mut home_dirs = Vec::new();
+        home_dirs.extend(var("DENO_INSTALL").map(|dir| PathBuf::from(dir).join("bin")));
+        home_dirs.extend(var("BUN_INSTALL").map(|dir| PathBuf::from(dir).join("bin")));
+        home_dirs.extend(var("SCOOP").map(|dir| PathBuf::from(dir).join("shims")));
+        if let Some(home) = &home {
+            home_dirs.extend(
+                [".deno/bin", ".bun/bin", "scoop/shims", ".local/bin"].map(|dir| home.join(dir)),
+            );
+        }
+        home_dirs.extend(
+            var("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join(r"Microsoft\WinGet\Links")),
+        );
+        Self {
+            path: var("PATH")
+                .map(|path| env::split_paths(&path).collect())
+                .unwrap_or_default(),
+            extensions,
+            home_dirs,
+        }
+    }
+
+    /// File names the executable may have, the bare name first.
+    fn file_names(&self, exe: &str) -> Vec<String> {
+        let mut names = vec![exe.to_string()];
+        names.extend(self.extensions.iter().map(|ext| format!("{}{}", exe, ext)));
+        names
+    }
+
+    fn find_in(&self, dirs: &[PathBuf], exe: &str) -> Vec<PathBuf> {
+        let names = self.file_names(exe);
+        dirs.iter()
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .filter(|path| is_executable(path))
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Strips the quotes and the `,<index>` icon suffix registry paths often carry.
+fn registry_path(value: &str) -> PathBuf {
+    let value = value.trim();
+    let value = match value.rsplit_once(',') {
+        Some((path, index)) if index.trim().parse::<i32>().is_ok() => path,
+        _ => value,
+    };
+    PathBuf::from(value.trim().trim_matches('"'))
+}
+
+/// `App Paths` registrations, which `ShellExecute` and the Run dialog use instead of `PATH`.
+fn find_app_paths<R: Registry>(reg: &R, exe: &str) -> Vec<PathBuf> {
+    let name = match Path::new(exe).extension() {
+        Some(_) => exe.to_string(),
+        None => format!("{}.exe", exe),
+    };
+    let path = format!(
+        r"SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths\{}",
+        name
+    );
+    [Hive::CurrentUser, Hive::LocalMachine]
+        .iter()
+        .filter_map(|&hive| reg.open(hive, &path).ok())
+        .filter_map(|key| key.get_value::<String>("").ok())
+        .map(|value| registry_path(&value))
+        .collect()
+}
+
+/// Executables named like the tool in the install location or next to the icon of uninstall entries.
+fn find_uninstall<R: Registry>(reg: &R, locator: &Locator, exe: &str) -> Vec<PathBuf> {
+    let filter = SoftwareFilter {
+        system: true,
+        ..Default::default()
+    };
+    let mut names = locator.file_names(exe);
+    names.push(format!("{}.exe", exe));
+    registry::get_installed_software(reg, &filter)
+        .into_values()
+        .flat_map(|info| {
+            let icon = info.display_icon.as_deref().map(registry_path);
+            let dirs = info.path.as_deref().map(registry_path).into_iter();
+            let dirs = dirs.flat_map(|dir| [dir.join("bin"), dir]);
+            let dirs = dirs.chain(
+                icon.as_deref()
+                    .and_then(Path::parent)
+                    .map(Path::to_path_buf),
+            );
+            dirs.collect::<Vec<_>>()
+        })
+        .flat_map(|dir| {
+            names
+                .iter()
+                .map(move |name| dir.join(name))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Reads the pipe to its end on a thread, so a chatty tool never blocks on a full pipe.
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> mpsc::Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        let _ = sender.send(buffer);
+    });
+    receiver
+}
+
+/// Runs the tool and reads the first version number from its output.
+fn read_version(path: &Path, args: &[&str]) -> (Option<String>, Option<String>) {
+    let child = Command::new(path)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let Ok(mut child) = child else {
+        return (None, None);
+    };
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let deadline = Instant::now() + VERSION_TIMEOUT;
+    while let Ok(None) = child.try_wait() {
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return (None, None);
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    // A process the tool left running may still hold the pipes open
+    let remaining = || deadline.saturating_duration_since(Instant::now());
+    let (Ok(stdout), Ok(stderr)) = (
+        stdout.recv_timeout(remaining()),
+        stderr.recv_timeout(remaining()),
+    ) else {
+        return (None, None);
+    };
+    // Some tools print their version to stderr
+    let text = match stdout.iter().all(u8::is_ascii_whitespace) {
+        true => String::from_utf8_lossy(&stderr).into_owned(),
+        false => String::from_utf8_lossy(&stdout).into_owned(),
+    };
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty());
+    let version = regex::Regex::new(r"\d+(?:\.\d+)+")
+        .ok()
+        .zip(line)
+        .and_then(|(regex, line)| regex.find(line))
+        .map(|version| version.as_str().to_string());
+    (version, line.map(str::to_string))
+}
+
+/// Every distinct installation of the tool, in `PATH` order first.
+pub fn locate<R: Registry>(locator: &Locator, reg: Option<&R>, name: &str) -> Vec<ToolInfo> {
+    let tool = TOOLS
+        .iter()
+        .find(|(id, _, _)| id.eq_ignore_ascii_case(name));
+    let (exe, args) = match tool {
+        Some(&(_, exe, args)) => (exe, args),
+        None => (name, &["--version"][..]),
+    };
+    let mut candidates: Vec<(&'static str, PathBuf)> = Vec::new();
+    let mut push = |source, paths: Vec<PathBuf>| {
+        candidates.extend(paths.into_iter().map(|path| (source, path)));
+    };
+    push("path", locator.find_in(&locator.path, exe));
+    if let Some(reg) = reg {
+        push("app-paths", find_app_paths(reg, exe));
+        push("uninstall", find_uninstall(reg, locator, exe));
+    }
+    push("home", locator.find_in(&locator.home_dirs, exe));
+
+    let mut seen = Vec::new();
+    candidates
+        .into_iter()
+        .filter(|(_, path)| is_executable(path))
+        .filter(|(_, path)| {
+            let real = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            let new = !seen.contains(&real);
+            seen.push(real);
+            new
+        })
+        .map(|(source, path)| {
+            let (version, version_text) = read_version(&path, args);
+            ToolInfo {
+                path,
+                source,
+                version,
+                version_text,
+            }
+        })
+        .collect()
+}
+
+pub fn known_tools() -> impl Iterator<Item = &'static str> {
+    TOOLS.iter().map(|(id, _, _)| *id)
+}
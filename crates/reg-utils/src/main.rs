@@ -1,231 +1,113 @@
-#[macro_use]
-extern crate mashup;
-use json::{object, JsonValue};
-use std::collections::BTreeMap as Map;
-use std::io;
-use windows_sys::core::{GUID, PWSTR};
-use windows_sys::Win32::UI::Shell;
-use winreg::{enums as e, RegKey};
-
-static HKCU: RegKey = RegKey::predef(e::HKEY_CURRENT_USER);
-static HKCR: RegKey = RegKey::predef(e::HKEY_CLASSES_ROOT);
-static HKLM: RegKey = RegKey::predef(e::HKEY_LOCAL_MACHINE);
-
-pub fn get_default_webbrowser_id() -> io::Result<String> {
-    let path = r"SOFTWARE\Microsoft\Windows\Shell\Associations\UrlAssociations\http\UserChoice";
-    HKCU.open_subkey(path)?.get_value("ProgId")
-}
-pub fn get_webbrowser_command(prog_id: &str) -> io::Result<String> {
-    HKCR.open_subkey(prog_id)?
-        .open_subkey(r"shell\open\command")?
-        .get_value("")
-}
-pub fn get_default_webbrowser_command() -> io::Result<String> {
-    let prog_id = get_default_webbrowser_id()?;
-    get_webbrowser_command(&prog_id)
-}
-
-pub struct BrowserInfo {
-    pub name: String,
-    pub command: String,
-}
-impl From<BrowserInfo> for JsonValue {
-    fn from(info: BrowserInfo) -> JsonValue {
-        let words = shell_words::split(&info.command).ok();
-        object! {
-            name: info.name,
-            command: info.command,
-            words: words,
-        }
-    }
-}
-
-// 枚举安装的浏览器(https://www.cnblogs.com/talenth/p/14049927.html)
-pub fn collect_webbrowser() -> Map<String, String> {
-    let path = (
-        r"SOFTWARE\Wow6432Node\Clients\StartMenuInternet",
-        r"SOFTWARE\Clients\StartMenuInternet",
-    );
-    let keys = [
-        HKLM.open_subkey(path.0),
-        HKLM.open_subkey(path.1),
-        HKCU.open_subkey(path.0),
-        HKCU.open_subkey(path.1),
+use reg_utils::output::{self, ErrorKind};
+use reg_utils::KNOWN_FOLDER_ID_LIST;
+
+fn usage_known_folder<D: std::fmt::Display>(arg0: D) -> String {
+    let mut lines = vec![
+        "Usage:".to_string(),
+        format!("\t$ {} <known-folder> --all", arg0),
+        format!("\t$ {} <known-folder> <", arg0),
     ];
-    keys.iter()
-        .map(Result::as_ref)
-        .filter_map(Result::ok)
-        .flat_map(|key: &RegKey| {
-            key.enum_keys().filter_map(Result::ok).map(|name: String| {
-                let key = key.open_subkey(&name)?;
-                let name = key.get_value("")?;
-                let path = r"Capabilities\URLAssociations";
-                let id = key.open_subkey(path)?.get_value("https")?;
-                io::Result::Ok((id, name))
-            })
-        })
-        .filter_map(Result::ok)
-        .collect()
-}
-pub fn collect_webbrowser_info() -> Map<String, BrowserInfo> {
-    collect_webbrowser()
-        .into_iter()
-        .map(|(id, name)| {
-            let command = get_webbrowser_command(&id)?;
-            io::Result::Ok((id, BrowserInfo { name, command }))
-        })
-        .filter_map(Result::ok)
-        .collect()
-}
-
-pub struct SoftwareInfo {
-    pub name: String,
-    pub version: String,
-    pub path: String,
-}
-impl From<SoftwareInfo> for JsonValue {
-    fn from(info: SoftwareInfo) -> JsonValue {
-        object! {
-            name: info.name,
-            version: info.version,
-            path: info.path,
-        }
-    }
-}
-
-pub fn get_installed_software() -> Map<String, SoftwareInfo> {
-    let keys = [HKLM.open_subkey(r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall")];
-    keys.iter()
-        .map(Result::as_ref)
-        .filter_map(Result::ok)
-        .flat_map(|key: &RegKey| {
-            key.enum_keys().filter_map(Result::ok).map(|id: String| {
-                let key = key.open_subkey(&id)?;
-                let info = SoftwareInfo {
-                    name: key.get_value("DisplayName")?,
-                    version: key.get_value("DisplayVersion")?,
-                    path: key.get_value("InstallLocation")?,
-                };
-                io::Result::Ok((id, info))
-            })
-        })
-        .filter_map(Result::ok)
-        .collect()
-}
-
-macro_rules! known_folder_id {
-    [$($x:ident),+ $(,)?] => (
-        #[allow(unused)]
-        static KNOWN_FOLDER_ID_LIST: &[&'static str] = &[$(stringify!($x)),+];
-        fn known_folder_id(input: &str) -> Option<&GUID> {
-            Some(match input {
-                $(stringify!($x) => {
-                    mashup!{ m[$x] = FOLDERID_ $x; }
-                    &m! { Shell::$x }
-                })+
-                _ => return None
-            })
-        }
-    );
-}
-
-known_folder_id![Desktop, Documents, SendTo, StartMenu, Startup];
-
-unsafe fn len<T: Copy + Default + std::cmp::PartialEq>(ptr: *const T) -> usize {
-    use std::hint::unreachable_unchecked;
-    let default: T = Default::default();
-    for i in 0.. {
-        if unsafe { *ptr.add(i) } == default {
-            return i;
-        }
-    }
-    unsafe { unreachable_unchecked() }
-}
-
-fn known_folder(input: &str) -> Option<String> {
-    use std::ffi::OsString;
-    use std::os::windows::ffi::OsStringExt;
-    use std::ptr::null_mut;
-
-    let rfid = known_folder_id(input)?;
-    let mut path: PWSTR = null_mut();
-    unsafe { Shell::SHGetKnownFolderPath(rfid, 0, null_mut(), &mut path) };
-
-    let wide = unsafe { std::slice::from_raw_parts(path, len(path)) };
-    OsString::from_wide(wide).into_string().ok()
-}
-
-fn help_known_folder<D: std::fmt::Display>(arg0: D) -> ! {
-    eprintln!("Usage:");
-    eprintln!("\t$ {} <known-folder> <", &arg0);
     for item in KNOWN_FOLDER_ID_LIST {
-        eprintln!("\t\t| \"{}\"", item);
+        lines.push(format!("\t\t| \"{}\"", item));
     }
-    eprintln!("\t>");
-    std::process::exit(-1)
-}
-
-fn help<D: std::fmt::Display>(arg0: D) -> ! {
-    let lnk = "{ targetPath, iconPath, savePath }";
-    eprintln!("Usage:");
-    eprintln!("\t$ {} <browser|software|default>", &arg0);
-    eprintln!("\t$ {} <shortcut> \"{}\"", &arg0, lnk);
-    eprintln!("\t$ {} <known-folder> <...>", &arg0);
-    std::process::exit(-1)
-}
-
-pub fn main() -> io::Result<()> {
-    let args: Box<[String]> = std::env::args().collect();
-    if !(args.len() >= 2) {
-        help(&args[0]);
-    }
-    match args[1].as_ref() {
-        "browser" => {
-            let map = collect_webbrowser_info();
-            let default_id = get_default_webbrowser_id().ok();
-            let mut json = JsonValue::from(map);
-            json["$default"] = JsonValue::from(default_id);
-            println!("{}", json::stringify(json));
-        }
-        "software" => {
-            let map = get_installed_software();
-            println!("{}", json::stringify(map));
+    lines.push("\t>".to_string());
+    lines.join("\n")
+}
+
+fn usage<D: std::fmt::Display>(arg0: D) -> String {
+    let lnk = "{ targetPath, savePath, arguments?, workingDir?, description?, iconPath?, iconIndex?, showCommand?, hotkey?, runAsAdmin?, overwrite? }";
+    let url =
+        "{ url, savePath | dir + title, iconFile?, iconIndex?, hotkey?, properties?, overwrite? }";
+    [
+        "Usage: every command prints { ok, version, data | error }, add --pretty to indent it"
+            .to_string(),
+        "\tor --jsonl to print list data as one JSON item per line, as history does without --envelope"
+            .to_string(),
+        format!("\t$ {} <browser|default> [--reg <file.reg>]", arg0),
+        format!(
+            "\t$ {} <software> [--name <regex>] [--publisher <regex>] [--all] [--reg <file.reg>]",
+            arg0
+        ),
+        format!("\t$ {} <locate> [tool...] [--reg <file.reg>]", arg0),
+        format!("\t$ {} <shortcut> \"{}\"", arg0, lnk),
+        format!("\t$ {} <shortcut-info> <file.lnk>", arg0),
+        format!(
+            "\t$ {} <shortcut-check> [--fix] [--install <dir>] [--dir <dir>...]",
+            arg0
+        ),
+        format!("\t$ {} <url-shortcut> \"{}\"", arg0, url),
+        format!("\t$ {} <url-info> <file.url>", arg0),
+        format!(
+            "\t$ {} <protocol> <register|unregister|status> <scheme> [--command <template>] [--name <name>] [--icon <path>]",
+            arg0
+        ),
+        format!(
+            "\t$ {} <context-menu> <install|uninstall|status> [--command <template>] [--label <text>] [--icon <path>]",
+            arg0
+        ),
+        format!(
+            "\t$ {} <send-to> <install|uninstall|status> [--dir <dir>]",
+            arg0
+        ),
+        format!(
+            "\t$ {} <autostart> <enable|disable|status> [--method <startup|run>]",
+            arg0
+        ),
+        format!("\t$ {} <known-folder> <...>", arg0),
+        format!(
+            "\t$ {} <open> <url> [--browser <id>] [--profile <name>] [--app-window] [--new-window] [--private] [--dry-run]",
+            arg0
+        ),
+        format!(
+            "\t$ {} <cookies> --profile <firefox profile dir> --domain <domain> [--format <json|netscape>]",
+            arg0
+        ),
+        format!(
+            "\t$ {} <history> [--site <bilibili|youtube|niconico|pixiv>...] [--pattern <regex>...] [--since <time>] [--until <time>] [--source <history|bookmark>] [--browser <id>] [--profile <name>] [--dir <profile dir>...] [--envelope]",
+            arg0
+        ),
+        format!("\t$ {} <proxy> [--reg <file.reg>]", arg0),
+        format!("\t$ {} <system> [--reg <file.reg>]", arg0),
+        format!("\t$ {} <serve> [--reg <file.reg>]", arg0),
+    ]
+    .join("\n")
+}
+
+pub fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let pretty = args.iter().any(|arg| arg == "--pretty");
+    let envelope = args.iter().any(|arg| arg == "--envelope");
+    // `history` is piped into batch mode, so it prints lines by default
+    let lines = !envelope
+        && (args.iter().any(|arg| arg == "--jsonl")
+            || args.get(1).is_some_and(|arg| arg == "history"));
+    args.retain(|arg| arg != "--pretty" && arg != "--jsonl" && arg != "--envelope");
+    if args.get(1).is_some_and(|arg| arg == "serve") {
+        if let Err(err) = reg_utils::rpc::serve(&args[2..]) {
+            eprintln!("{}", err);
+            std::process::exit(1);
         }
-        "default" => {
-            let command = get_default_webbrowser_command().ok();
-            let words = command.as_deref().map(shell_words::split).map(Result::ok);
-            let obj = object! { command: command, words: words.flatten() };
-            println!("{}", json::stringify(obj));
-        }
-        "shortcut" => {
-            let Some(arg2) = args.get(2) else {
-                help(&args[0]);
-            };
-            let mut data = json::parse(arg2).map_err(io::Error::other)?;
-            let icon_path = data["iconPath"].take_string();
-            let (Some(target_path), Some(save_path)) =
-                (data["targetPath"].as_str(), data["savePath"].as_str())
-            else {
-                help(&args[0]);
-            };
-
-            let mut shortcut = mslnk::ShellLink::new(target_path).map_err(io::Error::other)?;
-            shortcut.set_icon_location(icon_path);
-            shortcut.create_lnk(save_path).map_err(io::Error::other)?;
-        }
-        "known-folder" => {
-            let Some(arg2) = args.get(2) else {
-                help_known_folder(&args[0]);
-            };
-            let Some(path) = known_folder(arg2) else {
-                help_known_folder(&args[0]);
-            };
-            println!("{}", path);
+        return;
+    }
+    let result = reg_utils::run(&mut args);
+    if let Err(err) = &result {
+        if err.kind == ErrorKind::BadArguments {
+            match args.get(1).map(String::as_str) {
+                Some("known-folder") => eprintln!("{}", usage_known_folder(&args[0])),
+                _ => eprintln!("{}", usage(&args[0])),
+            }
         }
-        arg1 => {
-            eprintln!("Unknown command: {}", arg1);
-            help(&args[0]);
+    }
+    let json = output::envelope(&result);
+    match (&result, pretty) {
+        // One item per line for list data, failures stay enveloped
+        (Ok(data), _) if lines && data.is_array() => {
+            for item in data.members() {
+                println!("{}", item.dump());
+            }
         }
+        (_, true) => println!("{}", json.pretty(2)),
+        (_, false) => println!("{}", json.dump()),
     }
-    Ok(())
+    std::process::exit(if result.is_ok() { 0 } else { 1 })
 }
@@ -0,0 +1,79 @@
+use json::{object, JsonValue};
+use std::io;
+
+/// Bumped when the envelope, an error kind or the data of a command changes incompatibly.
+pub const VERSION: u32 = 1;
+
+/// The error kinds callers can rely on, as listed in `schema/envelope.schema.json`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorKind {
+    /// A registry key or value, file or folder that does not exist.
+    NotFound,
+    AccessDenied,
+    /// An unknown command, action or option, or a missing or malformed argument.
+    BadArguments,
+    /// The command is not available on this platform.
+    Unsupported,
+    /// A file or registry value that exists but cannot be read as expected.
+    InvalidData,
+    /// Any other I/O or system error.
+    Io,
+}
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::NotFound => "notFound",
+            Self::AccessDenied => "accessDenied",
+            Self::BadArguments => "badArguments",
+            Self::Unsupported => "unsupported",
+            Self::InvalidData => "invalidData",
+            Self::Io => "io",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+impl Error {
+    pub fn bad_arguments<S: Into<String>>(message: S) -> Self {
+        Self {
+            kind: ErrorKind::BadArguments,
+            message: message.into(),
+        }
+    }
+}
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        let kind = match err.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorKind::AccessDenied,
+            io::ErrorKind::InvalidInput => ErrorKind::BadArguments,
+            io::ErrorKind::Unsupported => ErrorKind::Unsupported,
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => ErrorKind::InvalidData,
+            _ => ErrorKind::Io,
+        };
+        Self {
+            kind,
+            message: err.to_string(),
+        }
+    }
+}
+impl From<&Error> for JsonValue {
+    fn from(err: &Error) -> JsonValue {
+        object! {
+            kind: err.kind.as_str(),
+            message: err.message.as_str(),
+        }
+    }
+}
+
+/// Wraps the result of a command in `{ ok, version, data | error }`.
+pub fn envelope(result: &Result<JsonValue, Error>) -> JsonValue {
+    match result {
+        Ok(data) => object! { ok: true, version: VERSION, data: data.clone() },
+        Err(err) => object! { ok: false, version: VERSION, error: err },
+    }
+}
@@ -0,0 +1,150 @@
+use crate::launch::{self, Family, PROFILE_DIRECTORY};
+use crate::urlfile::parse_ini;
+use json::{object, JsonValue};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Chromium based browsers by a word of their ID or program name, with their user data directory
+/// under LocalAppData on Windows and under the config home elsewhere.
+const CHROMIUM_DIRS: &[(&str, &str, &str)] = &[
+    ("msedge", "Microsoft/Edge/User Data", "microsoft-edge"),
+    (
+        "microsoft-edge",
+        "Microsoft/Edge/User Data",
+        "microsoft-edge",
+    ),
+    (
+        "brave",
+        "BraveSoftware/Brave-Browser/User Data",
+        "BraveSoftware/Brave-Browser",
+    ),
+    ("vivaldi", "Vivaldi/User Data", "vivaldi"),
+    ("chromium", "Chromium/User Data", "chromium"),
+    ("chrome", "Google/Chrome/User Data", "google-chrome"),
+];
+
+pub struct Profile {
+    /// The profile directory.
+    pub path: PathBuf,
+    /// The name shown in the browser's profile picker.
+    pub name: String,
+    /// The arguments selecting the profile, which go before the URL.
+    pub args: Vec<String>,
+    /// Whether the browser opens this profile when none is selected.
+    pub default: bool,
+}
+impl From<&Profile> for JsonValue {
+    fn from(profile: &Profile) -> JsonValue {
+        object! {
+            path: profile.path.to_string_lossy().into_owned(),
+            name: profile.name.as_str(),
+            args: profile.args.clone(),
+            default: profile.default,
+        }
+    }
+}
+impl Profile {
+    /// Whether `query` names this profile, by its directory name or its display name.
+    pub fn matches(&self, query: &str) -> bool {
+        self.name == query || self.path.file_name().is_some_and(|name| name == query)
+    }
+}
+
+/// Joins a path written with `/` to `root`, using the separators of the platform.
+fn join(root: &Path, relative: &str) -> PathBuf {
+    relative
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty())
+        .fold(root.to_path_buf(), |path, part| path.join(part))
+}
+
+/// The profiles listed in `profile.info_cache` of the `Local State` file in `user_data`.
+pub fn chromium(user_data: &Path) -> io::Result<Vec<Profile>> {
+    let text = fs::read_to_string(user_data.join("Local State"))?;
+    let state =
+        json::parse(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let profile = &state["profile"];
+    // A new window opens in the profile used last
+    let last_used = profile["last_used"].as_str().unwrap_or("Default");
+    let profiles = profile["info_cache"]
+        .entries()
+        .map(|(dir, info)| Profile {
+            path: user_data.join(dir),
+            name: info["name"].as_str().unwrap_or(dir).to_string(),
+            args: vec![format!("{}{}", PROFILE_DIRECTORY, dir)],
+            default: dir == last_used,
+        })
+        .collect();
+    Ok(profiles)
+}
+
+/// The profiles listed in `profiles.ini` in `root`.
+pub fn firefox(root: &Path) -> io::Result<Vec<Profile>> {
+    let groups = parse_ini(&fs::read_to_string(root.join("profiles.ini"))?);
+    // Since Firefox 67 every installation has its own default in an `Install` group
+    let install_default = groups
+        .iter()
+        .filter(|(name, _)| name.starts_with("Install"))
+        .find_map(|(_, group)| group.get("Default"));
+    let profiles = groups
+        .iter()
+        .filter(|(name, _)| name.starts_with("Profile"))
+        .filter_map(|(_, group)| {
+            let name = group.get("Name")?;
+            let path = group.get("Path")?;
+            let default = match install_default {
+                Some(default) => default == path,
+                None => group.get("Default").is_some_and(|value| value == "1"),
+            };
+            let path = match group.get("IsRelative").map(String::as_str) {
+                Some("0") => PathBuf::from(path),
+                _ => join(root, path),
+            };
+            Some(Profile {
+                path,
+                name: name.clone(),
+                args: vec!["-P".to_string(), name.clone()],
+                default,
+            })
+        })
+        .collect();
+    Ok(profiles)
+}
+
+/// Where the browser with this ID and command keeps its profiles.
+fn data_dir(id: &str, words: &[String]) -> Option<(Family, PathBuf)> {
+    let names = launch::program_names(id, words);
+    let has = |key: &str| names.iter().any(|name| name.contains(key));
+    let family = Family::classify(id, words);
+    match family {
+        Family::Firefox if has("firefox") => {
+            let root = match cfg!(windows) {
+                true => join(
+                    &crate::known_folder("RoamingAppData").ok()?,
+                    "Mozilla/Firefox",
+                ),
+                false => join(Path::new(&std::env::var_os("HOME")?), ".mozilla/firefox"),
+            };
+            Some((family, root))
+        }
+        Family::Chromium | Family::Edge => {
+            let (_, windows, xdg) = CHROMIUM_DIRS.iter().find(|(key, ..)| has(key))?;
+            let root = match cfg!(windows) {
+                true => join(&crate::known_folder("LocalAppData").ok()?, windows),
+                false => join(&crate::known_folder("RoamingAppData").ok()?, xdg),
+            };
+            Some((family, root))
+        }
+        _ => None,
+    }
+}
+
+/// The profiles of a registered browser, empty when it has none or keeps them elsewhere.
+pub fn find(id: &str, words: &[String]) -> Vec<Profile> {
+    let profiles = match data_dir(id, words) {
+        Some((Family::Firefox, root)) => firefox(&root),
+        Some((_, root)) => chromium(&root),
+        None => return Vec::new(),
+    };
+    profiles.unwrap_or_default()
+}
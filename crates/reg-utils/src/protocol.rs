@@ -0,0 +1,168 @@
+use crate::registry::{Hive, Key, Registry, RegistryMut, Value};
+use json::{object, JsonValue};
+use std::io;
+
+pub struct Handler {
+    pub scheme: String,
+    /// Command line run for a link, `%1` is replaced with the URL.
+    pub command: String,
+    pub name: String,
+    pub icon: Option<String>,
+}
+
+/// Where a scheme is handled, `handler` is a registry key or a desktop file ID.
+pub struct ProtocolStatus {
+    pub scheme: String,
+    pub registered: bool,
+    pub handler: Option<String>,
+    pub command: Option<String>,
+    pub words: Option<Vec<String>>,
+}
+impl From<ProtocolStatus> for JsonValue {
+    fn from(status: ProtocolStatus) -> JsonValue {
+        object! {
+            scheme: status.scheme,
+            registered: status.registered,
+            handler: status.handler,
+            command: status.command,
+            words: status.words,
+        }
+    }
+}
+
+/// Schemes are case-insensitive and limited to `ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )`.
+pub fn check_scheme(scheme: &str) -> io::Result<String> {
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    match valid {
+        true => Ok(scheme.to_ascii_lowercase()),
+        false => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid scheme: {}", scheme),
+        )),
+    }
+}
+
+/// Schemes of browsers and the system, which a handler must never take over.
+const RESERVED: &[&str] = &[
+    "about",
+    "blob",
+    "data",
+    "file",
+    "ftp",
+    "http",
+    "https",
+    "javascript",
+    "mailto",
+    "ms-settings",
+    "tel",
+    "ws",
+    "wss",
+];
+
+/// Fails for a scheme in [`RESERVED`].
+pub fn check_reserved(scheme: &str) -> io::Result<()> {
+    match RESERVED.contains(&&*scheme.to_ascii_lowercase()) {
+        true => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Reserved scheme: {}", scheme),
+        )),
+        false => Ok(()),
+    }
+}
+
+fn classes_path(scheme: &str) -> String {
+    format!(r"Software\Classes\{}", scheme)
+}
+
+/// Whether the program of `command` lives in `install`.
+fn runs_from(command: &str, install: &str) -> bool {
+    let program = shell_words::split(command)
+        .ok()
+        .and_then(|words| words.into_iter().next());
+    program.is_some_and(|program| {
+        crate::shortcut::strip_root(&program, install).is_some_and(|rest| !rest.is_empty())
+    })
+}
+
+/// Fails when the per-user key of `scheme` exists and does not run a program of `install`.
+fn check_owner<R: Registry>(reg: &R, scheme: &str, install: &str) -> io::Result<()> {
+    let Ok(key) = reg.open(Hive::CurrentUser, &classes_path(scheme)) else {
+        return Ok(());
+    };
+    let command: Option<String> = key
+        .open_subkey(r"shell\open\command")
+        .and_then(|key| key.get_value(""))
+        .ok();
+    match command.is_some_and(|command| runs_from(&command, install)) {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is registered by another app", scheme),
+        )),
+    }
+}
+
+/// Writes the per-user key of the scheme, replacing it only when it was written for `install`.
+pub fn register<R: RegistryMut>(reg: &mut R, handler: &Handler, install: &str) -> io::Result<()> {
+    check_reserved(&handler.scheme)?;
+    check_owner(reg, &handler.scheme, install)?;
+    let path = classes_path(&handler.scheme);
+    let name = format!("URL:{}", handler.name);
+    reg.delete_tree(Hive::CurrentUser, &path)?;
+    reg.set_value(Hive::CurrentUser, &path, "", Value::String(name))?;
+    reg.set_value(
+        Hive::CurrentUser,
+        &path,
+        "URL Protocol",
+        Value::String(String::new()),
+    )?;
+    if let Some(icon) = &handler.icon {
+        let path = format!(r"{}\DefaultIcon", path);
+        reg.set_value(Hive::CurrentUser, &path, "", Value::String(icon.clone()))?;
+    }
+    let path = format!(r"{}\shell\open\command", path);
+    reg.set_value(
+        Hive::CurrentUser,
+        &path,
+        "",
+        Value::String(handler.command.clone()),
+    )
+}
+
+/// Removes the per-user key of the scheme when it was written for `install`.
+pub fn unregister<R: RegistryMut>(reg: &mut R, scheme: &str, install: &str) -> io::Result<()> {
+    check_reserved(scheme)?;
+    check_owner(reg, scheme, install)?;
+    reg.delete_tree(Hive::CurrentUser, &classes_path(scheme))
+}
+
+/// The per-user registration, or the merged `HKEY_CLASSES_ROOT` one.
+pub fn status<R: Registry>(reg: &R, scheme: &str) -> ProtocolStatus {
+    let keys = [
+        (Hive::CurrentUser, classes_path(scheme)),
+        (Hive::ClassesRoot, scheme.to_string()),
+    ];
+    let found = keys.iter().find_map(|(hive, path)| {
+        let key = reg.open(*hive, path).ok()?;
+        key.get_raw_value("URL Protocol").ok()?;
+        let command = key.open_subkey(r"shell\open\command").ok()?.get_value("");
+        let hive = match hive {
+            Hive::CurrentUser => "HKEY_CURRENT_USER",
+            _ => "HKEY_CLASSES_ROOT",
+        };
+        Some((format!(r"{}\{}", hive, path), command.ok()))
+    });
+    let (handler, command) = found.unzip();
+    let command: Option<String> = command.flatten();
+    ProtocolStatus {
+        scheme: scheme.to_string(),
+        registered: handler.is_some(),
+        handler,
+        words: command
+            .as_deref()
+            .and_then(|command| shell_words::split(command).ok()),
+        command,
+    }
+}
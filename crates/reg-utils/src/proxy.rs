@@ -0,0 +1,227 @@
+use crate::registry::{Hive, Key, Registry};
+use crate::urlfile::parse_ini;
+use json::{object, JsonValue};
+
+const INTERNET_SETTINGS: &str = r"Software\Microsoft\Windows\CurrentVersion\Internet Settings";
+
+/// Proxy settings in one shape whatever they were read from, with proxies as URLs.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct Proxy {
+    /// Where the settings came from: registry, environment, gnome or kde.
+    pub source: Option<&'static str>,
+    /// The URL of a proxy auto-config script.
+    pub pac: Option<String>,
+    pub http: Option<String>,
+    pub https: Option<String>,
+    pub socks: Option<String>,
+    /// Hosts reached directly, as patterns like `*.example.com`, `192.168.0.0/16` or `<local>`.
+    pub bypass: Vec<String>,
+}
+impl Proxy {
+    /// `auto` with a PAC script, `manual` with a proxy, `direct` otherwise.
+    pub fn mode(&self) -> &'static str {
+        if self.pac.is_some() {
+            "auto"
+        } else if self.http.is_some() || self.https.is_some() || self.socks.is_some() {
+            "manual"
+        } else {
+            "direct"
+        }
+    }
+}
+impl From<&Proxy> for JsonValue {
+    fn from(proxy: &Proxy) -> JsonValue {
+        object! {
+            mode: proxy.mode(),
+            source: proxy.source,
+            pac: proxy.pac.as_deref(),
+            http: proxy.http.as_deref(),
+            https: proxy.https.as_deref(),
+            socks: proxy.socks.as_deref(),
+            bypass: proxy.bypass.clone(),
+        }
+    }
+}
+
+/// Adds `scheme` to an address without one, e.g. `127.0.0.1:7890`.
+fn with_scheme(address: &str, scheme: &str) -> Option<String> {
+    let address = address.trim().trim_end_matches('/');
+    match address {
+        "" => None,
+        address if address.contains("://") => Some(address.to_string()),
+        address => Some(format!("{}://{}", scheme, address)),
+    }
+}
+
+/// Parses `ProxyServer`, either one `host:port` for every protocol or a list like
+/// `http=host:port;https=host:port;socks=host:port`.
+pub fn parse_proxy_server(text: &str, proxy: &mut Proxy) {
+    if !text.contains('=') {
+        proxy.http = with_scheme(text, "http");
+        proxy.https = proxy.http.clone();
+        return;
+    }
+    for entry in text.split(';') {
+        let Some((protocol, address)) = entry.split_once('=') else {
+            continue;
+        };
+        match protocol.trim().to_ascii_lowercase().as_str() {
+            "http" => proxy.http = with_scheme(address, "http"),
+            // The connection to the proxy itself is plain HTTP
+            "https" => proxy.https = with_scheme(address, "http"),
+            // WinINet only speaks SOCKS4
+            "socks" => proxy.socks = with_scheme(address, "socks4"),
+            _ => {}
+        }
+    }
+}
+
+/// Splits a bypass list on `;` and `,`, the separators of Windows and of the other sources.
+pub fn parse_bypass(text: &str) -> Vec<String> {
+    text.split([';', ','])
+        .map(str::trim)
+        .filter(|host| !host.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Reads the current user's Internet Settings, which WinINet and most browsers follow.
+pub fn from_registry<R: Registry>(reg: &R) -> Proxy {
+    let Ok(key) = reg.open(Hive::CurrentUser, INTERNET_SETTINGS) else {
+        return Proxy::default();
+    };
+    let mut proxy = Proxy {
+        source: Some("registry"),
+        pac: key.get_value::<String>("AutoConfigURL").ok(),
+        ..Default::default()
+    };
+    if key
+        .get_value::<u32>("ProxyEnable")
+        .is_ok_and(|enable| enable != 0)
+    {
+        if let Ok(server) = key.get_value::<String>("ProxyServer") {
+            parse_proxy_server(&server, &mut proxy);
+        }
+        if let Ok(bypass) = key.get_value::<String>("ProxyOverride") {
+            proxy.bypass = parse_bypass(&bypass);
+        }
+    }
+    proxy
+}
+
+/// Reads `http_proxy`, `https_proxy`, `all_proxy` and `no_proxy`, the lowercase names first.
+///
+/// `None` when none of them is set.
+pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Option<Proxy> {
+    let get = |name: &str| {
+        var(name)
+            .or_else(|| var(&name.to_ascii_uppercase()))
+            .filter(|value| !value.trim().is_empty())
+    };
+    let mut proxy = Proxy {
+        source: Some("environment"),
+        http: get("http_proxy").and_then(|value| with_scheme(&value, "http")),
+        https: get("https_proxy").and_then(|value| with_scheme(&value, "http")),
+        bypass: get("no_proxy")
+            .map(|value| parse_bypass(&value))
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    if let Some(all) = get("all_proxy").and_then(|value| with_scheme(&value, "http")) {
+        if all.starts_with("socks") {
+            proxy.socks = Some(all);
+        } else {
+            proxy.http.get_or_insert_with(|| all.clone());
+            proxy.https.get_or_insert(all);
+        }
+    }
+    Some(proxy).filter(|proxy| proxy.mode() != "direct" || !proxy.bypass.is_empty())
+}
+
+/// Unquotes a GVariant string like `'manual'`.
+fn gvariant_string(value: &str) -> Option<&str> {
+    let value = value.trim();
+    value
+        .strip_prefix('\'')
+        .and_then(|value| value.strip_suffix('\''))
+        .or_else(|| value.strip_prefix('"')?.strip_suffix('"'))
+}
+
+/// Parses the output of `dconf dump /system/proxy/`, GNOME's `org.gnome.system.proxy` settings.
+pub fn parse_gnome(text: &str) -> Proxy {
+    let groups = parse_ini(text);
+    let get = |group: &str, key: &str| groups.get(group)?.get(key).map(String::as_str);
+    let mut proxy = Proxy {
+        source: Some("gnome"),
+        ..Default::default()
+    };
+    match get("/", "mode").and_then(gvariant_string) {
+        Some("auto") => {
+            proxy.pac = get("/", "autoconfig-url")
+                .and_then(gvariant_string)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string);
+        }
+        Some("manual") => {
+            let address = |group: &str, scheme: &str| {
+                let host = get(group, "host").and_then(gvariant_string)?;
+                let port = get(group, "port").map_or("0", str::trim);
+                match port {
+                    "0" => with_scheme(host, scheme),
+                    port => with_scheme(&format!("{}:{}", host, port), scheme),
+                }
+            };
+            proxy.http = address("http", "http");
+            proxy.https = address("https", "http");
+            proxy.socks = address("socks", "socks5");
+        }
+        Some(_) => return proxy,
+        // Nothing was ever set, e.g. outside of GNOME
+        None => return Proxy::default(),
+    }
+    if let Some(hosts) = get("/", "ignore-hosts") {
+        let hosts = hosts.trim().trim_start_matches('[').trim_end_matches(']');
+        proxy.bypass = hosts
+            .split(',')
+            .filter_map(gvariant_string)
+            .map(str::to_string)
+            .collect();
+    }
+    proxy
+}
+
+/// Parses KDE's `kioslaverc`, whose `[Proxy Settings]` hold proxies as `http://host:port` or as
+/// `http://host port`.
+pub fn parse_kde(text: &str) -> Proxy {
+    let groups = parse_ini(text);
+    let get = |key: &str| {
+        let value = groups.get("Proxy Settings")?.get(key)?.trim();
+        Some(value).filter(|value| !value.is_empty())
+    };
+    let address = |key: &str, scheme: &str| {
+        let value = get(key)?;
+        match value.rsplit_once(' ') {
+            Some((host, port)) if port != "0" => {
+                with_scheme(&format!("{}:{}", host.trim(), port), scheme)
+            }
+            Some((host, _)) => with_scheme(host, scheme),
+            None => with_scheme(value, scheme),
+        }
+    };
+    let mut proxy = Proxy {
+        source: Some("kde"),
+        ..Default::default()
+    };
+    // 0 is no proxy, 3 is WPAD and 4 the environment, which is read before
+    match get("ProxyType") {
+        Some("1") => {
+            proxy.http = address("httpProxy", "http");
+            proxy.https = address("httpsProxy", "http");
+            proxy.socks = address("socksProxy", "socks5");
+            proxy.bypass = get("NoProxyFor").map(parse_bypass).unwrap_or_default();
+        }
+        Some("2") => proxy.pac = get("Proxy Config Script").map(str::to_string),
+        _ => {}
+    }
+    proxy
+}
@@ -0,0 +1,251 @@
+use crate::registry::{Hive, Key, Registry, RegistryMut, Value};
+use std::collections::BTreeMap as Map;
+use std::io;
+use std::path::Path;
+
+/// An in-memory registry key. Key and value names are case-insensitive like the real registry.
+#[derive(Default)]
+pub struct Node {
+    name: String,
+    keys: Map<String, Node>,
+    values: Map<String, (String, Value)>,
+}
+
+static EMPTY: Node = Node {
+    name: String::new(),
+    keys: Map::new(),
+    values: Map::new(),
+};
+
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split('\\').filter(|name| !name.is_empty())
+}
+
+impl Node {
+    /// Opens the key at `path`, creating missing keys on the way.
+    pub fn create_subkey(&mut self, path: &str) -> &mut Node {
+        components(path).fold(self, |node, name| {
+            node.keys
+                .entry(name.to_lowercase())
+                .or_insert_with(|| Node {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+        })
+    }
+    pub fn delete_subkey(&mut self, path: &str) {
+        let mut names: Vec<&str> = components(path).collect();
+        let Some(last) = names.pop() else {
+            return;
+        };
+        let mut node = self;
+        for name in names {
+            match node.keys.get_mut(&name.to_lowercase()) {
+                Some(child) => node = child,
+                None => return,
+            }
+        }
+        node.keys.remove(&last.to_lowercase());
+    }
+    pub fn set_value(&mut self, name: &str, value: Value) {
+        self.values
+            .insert(name.to_lowercase(), (name.to_string(), value));
+    }
+    pub fn delete_value(&mut self, name: &str) {
+        self.values.remove(&name.to_lowercase());
+    }
+}
+
+impl<'a> Key for &'a Node {
+    fn open_subkey(&self, path: &str) -> io::Result<Self> {
+        let mut node: &'a Node = self;
+        for name in components(path) {
+            node = node
+                .keys
+                .get(&name.to_lowercase())
+                .ok_or(io::ErrorKind::NotFound)?;
+        }
+        Ok(node)
+    }
+    fn enum_keys(&self) -> Vec<String> {
+        self.keys.values().map(|node| node.name.clone()).collect()
+    }
+    fn get_raw_value(&self, name: &str) -> io::Result<Value> {
+        let (_, value) = self
+            .values
+            .get(&name.to_lowercase())
+            .ok_or(io::ErrorKind::NotFound)?;
+        Ok(value.clone())
+    }
+}
+
+/// A registry held in memory, filled by hand or loaded from a `.reg` export.
+#[derive(Default)]
+pub struct MemoryRegistry {
+    hives: Map<Hive, Node>,
+}
+
+impl Registry for MemoryRegistry {
+    type Key<'a> = &'a Node;
+    fn root(&self, hive: Hive) -> &Node {
+        self.hives.get(&hive).unwrap_or(&EMPTY)
+    }
+}
+
+impl RegistryMut for MemoryRegistry {
+    fn set_value(&mut self, hive: Hive, path: &str, name: &str, value: Value) -> io::Result<()> {
+        self.hive_mut(hive)
+            .create_subkey(path)
+            .set_value(name, value);
+        Ok(())
+    }
+    fn delete_value(&mut self, hive: Hive, path: &str, name: &str) -> io::Result<()> {
+        let root = self.hive_mut(hive);
+        let mut node = Some(root);
+        for key in components(path) {
+            node = node.and_then(|node| node.keys.get_mut(&key.to_lowercase()));
+        }
+        if let Some(node) = node {
+            node.delete_value(name);
+        }
+        Ok(())
+    }
+    fn delete_tree(&mut self, hive: Hive, path: &str) -> io::Result<()> {
+        self.hive_mut(hive).delete_subkey(path);
+        Ok(())
+    }
+}
+
+fn invalid_data(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid line: {}", line),
+    )
+}
+
+/// Parses a `"..."` string at the start of `input`, returning it and the rest of the input.
+fn parse_quoted(input: &str) -> Option<(String, &str)> {
+    let mut chars = input.strip_prefix('"')?.char_indices();
+    let mut output = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((output, &input[i + 2..])),
+            '\\' => output.push(chars.next()?.1),
+            c => output.push(c),
+        }
+    }
+    None
+}
+
+fn parse_hex(input: &str) -> Option<Vec<u8>> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|byte| !byte.is_empty())
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect()
+}
+
+fn parse_data(data: &str) -> Option<Value> {
+    if data.starts_with('"') {
+        let (string, rest) = parse_quoted(data)?;
+        return rest.trim().is_empty().then_some(Value::String(string));
+    }
+    if let Some(dword) = data.strip_prefix("dword:") {
+        return u32::from_str_radix(dword.trim(), 16).ok().map(Value::Dword);
+    }
+    if let Some(bytes) = data.strip_prefix("hex:") {
+        return parse_hex(bytes).map(Value::Binary);
+    }
+    let (vtype, bytes) = data.strip_prefix("hex(")?.split_once("):")?;
+    let vtype = u32::from_str_radix(vtype, 16).ok()?;
+    Some(Value::from_raw(vtype, &parse_hex(bytes)?))
+}
+
+impl MemoryRegistry {
+    pub fn hive_mut(&mut self, hive: Hive) -> &mut Node {
+        self.hives.entry(hive).or_default()
+    }
+
+    /// Parses the text of a `.reg` export (`REGEDIT4` or version 5.00).
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut reg = Self::default();
+        let mut current: Option<(Hive, String)> = None;
+        let mut logical = String::new();
+        for line in text.lines() {
+            let line = line.trim();
+            // Long hex values continue on the next line after a trailing backslash
+            if let Some(line) = line.strip_suffix('\\') {
+                logical.push_str(line);
+                continue;
+            }
+            logical.push_str(line);
+            let line = std::mem::take(&mut logical);
+            let line = line.as_str();
+
+            if line.is_empty()
+                || line.starts_with(';')
+                || line == "REGEDIT4"
+                || line.starts_with("Windows Registry Editor")
+            {
+                continue;
+            }
+            if let Some(path) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                let (delete, path) = match path.strip_prefix('-') {
+                    Some(path) => (true, path),
+                    None => (false, path),
+                };
+                let (hive, path) = path.split_once('\\').unwrap_or((path, ""));
+                let hive = Hive::parse(hive).ok_or_else(|| invalid_data(line))?;
+                if delete {
+                    reg.hive_mut(hive).delete_subkey(path);
+                    current = None;
+                } else {
+                    reg.hive_mut(hive).create_subkey(path);
+                    current = Some((hive, path.to_string()));
+                }
+                continue;
+            }
+
+            let Some((hive, path)) = &current else {
+                return Err(invalid_data(line));
+            };
+            let (name, rest) = match line.strip_prefix('@') {
+                Some(rest) => (String::new(), rest),
+                None => parse_quoted(line).ok_or_else(|| invalid_data(line))?,
+            };
+            let data = rest
+                .trim_start()
+                .strip_prefix('=')
+                .ok_or_else(|| invalid_data(line))?;
+            let key = reg.hive_mut(*hive).create_subkey(path);
+            match data.trim() {
+                "-" => key.delete_value(&name),
+                data => key.set_value(&name, parse_data(data).ok_or_else(|| invalid_data(line))?),
+            }
+        }
+        Ok(reg)
+    }
+
+    /// Loads a `.reg` file, either UTF-16LE with a BOM as written by regedit or UTF-8.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let text = match bytes.strip_prefix(&[0xFF, 0xFE]) {
+            Some(wide) => {
+                let wide: Vec<u16> = wide
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16(&wide).map_err(io::Error::other)?
+            }
+            None => {
+                let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+                String::from_utf8(bytes.to_vec()).map_err(io::Error::other)?
+            }
+        };
+        Self::parse(&text)
+    }
+}
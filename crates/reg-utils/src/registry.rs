@@ -0,0 +1,293 @@
+use crate::{BrowserInfo, SoftwareFilter, SoftwareInfo};
+use std::collections::BTreeMap as Map;
+use std::io;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Hive {
+    ClassesRoot,
+    CurrentUser,
+    LocalMachine,
+    Users,
+}
+impl Hive {
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match &*name.to_ascii_uppercase() {
+            "HKEY_CLASSES_ROOT" | "HKCR" => Self::ClassesRoot,
+            "HKEY_CURRENT_USER" | "HKCU" => Self::CurrentUser,
+            "HKEY_LOCAL_MACHINE" | "HKLM" => Self::LocalMachine,
+            "HKEY_USERS" | "HKU" => Self::Users,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    None,
+    String(String),
+    ExpandString(String),
+    Binary(Vec<u8>),
+    Dword(u32),
+    MultiString(Vec<String>),
+    Qword(u64),
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&wide)
+}
+
+impl Value {
+    /// Decodes the raw bytes of a value of the given `REG_*` type.
+    pub fn from_raw(vtype: u32, bytes: &[u8]) -> Self {
+        let string = || {
+            let string = decode_utf16(bytes);
+            string.trim_end_matches('\0').to_string()
+        };
+        match vtype {
+            0 => Self::None,
+            1 => Self::String(string()),
+            2 => Self::ExpandString(string()),
+            4 if bytes.len() >= 4 => {
+                Self::Dword(u32::from_le_bytes(bytes[..4].try_into().unwrap()))
+            }
+            7 => Self::MultiString(
+                string()
+                    .split('\0')
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            ),
+            11 if bytes.len() >= 8 => {
+                Self::Qword(u64::from_le_bytes(bytes[..8].try_into().unwrap()))
+            }
+            _ => Self::Binary(bytes.to_vec()),
+        }
+    }
+}
+
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Option<Self>;
+}
+impl FromValue for String {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::String(string) | Value::ExpandString(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+impl FromValue for u32 {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Dword(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+impl FromValue for u64 {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Dword(n) => Some(n as u64),
+            Value::Qword(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+impl FromValue for Vec<String> {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::MultiString(list) => Some(list),
+            _ => None,
+        }
+    }
+}
+
+/// Read access to a registry key, as implemented by `winreg` and by [`crate::regfile`].
+pub trait Key: Sized {
+    fn open_subkey(&self, path: &str) -> io::Result<Self>;
+    fn enum_keys(&self) -> Vec<String>;
+    /// Reads a value, the empty name is the default value.
+    fn get_raw_value(&self, name: &str) -> io::Result<Value>;
+
+    fn get_value<T: FromValue>(&self, name: &str) -> io::Result<T> {
+        T::from_value(self.get_raw_value(name)?).ok_or_else(|| io::ErrorKind::InvalidData.into())
+    }
+}
+
+pub trait Registry {
+    type Key<'a>: Key
+    where
+        Self: 'a;
+    fn root(&self, hive: Hive) -> Self::Key<'_>;
+
+    fn open(&self, hive: Hive, path: &str) -> io::Result<Self::Key<'_>> {
+        self.root(hive).open_subkey(path)
+    }
+}
+
+/// Write access for the keys reg-utils registers itself, creating missing keys on the way.
+pub trait RegistryMut: Registry {
+    fn set_value(&mut self, hive: Hive, path: &str, name: &str, value: Value) -> io::Result<()>;
+    /// Deletes a value, a missing key or value is not an error.
+    fn delete_value(&mut self, hive: Hive, path: &str, name: &str) -> io::Result<()>;
+    /// Deletes the key and everything below it, a missing key is not an error.
+    fn delete_tree(&mut self, hive: Hive, path: &str) -> io::Result<()>;
+}
+
+pub fn get_default_webbrowser_id<R: Registry>(reg: &R) -> io::Result<String> {
+    let path = r"SOFTWARE\Microsoft\Windows\Shell\Associations\UrlAssociations\http\UserChoice";
+    reg.open(Hive::CurrentUser, path)?.get_value("ProgId")
+}
+pub fn get_webbrowser_command<R: Registry>(reg: &R, prog_id: &str) -> io::Result<String> {
+    reg.open(Hive::ClassesRoot, prog_id)?
+        .open_subkey(r"shell\open\command")?
+        .get_value("")
+}
+pub fn get_default_webbrowser<R: Registry>(reg: &R) -> io::Result<BrowserInfo> {
+    let prog_id = get_default_webbrowser_id(reg)?;
+    let command = get_webbrowser_command(reg, &prog_id)?;
+    Ok(BrowserInfo::new(prog_id, command))
+}
+
+// 枚举安装的浏览器(https://www.cnblogs.com/talenth/p/14049927.html)
+pub fn collect_webbrowser<R: Registry>(reg: &R) -> Map<String, String> {
+    let path = (
+        r"SOFTWARE\Wow6432Node\Clients\StartMenuInternet",
+        r"SOFTWARE\Clients\StartMenuInternet",
+    );
+    let keys = [
+        reg.open(Hive::LocalMachine, path.0),
+        reg.open(Hive::LocalMachine, path.1),
+        reg.open(Hive::CurrentUser, path.0),
+        reg.open(Hive::CurrentUser, path.1),
+    ];
+    keys.iter()
+        .map(Result::as_ref)
+        .filter_map(Result::ok)
+        .flat_map(|key| {
+            key.enum_keys().into_iter().map(|name: String| {
+                let key = key.open_subkey(&name)?;
+                let name = key.get_value("")?;
+                let path = r"Capabilities\URLAssociations";
+                let id = key.open_subkey(path)?.get_value("https")?;
+                io::Result::Ok((id, name))
+            })
+        })
+        .filter_map(Result::ok)
+        .collect()
+}
+pub fn collect_webbrowser_info<R: Registry>(reg: &R) -> Map<String, BrowserInfo> {
+    collect_webbrowser(reg)
+        .into_iter()
+        .map(|(id, name)| {
+            let command = get_webbrowser_command(reg, &id)?;
+            io::Result::Ok((id, BrowserInfo::new(name, command)))
+        })
+        .filter_map(Result::ok)
+        .collect()
+}
+
+fn install_date(date: String) -> String {
+    match date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) {
+        true => format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..]),
+        false => date,
+    }
+}
+
+fn read_software<K: Key>(key: &K, architecture: Option<&'static str>) -> io::Result<SoftwareInfo> {
+    let text = |name: &str| {
+        let value: Option<String> = key.get_value(name).ok();
+        value.filter(|value| !value.trim().is_empty())
+    };
+    Ok(SoftwareInfo {
+        name: text("DisplayName").ok_or(io::ErrorKind::NotFound)?,
+        version: text("DisplayVersion"),
+        path: text("InstallLocation"),
+        publisher: text("Publisher"),
+        install_date: text("InstallDate").map(install_date),
+        estimated_size: key.get_value("EstimatedSize").ok(),
+        uninstall_string: text("UninstallString"),
+        display_icon: text("DisplayIcon"),
+        architecture,
+        system_component: key
+            .get_value::<u32>("SystemComponent")
+            .is_ok_and(|n| n != 0),
+    })
+}
+
+/// The architecture of the native registry view, from the machine's `PROCESSOR_ARCHITECTURE`.
+/// Without it a machine with a 32-bit view is taken as x64 and one without as x86.
+fn native_architecture<R: Registry>(reg: &R) -> &'static str {
+    let path = r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+    let architecture: Option<String> = reg
+        .open(Hive::LocalMachine, path)
+        .and_then(|key| key.get_value("PROCESSOR_ARCHITECTURE"))
+        .ok();
+    let wow64 = reg
+        .open(Hive::LocalMachine, r"SOFTWARE\WOW6432Node")
+        .is_ok();
+    match architecture
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+        .as_str()
+    {
+        "AMD64" => "x64",
+        "ARM64" => "arm64",
+        "X86" => "x86",
+        _ if wow64 => "x64",
+        _ => "x86",
+    }
+}
+
+/// Reads the uninstall entries of both registry views of the machine and of the current user,
+/// keyed by hive, view and subkey like `HKLM\WOW6432Node\{id}`.
+/// Entries registered more than once with the same name, version and publisher are kept once.
+pub fn get_installed_software<R: Registry>(
+    reg: &R,
+    filter: &SoftwareFilter,
+) -> Map<String, SoftwareInfo> {
+    let path = (
+        r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+        r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+    );
+    let native = Some(native_architecture(reg));
+    let keys = [
+        ("HKLM", reg.open(Hive::LocalMachine, path.0), native),
+        (
+            r"HKLM\WOW6432Node",
+            reg.open(Hive::LocalMachine, path.1),
+            Some("x86"),
+        ),
+        ("HKCU", reg.open(Hive::CurrentUser, path.0), None),
+        (
+            r"HKCU\WOW6432Node",
+            reg.open(Hive::CurrentUser, path.1),
+            None,
+        ),
+    ];
+    let mut seen = std::collections::BTreeSet::new();
+    keys.iter()
+        .filter_map(|(root, key, architecture)| Some((*root, key.as_ref().ok()?, *architecture)))
+        .flat_map(|(root, key, architecture)| {
+            key.enum_keys().into_iter().map(move |id: String| {
+                let info = read_software(&key.open_subkey(&id)?, architecture)?;
+                io::Result::Ok((format!(r"{}\{}", root, id), info))
+            })
+        })
+        .filter_map(Result::ok)
+        .filter(|(_, info)| filter.matches(info))
+        .filter(|(_, info)| {
+            let lower = |text: &Option<String>| text.as_deref().map(str::to_lowercase);
+            let identity = (
+                info.name.to_lowercase(),
+                lower(&info.version),
+                lower(&info.publisher),
+            );
+            seen.insert(identity)
+        })
+        .collect()
+}
@@ -0,0 +1,126 @@
+use crate::output::{Error, ErrorKind, VERSION};
+use crate::{COMMANDS, PLATFORM};
+use json::{object, JsonValue};
+use std::io::{self, BufRead, Write};
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+/// Every other error kind, which is given in `error.data.kind`.
+const SERVER_ERROR: i32 = -32000;
+
+/// The commands and where they work, the platform this build serves and the envelope version.
+fn capabilities() -> JsonValue {
+    let mut commands = JsonValue::new_object();
+    for (name, platforms) in COMMANDS {
+        commands[*name] = object! {
+            platforms: *platforms,
+            supported: platforms.contains(&PLATFORM),
+        };
+    }
+    object! {
+        version: VERSION,
+        platform: PLATFORM,
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        commands: commands,
+    }
+}
+
+fn error_object(code: i32, err: &Error) -> JsonValue {
+    object! {
+        code: code,
+        message: err.message.as_str(),
+        data: { kind: err.kind.as_str() },
+    }
+}
+
+/// Answers one request line, `None` for notifications.
+fn handle(line: &str, extra: &[String]) -> Option<JsonValue> {
+    let reply = |id: JsonValue, result: Result<JsonValue, JsonValue>| {
+        Some(match result {
+            Ok(result) => object! { jsonrpc: "2.0", id: id, result: result },
+            Err(error) => object! { jsonrpc: "2.0", id: id, error: error },
+        })
+    };
+    let request = match json::parse(line) {
+        Ok(request) => request,
+        Err(err) => {
+            let err = Error {
+                kind: ErrorKind::BadArguments,
+                message: err.to_string(),
+            };
+            return reply(JsonValue::Null, Err(error_object(PARSE_ERROR, &err)));
+        }
+    };
+    let id = request["id"].clone();
+    let notification = !request.has_key("id");
+    let invalid = |message: &str| {
+        Err(error_object(
+            INVALID_REQUEST,
+            &Error::bad_arguments(message),
+        ))
+    };
+    let result = match (request["method"].as_str(), &request["params"]) {
+        _ if !request.is_object() => invalid("Request is not an object"),
+        (None, _) => invalid("Missing method"),
+        (Some("capabilities"), _) => Ok(capabilities()),
+        (Some(method), _) if COMMANDS.iter().all(|(name, _)| *name != method) => {
+            let err = Error::bad_arguments(format!("Unknown method: {}", method));
+            Err(error_object(METHOD_NOT_FOUND, &err))
+        }
+        (Some(method), params) if params.is_null() || params.is_array() => {
+            let params: Option<Vec<String>> = params
+                .members()
+                .map(|param| param.as_str().map(str::to_string))
+                .collect();
+            match params {
+                Some(params) => {
+                    let mut args = vec!["reg-utils".to_string(), method.to_string()];
+                    args.extend(params);
+                    args.extend_from_slice(extra);
+                    crate::run(&mut args).map_err(|err| {
+                        let code = match err.kind {
+                            ErrorKind::BadArguments => INVALID_PARAMS,
+                            _ => SERVER_ERROR,
+                        };
+                        error_object(code, &err)
+                    })
+                }
+                None => invalid("Params must be strings"),
+            }
+        }
+        _ => invalid("Params must be an array of command line arguments"),
+    };
+    match notification {
+        true => None,
+        false => reply(id, result),
+    }
+}
+
+/// Answers JSON-RPC 2.0 requests, one per line on stdin, until it is closed.
+///
+/// `method` is a command and `params` its arguments, e.g. `["Desktop"]` for `known-folder`.
+/// Requests run concurrently, so responses come back in completion order. `extra` is appended
+/// to the arguments of every request, e.g. `--reg <file.reg>`.
+pub fn serve(extra: &[String]) -> io::Result<()> {
+    let stdin = io::stdin();
+    std::thread::scope(|scope| {
+        for line in stdin.lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            scope.spawn(move || {
+                if let Some(response) = handle(&line, extra) {
+                    let mut stdout = io::stdout().lock();
+                    // A closed stdout leaves nobody to answer
+                    let _ = writeln!(stdout, "{}", response.dump()).and_then(|_| stdout.flush());
+                }
+            });
+        }
+        // Requests still running finish before the scope returns
+        Ok(())
+    })
+}
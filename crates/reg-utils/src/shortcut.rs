@@ -0,0 +1,434 @@
+use crate::lnk::{self, ShellLink, ShowCommand, RUN_AS_USER};
+use json::{object, JsonValue};
+use std::io;
+use std::path::Path;
+
+fn invalid_input<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+/// What `shortcut` does when `savePath` already exists.
+#[derive(Clone, Copy, Default)]
+pub enum Overwrite {
+    Fail,
+    #[default]
+    Replace,
+    Keep,
+}
+impl TryFrom<&str> for Overwrite {
+    type Error = io::Error;
+    fn try_from(overwrite: &str) -> Result<Self, Self::Error> {
+        Ok(match overwrite {
+            "fail" => Self::Fail,
+            "replace" => Self::Replace,
+            "keep" => Self::Keep,
+            _ => {
+                return Err(invalid_input(format!(
+                    "Unknown overwrite policy: {}",
+                    overwrite
+                )))
+            }
+        })
+    }
+}
+
+impl Overwrite {
+    pub fn status(self, exists: bool) -> Status {
+        match (exists, self) {
+            (false, _) => Status::Created,
+            (true, Self::Replace) => Status::Replaced,
+            (true, Self::Keep) => Status::Kept,
+            (true, Self::Fail) => Status::Exists,
+        }
+    }
+}
+
+/// What happened to the file, `Exists` is reported when the policy is `fail`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Created,
+    Replaced,
+    Kept,
+    Exists,
+}
+impl Status {
+    pub fn writes(self) -> bool {
+        matches!(self, Self::Created | Self::Replaced)
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Replaced => "replaced",
+            Self::Kept => "kept",
+            Self::Exists => "exists",
+        }
+    }
+}
+
+/// Builds the link described by the `shortcut` JSON options.
+pub fn from_options(data: &JsonValue) -> io::Result<ShellLink> {
+    let string = |key: &str| data[key].as_str().map(str::to_string);
+    let target = string("targetPath").ok_or_else(|| invalid_input("Missing targetPath"))?;
+    let mut link = ShellLink::new(&target);
+    if link.id_list.is_none() && link.link_info.is_none() {
+        return Err(invalid_input(format!("Not an absolute path: {}", target)));
+    }
+    link.arguments = string("arguments");
+    link.working_dir = string("workingDir");
+    link.name = string("description");
+    link.icon_location = string("iconPath");
+    link.icon_index = data["iconIndex"].as_i32().unwrap_or_default();
+    if let Some(show) = data["showCommand"].as_str() {
+        link.show_command = ShowCommand::try_from(show)?;
+    }
+    if let Some(hotkey) = data["hotkey"].as_str() {
+        link.hotkey = lnk::parse_hotkey(hotkey)?;
+    }
+    if data["runAsAdmin"].as_bool().unwrap_or_default() {
+        link.flags |= RUN_AS_USER;
+    }
+    Ok(link)
+}
+
+/// Writes the shortcut and describes the outcome.
+pub fn create(data: &JsonValue) -> io::Result<JsonValue> {
+    let save_path = data["savePath"]
+        .as_str()
+        .ok_or_else(|| invalid_input("Missing savePath"))?;
+    let overwrite = match data["overwrite"].as_str() {
+        Some(overwrite) => Overwrite::try_from(overwrite)?,
+        None => Overwrite::default(),
+    };
+    let link = from_options(data)?;
+    let status = overwrite.status(Path::new(save_path).exists());
+    if status.writes() {
+        link.save(save_path)?;
+    }
+    Ok(object! {
+        savePath: save_path,
+        status: status.as_str(),
+        shortcut: status.writes().then(|| JsonValue::from(&link)),
+    })
+}
+
+/// Description of the shortcuts the app creates.
+const NAME: &str = "Metadata Fetcher";
+
+/// Options of the Send To item, Explorer passes the selected paths to `script`.
+pub fn send_to(script: &str, icon: &str, save_path: &str) -> JsonValue {
+    let working_dir = script.rsplit_once(['\\', '/']).map(|(dir, _)| dir);
+    object! {
+        targetPath: script,
+        savePath: save_path,
+        workingDir: working_dir,
+        iconPath: icon,
+        description: NAME,
+        showCommand: "minimized",
+    }
+}
+
+/// Files of an install, relative to its root, that our shortcuts point at.
+const INSTALL_MARKERS: &[&str] = &["run.bat", r"dist\favicon.ico", r"dist\reg-utils.exe"];
+/// Files only an install has, a `run.bat` alone may belong to any app.
+const DIST_MARKERS: &[&str] = &[r"dist\reg-utils.exe", r"dist\favicon.ico"];
+
+/// Strips `root` from the start of `path`, comparing like Windows paths.
+pub(crate) fn strip_root<'a>(path: &'a str, root: &str) -> Option<&'a str> {
+    let root = root.trim_end_matches(['\\', '/']);
+    let head = path.get(..root.len())?;
+    let rest = &path[root.len()..];
+    let same = head
+        .chars()
+        .zip(root.chars())
+        .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()) || (a == '/' && b == '\\'));
+    (same && (rest.is_empty() || rest.starts_with(['\\', '/']))).then_some(rest)
+}
+
+/// The install root of a path to one of the [`INSTALL_MARKERS`].
+fn install_root(path: &str) -> Option<&str> {
+    INSTALL_MARKERS.iter().find_map(|marker| {
+        let cut = path.len().checked_sub(marker.len())?;
+        let (root, tail) = (path.get(..cut)?, path.get(cut..)?);
+        let root = root.strip_suffix(['\\', '/'])?;
+        let same = tail.replace('/', "\\").eq_ignore_ascii_case(marker);
+        (same && !root.is_empty()).then_some(root)
+    })
+}
+
+/// `marker` under `root`, with the separators `root` uses.
+fn join_marker(root: &str, marker: &str) -> String {
+    let sep = if root.contains('/') && !root.contains('\\') {
+        "/"
+    } else {
+        "\\"
+    };
+    format!("{}{}{}", root, sep, marker.replace('\\', sep))
+}
+
+/// Whether a shortcut into `root` is one of ours: we created it, or the root is an install.
+fn is_install(link: &ShellLink, root: &str, exists: &dyn Fn(&str) -> bool) -> bool {
+    link.name.as_deref() == Some(NAME)
+        || DIST_MARKERS
+            .iter()
+            .any(|marker| exists(&join_marker(root, marker)))
+}
+
+fn rebase(path: &mut Option<String>, from: &str, to: &str) {
+    if let Some(rest) = path.as_deref().and_then(|path| strip_root(path, from)) {
+        *path = Some(format!("{}{}", to.trim_end_matches(['\\', '/']), rest));
+    }
+}
+
+/// Known folders where installs leave shortcuts.
+pub const LINK_FOLDERS: &[&str] = &[
+    "Desktop",
+    "PublicDesktop",
+    "Documents",
+    "SendTo",
+    "StartMenu",
+    "Startup",
+];
+
+fn collect_links(dir: &Path, recursive: bool, output: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive {
+                collect_links(&path, recursive, output);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"))
+        {
+            output.push(path);
+        }
+    }
+}
+
+/// Finds the shortcuts pointing into an install and, with `fix`, moves stale ones to `install`.
+///
+/// A shortcut is `stale` when its target is gone, `other` when it points at another existing
+/// install and `ok` when it points at `install`. Stale ones are only moved when their target
+/// exists in `install`, and `error` tells why one could not be saved.
+pub fn check(folders: &[(String, String)], install: &str, fix: bool) -> Vec<JsonValue> {
+    let mut links = Vec::new();
+    for (name, dir) in folders {
+        let mut paths = Vec::new();
+        collect_links(Path::new(dir), name == "StartMenu", &mut paths);
+        links.extend(paths.into_iter().map(|path| (name.as_str(), path)));
+    }
+    let exists = |path: &str| Path::new(path).exists();
+    links
+        .into_iter()
+        .filter_map(|(folder, path)| {
+            let link = ShellLink::load(&path).ok()?;
+            let mut json = check_link(link, &path, install, fix, &exists)?;
+            json["folder"] = folder.into();
+            Some(json)
+        })
+        .collect()
+}
+
+fn check_link(
+    mut link: ShellLink,
+    path: &Path,
+    install: &str,
+    fix: bool,
+    exists: &dyn Fn(&str) -> bool,
+) -> Option<JsonValue> {
+    let target = link.target();
+    let icon = link.icon_location.clone();
+    let root = [&target, &icon]
+        .iter()
+        .find_map(|path| install_root(path.as_deref()?))?
+        .to_string();
+    if !is_install(&link, &root, exists) {
+        return None;
+    }
+    let status = match target.as_deref() {
+        Some(target) if exists(target) => {
+            match strip_root(&root, install).is_some_and(str::is_empty) {
+                true => "ok",
+                false => "other",
+            }
+        }
+        _ => "stale",
+    };
+    let mut new_target = target.clone();
+    rebase(&mut new_target, &root, install);
+    let mut error = None;
+    let fixed = match new_target {
+        Some(new_target) if fix && status == "stale" && exists(&new_target) => {
+            link.set_target(&new_target);
+            rebase(&mut link.icon_location, &root, install);
+            rebase(&mut link.working_dir, &root, install);
+            match link.save(path) {
+                Ok(()) => true,
+                Err(err) => {
+                    error = Some(err.to_string());
+                    false
+                }
+            }
+        }
+        _ => false,
+    };
+    Some(object! {
+        path: path.to_string_lossy().into_owned(),
+        folder: null,
+        target: target,
+        icon: icon,
+        root: root,
+        status: status,
+        fixed: fixed,
+        error: error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTALL: &str = r"C:\Metadata Fetcher";
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("reg-utils-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A stale link into `root`, with its icon and working directory there too.
+    fn link(root: &str, name: Option<&str>) -> ShellLink {
+        let mut link = ShellLink::new(&format!(r"{}\run.bat", root));
+        link.name = name.map(str::to_string);
+        link.icon_location = Some(format!(r"{}\app.ico", root));
+        link.working_dir = Some(root.to_string());
+        link
+    }
+
+    #[test]
+    fn strip_root_cases() {
+        assert_eq!(strip_root(r"C:\Foo\run.bat", r"c:\foo"), Some(r"\run.bat"));
+        assert_eq!(strip_root("C:/Foo/run.bat", r"C:\Foo\"), Some("/run.bat"));
+        assert_eq!(strip_root(r"C:\Foo", r"C:\Foo"), Some(""));
+        assert_eq!(strip_root(r"C:\Foobar\run.bat", r"C:\Foo"), None);
+        assert_eq!(strip_root(r"C:\Fo", r"C:\Foo"), None);
+        assert_eq!(strip_root(r"D:\Foo\run.bat", r"C:\Foo"), None);
+    }
+
+    #[test]
+    fn install_root_cases() {
+        assert_eq!(install_root(r"C:\MF\run.bat"), Some(r"C:\MF"));
+        assert_eq!(install_root(r"C:\MF\RUN.BAT"), Some(r"C:\MF"));
+        assert_eq!(install_root(r"C:\MF\dist\favicon.ico"), Some(r"C:\MF"));
+        assert_eq!(install_root("/opt/mf/dist/reg-utils.exe"), Some("/opt/mf"));
+        assert_eq!(install_root(r"C:\MF\notrun.bat"), None);
+        assert_eq!(install_root(r"C:\MF\favicon.ico"), None);
+        assert_eq!(install_root(r"\run.bat"), None);
+        assert_eq!(install_root("run.bat"), None);
+    }
+
+    #[test]
+    fn rebase_cases() {
+        let mut path = Some(r"C:\Old\dist\favicon.ico".to_string());
+        rebase(&mut path, r"c:\old", r"D:\New\");
+        assert_eq!(path.as_deref(), Some(r"D:\New\dist\favicon.ico"));
+        let mut path = Some(r"C:\Other\run.bat".to_string());
+        rebase(&mut path, r"C:\Old", r"D:\New");
+        assert_eq!(path.as_deref(), Some(r"C:\Other\run.bat"));
+        let mut path = None;
+        rebase(&mut path, r"C:\Old", r"D:\New");
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn check_link_cases() {
+        let dir = temp_dir("check-link");
+        let path = dir.join("link.lnk");
+        let new_target = format!(r"{}\run.bat", INSTALL);
+        let existing: Vec<String> = vec![
+            new_target.clone(),
+            join_marker(INSTALL, r"dist\favicon.ico"),
+            join_marker(r"C:\Portable MF", r"dist\reg-utils.exe"),
+            r"C:\Portable MF\run.bat".to_string(),
+        ];
+        let exists = |path: &str| existing.iter().any(|existing| existing == path);
+
+        // A `run.bat` of another app is left alone, even when our install has one
+        let other_app = link(r"C:\Games\OtherApp", None);
+        assert!(check_link(other_app, &path, INSTALL, true, &exists).is_none());
+        assert!(!path.exists());
+
+        // Ours by its description, moved with its icon and working directory
+        let json = check_link(link(r"C:\Old", Some(NAME)), &path, INSTALL, true, &exists);
+        let json = json.unwrap();
+        assert_eq!(json["root"], r"C:\Old");
+        assert_eq!(json["status"], "stale");
+        assert_eq!(json["fixed"], true);
+        assert_eq!(json["error"], JsonValue::Null);
+        let saved = ShellLink::load(&path).unwrap();
+        assert_eq!(saved.target().as_deref(), Some(&*new_target));
+        assert_eq!(
+            saved.icon_location.as_deref(),
+            Some(r"C:\Metadata Fetcher\app.ico")
+        );
+        assert_eq!(saved.working_dir.as_deref(), Some(INSTALL));
+
+        // Not moved to an install without the target
+        let json = check_link(link(r"C:\Old", Some(NAME)), &path, r"D:\MF", true, &exists);
+        assert_eq!(json.unwrap()["fixed"], false);
+
+        // Ours by the files of its root, which still exists
+        let json = check_link(link(r"C:\Portable MF", None), &path, INSTALL, true, &exists);
+        let json = json.unwrap();
+        assert_eq!(json["status"], "other");
+        assert_eq!(json["fixed"], false);
+        let json = check_link(link(INSTALL, None), &path, INSTALL, false, &exists);
+        assert_eq!(json.unwrap()["status"], "ok");
+
+        // Save errors are reported
+        let missing = dir.join("missing").join("link.lnk");
+        let json = check_link(
+            link(r"C:\Old", Some(NAME)),
+            &missing,
+            INSTALL,
+            true,
+            &exists,
+        );
+        let json = json.unwrap();
+        assert_eq!(json["fixed"], false);
+        assert!(json["error"].is_string());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_folders() {
+        let dir = temp_dir("check-folders");
+        link(r"C:\Games\OtherApp", None)
+            .save(dir.join("Other.lnk"))
+            .unwrap();
+        link(r"C:\Old", Some(NAME))
+            .save(dir.join("Metadata Fetcher.lnk"))
+            .unwrap();
+        let folders = [("Desktop".to_string(), dir.to_string_lossy().into_owned())];
+        let found = check(&folders, INSTALL, true);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["folder"], "Desktop");
+        assert_eq!(
+            found[0]["path"],
+            dir.join("Metadata Fetcher.lnk").to_str().unwrap()
+        );
+        assert_eq!(found[0]["status"], "stale");
+        // The new target is not on this disk
+        assert_eq!(found[0]["fixed"], false);
+        let other = ShellLink::load(dir.join("Other.lnk")).unwrap();
+        assert_eq!(
+            other.target().as_deref(),
+            Some(r"C:\Games\OtherApp\run.bat")
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
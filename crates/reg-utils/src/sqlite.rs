@@ -0,0 +1,52 @@
+use rusqlite::Connection;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fs, io};
+
+pub fn to_io_error(err: rusqlite::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Creates a directory only the owner may list, a directory planted at `path` is never reused.
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        fs::DirBuilder::new().mode(0o700).create(path)
+    }
+    #[cfg(not(unix))]
+    fs::create_dir(path)
+}
+
+/// Opens a copy of the database `name` in `dir` and runs `read` on it.
+///
+/// Browsers keep their databases open, often locked, with recent writes in the write-ahead log,
+/// so both are copied to a temporary directory and only the copy is opened. The copy may hold
+/// session tokens and is removed before returning; the original is never written.
+pub fn read_copy<T>(
+    dir: &Path,
+    name: &str,
+    read: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+) -> io::Result<T> {
+    if !dir.join(name).is_file() {
+        let message = format!("No {} in {}", name, dir.display());
+        return Err(io::Error::new(io::ErrorKind::NotFound, message));
+    }
+    // `serve` may read several databases at once
+    static COPIES: AtomicUsize = AtomicUsize::new(0);
+    let copy_id = COPIES.fetch_add(1, Ordering::Relaxed);
+    let temp = std::env::temp_dir().join(format!("reg-utils-{}-{}", std::process::id(), copy_id));
+    create_private_dir(&temp)?;
+    let wal = format!("{}-wal", name);
+    let result = fs::copy(dir.join(name), temp.join(name))
+        .and_then(|_| match fs::copy(dir.join(&wal), temp.join(&wal)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        })
+        .and_then(|_| {
+            let db = Connection::open(temp.join(name)).map_err(to_io_error)?;
+            read(&db).map_err(to_io_error)
+        });
+    let _ = fs::remove_dir_all(&temp);
+    result
+}
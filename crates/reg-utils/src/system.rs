@@ -0,0 +1,293 @@
+use crate::registry::{Hive, Key, Registry};
+use json::{object, JsonValue};
+use std::fs;
+use std::path::Path;
+
+const CURRENT_VERSION: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion";
+const TIME_ZONE: &str = r"SYSTEM\CurrentControlSet\Control\TimeZoneInformation";
+
+/// The `territory="001"` entries of CLDR's `windowsZones.xml`, Windows zone name to IANA zone.
+pub const WINDOWS_ZONES: &[(&str, &str)] = &[
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("UTC-11", "Etc/GMT+11"),
+    ("Aleutian Standard Time", "America/Adak"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Marquesas Standard Time", "Pacific/Marquesas"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("UTC-09", "Etc/GMT+9"),
+    ("Pacific Standard Time (Mexico)", "America/Tijuana"),
+    ("UTC-08", "Etc/GMT+8"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time (Mexico)", "America/Mazatlan"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Yukon Standard Time", "America/Whitehorse"),
+    ("Central America Standard Time", "America/Guatemala"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Easter Island Standard Time", "Pacific/Easter"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Eastern Standard Time (Mexico)", "America/Cancun"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Haiti Standard Time", "America/Port-au-Prince"),
+    ("Cuba Standard Time", "America/Havana"),
+    ("US Eastern Standard Time", "America/Indianapolis"),
+    ("Turks And Caicos Standard Time", "America/Grand_Turk"),
+    ("Paraguay Standard Time", "America/Asuncion"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("Venezuela Standard Time", "America/Caracas"),
+    ("Central Brazilian Standard Time", "America/Cuiaba"),
+    ("SA Western Standard Time", "America/La_Paz"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("Tocantins Standard Time", "America/Araguaina"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("SA Eastern Standard Time", "America/Cayenne"),
+    ("Argentina Standard Time", "America/Buenos_Aires"),
+    ("Greenland Standard Time", "America/Godthab"),
+    ("Montevideo Standard Time", "America/Montevideo"),
+    ("Magallanes Standard Time", "America/Punta_Arenas"),
+    ("Saint Pierre Standard Time", "America/Miquelon"),
+    ("Bahia Standard Time", "America/Bahia"),
+    ("UTC-02", "Etc/GMT+2"),
+    ("Azores Standard Time", "Atlantic/Azores"),
+    ("Cape Verde Standard Time", "Atlantic/Cape_Verde"),
+    ("UTC", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("Sao Tome Standard Time", "Africa/Sao_Tome"),
+    ("Morocco Standard Time", "Africa/Casablanca"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("Jordan Standard Time", "Asia/Amman"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("Middle East Standard Time", "Asia/Beirut"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("Syria Standard Time", "Asia/Damascus"),
+    ("West Bank Standard Time", "Asia/Hebron"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("FLE Standard Time", "Europe/Kiev"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("South Sudan Standard Time", "Africa/Juba"),
+    ("Kaliningrad Standard Time", "Europe/Kaliningrad"),
+    ("Sudan Standard Time", "Africa/Khartoum"),
+    ("Libya Standard Time", "Africa/Tripoli"),
+    ("Namibia Standard Time", "Africa/Windhoek"),
+    ("Arabic Standard Time", "Asia/Baghdad"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("Belarus Standard Time", "Europe/Minsk"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("E. Africa Standard Time", "Africa/Nairobi"),
+    ("Volgograd Standard Time", "Europe/Volgograd"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Astrakhan Standard Time", "Europe/Astrakhan"),
+    ("Azerbaijan Standard Time", "Asia/Baku"),
+    ("Russia Time Zone 3", "Europe/Samara"),
+    ("Mauritius Standard Time", "Indian/Mauritius"),
+    ("Saratov Standard Time", "Europe/Saratov"),
+    ("Georgian Standard Time", "Asia/Tbilisi"),
+    ("Caucasus Standard Time", "Asia/Yerevan"),
+    ("Afghanistan Standard Time", "Asia/Kabul"),
+    ("West Asia Standard Time", "Asia/Tashkent"),
+    ("Qyzylorda Standard Time", "Asia/Qyzylorda"),
+    ("Ekaterinburg Standard Time", "Asia/Yekaterinburg"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("India Standard Time", "Asia/Calcutta"),
+    ("Sri Lanka Standard Time", "Asia/Colombo"),
+    ("Nepal Standard Time", "Asia/Katmandu"),
+    ("Central Asia Standard Time", "Asia/Bishkek"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("Omsk Standard Time", "Asia/Omsk"),
+    ("Myanmar Standard Time", "Asia/Rangoon"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("Altai Standard Time", "Asia/Barnaul"),
+    ("W. Mongolia Standard Time", "Asia/Hovd"),
+    ("North Asia Standard Time", "Asia/Krasnoyarsk"),
+    ("N. Central Asia Standard Time", "Asia/Novosibirsk"),
+    ("Tomsk Standard Time", "Asia/Tomsk"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("North Asia East Standard Time", "Asia/Irkutsk"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("Ulaanbaatar Standard Time", "Asia/Ulaanbaatar"),
+    ("Aus Central W. Standard Time", "Australia/Eucla"),
+    ("Transbaikal Standard Time", "Asia/Chita"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("North Korea Standard Time", "Asia/Pyongyang"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Yakutsk Standard Time", "Asia/Yakutsk"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("AUS Central Standard Time", "Australia/Darwin"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("West Pacific Standard Time", "Pacific/Port_Moresby"),
+    ("Tasmania Standard Time", "Australia/Hobart"),
+    ("Vladivostok Standard Time", "Asia/Vladivostok"),
+    ("Lord Howe Standard Time", "Australia/Lord_Howe"),
+    ("Bougainville Standard Time", "Pacific/Bougainville"),
+    ("Russia Time Zone 10", "Asia/Srednekolymsk"),
+    ("Magadan Standard Time", "Asia/Magadan"),
+    ("Norfolk Standard Time", "Pacific/Norfolk"),
+    ("Sakhalin Standard Time", "Asia/Sakhalin"),
+    ("Central Pacific Standard Time", "Pacific/Guadalcanal"),
+    ("Russia Time Zone 11", "Asia/Kamchatka"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+    ("UTC+12", "Etc/GMT-12"),
+    ("Fiji Standard Time", "Pacific/Fiji"),
+    ("Chatham Islands Standard Time", "Pacific/Chatham"),
+    ("UTC+13", "Etc/GMT-13"),
+    ("Tonga Standard Time", "Pacific/Tongatapu"),
+    ("Samoa Standard Time", "Pacific/Apia"),
+    ("Line Islands Standard Time", "Pacific/Kiritimati"),
+];
+
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct SystemInfo {
+    /// The product name, e.g. `Windows 11 Pro` or `Ubuntu`.
+    pub name: Option<String>,
+    /// The release, e.g. `23H2` or `24.04`.
+    pub version: Option<String>,
+    pub build: Option<String>,
+    /// The update build revision, the number after the build in `winver`.
+    pub ubr: Option<u32>,
+    /// The user interface language as a BCP 47 tag, e.g. `zh-CN`.
+    pub language: Option<String>,
+    /// The IANA time zone, e.g. `Asia/Shanghai`.
+    pub time_zone: Option<String>,
+    /// The Windows time zone name the IANA one was converted from.
+    pub windows_time_zone: Option<String>,
+}
+impl From<&SystemInfo> for JsonValue {
+    fn from(info: &SystemInfo) -> JsonValue {
+        object! {
+            name: info.name.as_deref(),
+            version: info.version.as_deref(),
+            build: info.build.as_deref(),
+            ubr: info.ubr,
+            language: info.language.as_deref(),
+            timeZone: info.time_zone.as_deref(),
+            windowsTimeZone: info.windows_time_zone.as_deref(),
+        }
+    }
+}
+
+/// Converts a Windows time zone name like `China Standard Time` to its IANA zone.
+pub fn iana_time_zone(windows: &str) -> Option<&'static str> {
+    WINDOWS_ZONES
+        .iter()
+        .find(|(name, _)| *name == windows)
+        .map(|(_, zone)| *zone)
+}
+
+/// Reads the version, UI language and time zone from the registry.
+pub fn from_registry<R: Registry>(reg: &R) -> SystemInfo {
+    let mut info = SystemInfo::default();
+    if let Ok(key) = reg.open(Hive::LocalMachine, CURRENT_VERSION) {
+        let string = |name: &str| key.get_value::<String>(name).ok();
+        info.build = string("CurrentBuild").or_else(|| string("CurrentBuildNumber"));
+        info.version = string("DisplayVersion").or_else(|| string("ReleaseId"));
+        info.ubr = key.get_value::<u32>("UBR").ok();
+        info.name = string("ProductName").map(|name| {
+            let build: u32 = info
+                .build
+                .as_deref()
+                .and_then(|build| build.parse().ok())
+                .unwrap_or(0);
+            // Windows 11 kept the product name of Windows 10
+            match name.strip_prefix("Windows 10") {
+                Some(edition) if build >= 22000 => format!("Windows 11{}", edition),
+                _ => name,
+            }
+        });
+    }
+    // Set when the display language was changed, otherwise cached from the installed one
+    let desktop = reg.open(Hive::CurrentUser, r"Control Panel\Desktop");
+    let cached = reg.open(Hive::CurrentUser, r"Control Panel\Desktop\MuiCached");
+    let languages = [
+        desktop.and_then(|key| key.get_value::<Vec<String>>("PreferredUILanguages")),
+        cached.and_then(|key| key.get_value::<Vec<String>>("MachinePreferredUILanguages")),
+    ];
+    info.language = languages
+        .into_iter()
+        .find_map(|languages| languages.ok()?.into_iter().next())
+        .or_else(|| {
+            reg.open(Hive::CurrentUser, r"Control Panel\International")
+                .and_then(|key| key.get_value::<String>("LocaleName"))
+                .ok()
+        });
+    info.windows_time_zone = reg
+        .open(Hive::LocalMachine, TIME_ZONE)
+        .and_then(|key| key.get_value::<String>("TimeZoneKeyName"))
+        .ok();
+    info.time_zone = info
+        .windows_time_zone
+        .as_deref()
+        .and_then(iana_time_zone)
+        .map(str::to_string);
+    info
+}
+
+/// Parses `os-release`, whose values may be quoted like shell words.
+pub fn parse_os_release(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let value = shell_words::split(value).ok()?.join(" ");
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// The IANA zone of a `/etc/localtime` link target, e.g. `/usr/share/zoneinfo/Asia/Shanghai`.
+pub fn zone_from_link(target: &Path) -> Option<String> {
+    let target = target.to_str()?;
+    let (_, zone) = target.rsplit_once("zoneinfo/")?;
+    // Some distributions link into `posix/` or `right/` variants of the database
+    let zone = zone
+        .strip_prefix("posix/")
+        .or_else(|| zone.strip_prefix("right/"))
+        .unwrap_or(zone);
+    Some(zone.to_string()).filter(|zone| !zone.is_empty())
+}
+
+/// Reads `etc/os-release` and `etc/localtime` under `root`, which is `/` outside of tests.
+/// `locale` is like `zh_CN`, without the encoding.
+pub fn from_files(root: &Path, locale: Option<&str>) -> SystemInfo {
+    let text = fs::read_to_string(root.join("etc/os-release"))
+        .or_else(|_| fs::read_to_string(root.join("usr/lib/os-release")))
+        .unwrap_or_default();
+    let fields = parse_os_release(&text);
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    let time_zone = fs::read_link(root.join("etc/localtime"))
+        .ok()
+        .and_then(|target| zone_from_link(&target))
+        // Debian also names the zone in a file
+        .or_else(|| {
+            let zone = fs::read_to_string(root.join("etc/timezone")).ok()?;
+            Some(zone.trim().to_string()).filter(|zone| !zone.is_empty())
+        });
+    SystemInfo {
+        name: field("NAME"),
+        version: field("VERSION_ID"),
+        build: field("BUILD_ID"),
+        ubr: None,
+        language: locale.map(|locale| locale.replace('_', "-")),
+        time_zone,
+        windows_time_zone: None,
+    }
+}
@@ -0,0 +1,368 @@
+use crate::lnk;
+use crate::shortcut::Overwrite;
+use json::{object, JsonValue};
+use std::collections::BTreeMap as Map;
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+const SECTION: &str = "InternetShortcut";
+/// Windows repeats values that do not fit the ANSI code page here, encoded as UTF-7.
+const SECTION_W: &str = "InternetShortcut.W";
+/// Keys written from the fields of [`InternetShortcut`], which `properties` may not repeat.
+const FIELD_KEYS: &[&str] = &["URL", "IconFile", "IconIndex", "HotKey"];
+
+fn invalid_input<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Characters UTF-7 writes as themselves, the rest go in `+...-` base64 runs of UTF-16.
+fn utf7_direct(c: char) -> bool {
+    c.is_ascii_alphanumeric() || " \t\r\n'(),-./:?!\"#$%&*;<=>@[]^_`{|}".contains(c)
+}
+
+pub fn encode_utf7(text: &str) -> String {
+    let mut output = String::new();
+    let mut run: Vec<u16> = Vec::new();
+    let flush = |run: &mut Vec<u16>, output: &mut String| {
+        if run.is_empty() {
+            return;
+        }
+        let bytes: Vec<u8> = run.drain(..).flat_map(u16::to_be_bytes).collect();
+        output.push('+');
+        for chunk in bytes.chunks(3) {
+            let n = chunk.iter().fold(0u32, |n, &b| n << 8 | b as u32) << (8 * (3 - chunk.len()));
+            for i in 0..=chunk.len() {
+                output.push(BASE64[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            }
+        }
+        output.push('-');
+    };
+    for c in text.chars() {
+        match c {
+            '+' => {
+                flush(&mut run, &mut output);
+                output.push_str("+-");
+            }
+            c if utf7_direct(c) => {
+                flush(&mut run, &mut output);
+                output.push(c);
+            }
+            c => run.extend(c.encode_utf16(&mut [0; 2]).iter()),
+        }
+    }
+    flush(&mut run, &mut output);
+    output
+}
+
+pub fn decode_utf7(text: &str) -> String {
+    let mut output = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '+' {
+            output.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'-') {
+            chars.next();
+            output.push('+');
+            continue;
+        }
+        let (mut bits, mut count, mut wide) = (0u32, 0, Vec::new());
+        while let Some(&c) = chars.peek() {
+            let Some(value) = BASE64.iter().position(|&b| b as char == c) else {
+                break;
+            };
+            chars.next();
+            bits = bits << 6 | value as u32;
+            count += 6;
+            if count >= 16 {
+                count -= 16;
+                wide.push((bits >> count) as u16);
+                bits &= (1 << count) - 1;
+            }
+        }
+        if chars.peek() == Some(&'-') {
+            chars.next();
+        }
+        output.push_str(&String::from_utf16_lossy(&wide));
+    }
+    output
+}
+
+/// Percent-encodes what does not belong in the ASCII `URL=` line.
+fn ascii_url(url: &str) -> String {
+    let mut output = String::new();
+    for c in url.chars() {
+        match c {
+            ' ' => output.push_str("%20"),
+            c if c.is_ascii() && !c.is_ascii_control() => output.push(c),
+            c => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    output.push_str(&format!("%{:02X}", b));
+                }
+            }
+        }
+    }
+    output
+}
+
+/// A file name for the title, without the characters and names Windows rejects.
+pub fn file_name(title: &str) -> String {
+    const RESERVED: &[&str] = &["CON", "PRN", "AUX", "NUL"];
+    let name: String = title
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    // Leaves room for the extension and a long directory within MAX_PATH
+    let name: String = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name: String = name.chars().take(120).collect();
+    let name = name.trim_end_matches(['.', ' ']);
+    let upper = name.to_ascii_uppercase();
+    let reserved = RESERVED.contains(&upper.as_str())
+        || ["COM", "LPT"]
+            .iter()
+            .any(|prefix| upper.len() == 4 && upper.starts_with(prefix));
+    match (name.is_empty(), reserved) {
+        (true, _) => "_".to_string(),
+        (false, true) => format!("_{}", name),
+        (false, false) => name.to_string(),
+    }
+}
+
+#[derive(Default)]
+pub struct InternetShortcut {
+    pub url: String,
+    pub icon_file: Option<String>,
+    pub icon_index: Option<i32>,
+    pub hotkey: Option<u16>,
+    /// Every other `[InternetShortcut]` property, e.g. `WorkingDirectory` or `ShowCommand`.
+    pub properties: Map<String, String>,
+}
+
+pub(crate) fn parse_ini(text: &str) -> Map<String, Map<String, String>> {
+    let mut groups: Map<String, Map<String, String>> = Map::new();
+    let mut current = None;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            current = Some(name.to_string());
+            continue;
+        }
+        let (Some(group), Some((key, value))) = (&current, line.split_once('=')) else {
+            continue;
+        };
+        let group = groups.entry(group.clone()).or_default();
+        group
+            .entry(key.trim().to_string())
+            .or_insert_with(|| value.trim().to_string());
+    }
+    groups
+}
+
+impl InternetShortcut {
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut groups = parse_ini(text);
+        let wide = groups.remove(SECTION_W).unwrap_or_default();
+        let mut section = groups.remove(SECTION).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "Not an Internet Shortcut")
+        })?;
+        let mut take = |key: &str| {
+            let value = section.remove(key);
+            wide.get(key).map(|value| decode_utf7(value)).or(value)
+        };
+        let url =
+            take("URL").ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing URL"))?;
+        let icon_file = take("IconFile").filter(|icon| !icon.is_empty());
+        let icon_index = take("IconIndex").and_then(|index| index.parse().ok());
+        let hotkey = take("HotKey")
+            .and_then(|hotkey| hotkey.parse().ok())
+            .filter(|&hotkey| hotkey != 0);
+        let properties = section
+            .into_iter()
+            .map(|(key, value)| {
+                let value = wide
+                    .get(&key)
+                    .map(|value| decode_utf7(value))
+                    .unwrap_or(value);
+                (key, value)
+            })
+            .collect();
+        Ok(Self {
+            url,
+            icon_file,
+            icon_index,
+            hotkey,
+            properties,
+        })
+    }
+
+    /// Rejects keys and values that would break the lines of the file or repeat a field.
+    pub fn check(&self) -> io::Result<()> {
+        let single_line = |name: &str, value: &str| match value.contains(['\r', '\n', '\0']) {
+            true => Err(invalid_input(format!("{} must be a single line", name))),
+            false => Ok(()),
+        };
+        single_line("url", &self.url)?;
+        if let Some(icon_file) = &self.icon_file {
+            single_line("iconFile", icon_file)?;
+        }
+        for (key, value) in &self.properties {
+            let invalid = key.is_empty()
+                || key.trim() != key
+                || key.starts_with([';', '['])
+                || key.contains(['=', '\r', '\n', '\0']);
+            if invalid {
+                return Err(invalid_input(format!("Invalid property name: {:?}", key)));
+            }
+            // Windows reads the keys case-insensitively
+            if let Some(field) = FIELD_KEYS
+                .iter()
+                .find(|field| field.eq_ignore_ascii_case(key))
+            {
+                return Err(invalid_input(format!(
+                    "{} is not a property, it has its own option",
+                    field
+                )));
+            }
+            single_line(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a `.url` file, which is ANSI or, when written by some tools, UTF-8 or UTF-16LE.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let text = match bytes.strip_prefix(&[0xFF, 0xFE]) {
+            Some(wide) => {
+                let wide: Vec<u16> = wide
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16_lossy(&wide)
+            }
+            None => {
+                let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+        };
+        Self::parse(&text)
+    }
+}
+
+/// Encodes the file in ASCII, with a UTF-7 `[InternetShortcut.W]` copy of non-ASCII values.
+///
+/// Only shortcuts that pass [`InternetShortcut::check`] are written back as they were.
+impl fmt::Display for InternetShortcut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines = vec![format!("[{}]", SECTION)];
+        let mut wide = Vec::new();
+        let mut push = |key: &str, value: &str| {
+            let ascii = match key {
+                "URL" => ascii_url(value),
+                _ => value
+                    .chars()
+                    .map(|c| if c.is_ascii() { c } else { '?' })
+                    .collect(),
+            };
+            if ascii != value {
+                wide.push(format!("{}={}", key, encode_utf7(value)));
+            }
+            lines.push(format!("{}={}", key, ascii));
+        };
+        push("URL", &self.url);
+        for (key, value) in &self.properties {
+            push(key, value);
+        }
+        if let Some(icon_file) = &self.icon_file {
+            push("IconFile", icon_file);
+            push(
+                "IconIndex",
+                &self.icon_index.unwrap_or_default().to_string(),
+            );
+        }
+        if let Some(hotkey) = self.hotkey {
+            push("HotKey", &hotkey.to_string());
+        }
+        if !wide.is_empty() {
+            lines.push(format!("[{}]", SECTION_W));
+            lines.extend(wide);
+        }
+        lines.push(String::new());
+        f.write_str(&lines.join("\r\n"))
+    }
+}
+
+impl From<&InternetShortcut> for JsonValue {
+    fn from(shortcut: &InternetShortcut) -> JsonValue {
+        let mut properties = JsonValue::new_object();
+        for (key, value) in &shortcut.properties {
+            properties[key.as_str()] = value.as_str().into();
+        }
+        object! {
+            url: shortcut.url.as_str(),
+            iconFile: shortcut.icon_file.as_deref(),
+            iconIndex: shortcut.icon_index,
+            hotkey: shortcut.hotkey.and_then(lnk::format_hotkey),
+            properties: properties,
+        }
+    }
+}
+
+/// Writes the `.url` file described by the `url-shortcut` JSON options.
+///
+/// The file goes to `savePath`, or to `dir` named after `title`.
+pub fn create(data: &JsonValue) -> io::Result<JsonValue> {
+    let string = |key: &str| data[key].as_str().map(str::to_string);
+    let url = string("url").ok_or_else(|| invalid_input("Missing url"))?;
+    let save_path = match (string("savePath"), string("dir")) {
+        (Some(save_path), _) => PathBuf::from(save_path),
+        (None, Some(dir)) => {
+            let title = string("title").unwrap_or_else(|| url.clone());
+            Path::new(&dir).join(format!("{}.url", file_name(&title)))
+        }
+        (None, None) => return Err(invalid_input("Missing savePath or dir")),
+    };
+    let overwrite = match data["overwrite"].as_str() {
+        Some(overwrite) => Overwrite::try_from(overwrite)?,
+        None => Overwrite::default(),
+    };
+    let mut properties = Map::new();
+    for (key, value) in data["properties"].entries() {
+        let value = match value.as_str() {
+            Some(value) => value.to_string(),
+            None => value.dump(),
+        };
+        properties.insert(key.to_string(), value);
+    }
+    let shortcut = InternetShortcut {
+        url,
+        icon_file: string("iconFile"),
+        icon_index: data["iconIndex"].as_i32(),
+        hotkey: string("hotkey")
+            .map(|hotkey| lnk::parse_hotkey(&hotkey))
+            .transpose()?,
+        properties,
+    };
+    shortcut.check()?;
+    let status = overwrite.status(save_path.exists());
+    if status.writes() {
+        std::fs::write(&save_path, shortcut.to_string())?;
+    }
+    Ok(object! {
+        savePath: save_path.to_string_lossy().into_owned(),
+        status: status.as_str(),
+        shortcut: status.writes().then(|| JsonValue::from(&shortcut)),
+    })
+}
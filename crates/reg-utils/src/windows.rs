@@ -0,0 +1,214 @@
+use crate::autostart::{self, Entry, Method};
+use crate::explorer::{self, Verb};
+use crate::locate::{self, Locator, ToolInfo};
+use crate::protocol::{self, Handler, ProtocolStatus};
+use crate::proxy::{self, Proxy};
+use crate::registry::{self, Hive, Key, Registry, RegistryMut, Value};
+use crate::system::{self, SystemInfo};
+use crate::{BrowserInfo, SoftwareFilter, SoftwareInfo};
+use json::JsonValue;
+use std::collections::BTreeMap as Map;
+use std::io;
+use std::path::PathBuf;
+use windows_sys::core::{GUID, PWSTR};
+use windows_sys::Win32::UI::Shell;
+use winreg::{enums as e, RegKey, RegValue};
+
+impl Key for RegKey {
+    fn open_subkey(&self, path: &str) -> io::Result<Self> {
+        RegKey::open_subkey(self, path)
+    }
+    fn enum_keys(&self) -> Vec<String> {
+        RegKey::enum_keys(self).filter_map(Result::ok).collect()
+    }
+    fn get_raw_value(&self, name: &str) -> io::Result<Value> {
+        let value = RegKey::get_raw_value(self, name)?;
+        Ok(Value::from_raw(value.vtype as u32, &value.bytes))
+    }
+}
+
+/// The live registry through `winreg`.
+pub struct System;
+
+impl Registry for System {
+    type Key<'a> = RegKey;
+    fn root(&self, hive: Hive) -> RegKey {
+        RegKey::predef(match hive {
+            Hive::ClassesRoot => e::HKEY_CLASSES_ROOT,
+            Hive::CurrentUser => e::HKEY_CURRENT_USER,
+            Hive::LocalMachine => e::HKEY_LOCAL_MACHINE,
+            Hive::Users => e::HKEY_USERS,
+        })
+    }
+}
+
+/// The raw value, the inverse of [`Value::from_raw`].
+fn to_reg_value(value: Value) -> RegValue {
+    let wide = |string: &str| -> Vec<u8> {
+        string
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    };
+    let (vtype, bytes) = match value {
+        Value::None => (e::REG_NONE, Vec::new()),
+        Value::String(string) => (e::REG_SZ, wide(&string)),
+        Value::ExpandString(string) => (e::REG_EXPAND_SZ, wide(&string)),
+        Value::Binary(bytes) => (e::REG_BINARY, bytes),
+        Value::Dword(n) => (e::REG_DWORD, n.to_le_bytes().to_vec()),
+        Value::MultiString(list) => {
+            let mut bytes: Vec<u8> = list.iter().flat_map(|item| wide(item)).collect();
+            bytes.extend([0, 0]);
+            (e::REG_MULTI_SZ, bytes)
+        }
+        Value::Qword(n) => (e::REG_QWORD, n.to_le_bytes().to_vec()),
+    };
+    RegValue { bytes, vtype }
+}
+
+impl RegistryMut for System {
+    fn set_value(&mut self, hive: Hive, path: &str, name: &str, value: Value) -> io::Result<()> {
+        let (key, _) = self.root(hive).create_subkey(path)?;
+        key.set_raw_value(name, &to_reg_value(value))
+    }
+    fn delete_value(&mut self, hive: Hive, path: &str, name: &str) -> io::Result<()> {
+        let key = self
+            .root(hive)
+            .open_subkey_with_flags(path, e::KEY_SET_VALUE);
+        match key.and_then(|key| key.delete_value(name)) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+    fn delete_tree(&mut self, hive: Hive, path: &str) -> io::Result<()> {
+        match self.root(hive).delete_subkey_all(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+pub fn get_default_webbrowser_id() -> io::Result<String> {
+    registry::get_default_webbrowser_id(&System)
+}
+pub fn get_default_webbrowser() -> io::Result<BrowserInfo> {
+    registry::get_default_webbrowser(&System)
+}
+pub fn proxy() -> Proxy {
+    proxy::from_registry(&System)
+}
+pub fn system_info() -> SystemInfo {
+    system::from_registry(&System)
+}
+pub fn collect_webbrowser_info() -> Map<String, BrowserInfo> {
+    registry::collect_webbrowser_info(&System)
+}
+pub fn register_protocol(handler: &Handler) -> io::Result<()> {
+    let install = crate::install_dir()?;
+    protocol::register(&mut System, handler, &install.to_string_lossy())
+}
+pub fn unregister_protocol(scheme: &str) -> io::Result<()> {
+    let install = crate::install_dir()?;
+    protocol::unregister(&mut System, scheme, &install.to_string_lossy())
+}
+pub fn protocol_status(scheme: &str) -> ProtocolStatus {
+    protocol::status(&System, scheme)
+}
+pub fn install_context_menu(verb: &Verb) -> io::Result<()> {
+    explorer::install(&mut System, verb)
+}
+pub fn uninstall_context_menu() -> io::Result<()> {
+    explorer::uninstall(&mut System)
+}
+pub fn context_menu_status() -> io::Result<JsonValue> {
+    Ok(explorer::status(&System))
+}
+fn startup_folder() -> Option<PathBuf> {
+    known_folder("Startup").ok()
+}
+pub fn enable_autostart(method: Method, script: &str) -> io::Result<()> {
+    autostart::enable(&mut System, startup_folder().as_deref(), method, script)
+}
+pub fn disable_autostart() -> io::Result<()> {
+    autostart::disable(&mut System, startup_folder().as_deref())
+}
+pub fn autostart_status() -> Vec<Entry> {
+    autostart::status(&System, startup_folder().as_deref())
+}
+pub fn locate(locator: &Locator, name: &str) -> Vec<ToolInfo> {
+    locate::locate(locator, Some(&System), name)
+}
+pub fn get_installed_software(filter: &SoftwareFilter) -> Map<String, SoftwareInfo> {
+    registry::get_installed_software(&System, filter)
+}
+
+macro_rules! known_folder_id {
+    [$($x:ident),+ $(,)?] => (
+        pub static KNOWN_FOLDER_ID_LIST: &[&'static str] = &[$(stringify!($x)),+];
+        fn known_folder_id(input: &str) -> Option<&GUID> {
+            Some(match input {
+                $(stringify!($x) => {
+                    mashup!{ m[$x] = FOLDERID_ $x; }
+                    &m! { Shell::$x }
+                })+
+                _ => return None
+            })
+        }
+    );
+}
+
+known_folder_id![
+    Desktop,
+    Documents,
+    Downloads,
+    Music,
+    Pictures,
+    Videos,
+    SendTo,
+    StartMenu,
+    Startup,
+    LocalAppData,
+    RoamingAppData,
+    ProgramData,
+    PublicDesktop,
+];
+
+unsafe fn len<T: Copy + Default + std::cmp::PartialEq>(ptr: *const T) -> usize {
+    use std::hint::unreachable_unchecked;
+    let default: T = Default::default();
+    for i in 0.. {
+        if unsafe { *ptr.add(i) } == default {
+            return i;
+        }
+    }
+    unsafe { unreachable_unchecked() }
+}
+
+pub fn known_folder(input: &str) -> io::Result<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::ptr::null_mut;
+    use windows_sys::Win32::System::Com::CoTaskMemFree;
+
+    let rfid = known_folder_id(input).ok_or_else(|| {
+        let message = format!("Unknown known folder: {}", input);
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    })?;
+    let mut path: PWSTR = null_mut();
+    let hr = unsafe { Shell::SHGetKnownFolderPath(rfid, 0, null_mut(), &mut path) };
+    let result = match hr {
+        0 => {
+            let wide = unsafe { std::slice::from_raw_parts(path, len(path)) };
+            Ok(PathBuf::from(OsString::from_wide(wide)))
+        }
+        // `HRESULT_FROM_WIN32` codes, so that e.g. a missing folder reads as `NotFound`
+        hr if hr as u32 & 0xFFFF_0000 == 0x8007_0000 => {
+            Err(io::Error::from_raw_os_error(hr & 0xFFFF))
+        }
+        hr => Err(io::Error::from_raw_os_error(hr)),
+    };
+    // The buffer must be freed whether or not the call succeeded
+    unsafe { CoTaskMemFree(path as *const _) };
+    result
+}
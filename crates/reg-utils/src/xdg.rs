@@ -0,0 +1,770 @@
+use crate::autostart::{Entry, Method, TRAY_ARG};
+use crate::explorer::Verb;
+use crate::locate::{self, Locator, ToolInfo};
+use crate::protocol::{self, Handler, ProtocolStatus};
+use crate::proxy::{self, Proxy};
+use crate::regfile::MemoryRegistry;
+use crate::system::{self, SystemInfo};
+use crate::{BrowserInfo, SoftwareFilter, SoftwareInfo};
+use json::JsonValue;
+use std::collections::BTreeMap as Map;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+/// The Windows names, `SendTo` and `PublicDesktop` have no counterpart here.
+pub static KNOWN_FOLDER_ID_LIST: &[&str] = &[
+    "Desktop",
+    "Documents",
+    "Downloads",
+    "Music",
+    "Pictures",
+    "Videos",
+    "SendTo",
+    "StartMenu",
+    "Startup",
+    "LocalAppData",
+    "RoamingAppData",
+    "ProgramData",
+    "PublicDesktop",
+];
+
+pub fn known_folder(input: &str) -> io::Result<PathBuf> {
+    Xdg::from_env().known_folder(input)
+}
+
+pub fn get_installed_software(_filter: &SoftwareFilter) -> Map<String, SoftwareInfo> {
+    Map::new()
+}
+
+fn no_explorer() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "No Explorer context menu here")
+}
+pub fn install_context_menu(_verb: &Verb) -> io::Result<()> {
+    Err(no_explorer())
+}
+pub fn uninstall_context_menu() -> io::Result<()> {
+    Err(no_explorer())
+}
+pub fn context_menu_status() -> io::Result<JsonValue> {
+    Err(no_explorer())
+}
+
+/// Without a registry only `PATH` and the per-user install directories are searched.
+pub fn locate(locator: &Locator, name: &str) -> Vec<ToolInfo> {
+    locate::locate(locator, None::<&MemoryRegistry>, name)
+}
+
+/// The XDG base directories, resolved once so lookups can be pointed at fixture trees.
+pub struct Xdg {
+    pub home: Option<PathBuf>,
+    pub data_home: Option<PathBuf>,
+    pub config_home: Option<PathBuf>,
+    pub data_dirs: Vec<PathBuf>,
+    pub config_dirs: Vec<PathBuf>,
+    /// Lowercased `XDG_CURRENT_DESKTOP` entries, for `<desktop>-mimeapps.list`.
+    pub desktops: Vec<String>,
+    /// Locale used for `Name[...]` lookups, e.g. `zh_CN`.
+    pub locale: Option<String>,
+    /// The system-wide Flatpak and Snap export directories, which are not always listed in
+    /// `XDG_DATA_DIRS`.
+    pub export_dirs: Vec<PathBuf>,
+}
+
+fn absolute(path: PathBuf) -> Option<PathBuf> {
+    Some(path).filter(|path| path.is_absolute())
+}
+
+impl Xdg {
+    pub fn from_env() -> Self {
+        let var = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+        let home = var("HOME").map(PathBuf::from);
+        let dir = |name: &str, default: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .and_then(absolute)
+                .or_else(|| home.as_ref().map(|home| home.join(default)))
+        };
+        let dirs = |name: &str, default: &str| -> Vec<PathBuf> {
+            let value = var(name).unwrap_or_else(|| default.into());
+            env::split_paths(&value).filter_map(absolute).collect()
+        };
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map(|value| {
+                let end = value.find(['.', '@']).unwrap_or(value.len());
+                value[..end].to_string()
+            })
+            .filter(|locale| locale != "C" && locale != "POSIX");
+        Self {
+            home: home.clone(),
+            data_home: dir("XDG_DATA_HOME", ".local/share"),
+            config_home: dir("XDG_CONFIG_HOME", ".config"),
+            data_dirs: dirs("XDG_DATA_DIRS", "/usr/local/share:/usr/share"),
+            config_dirs: dirs("XDG_CONFIG_DIRS", "/etc/xdg"),
+            desktops: env::var("XDG_CURRENT_DESKTOP")
+                .unwrap_or_default()
+                .split(':')
+                .filter(|desktop| !desktop.is_empty())
+                .map(str::to_lowercase)
+                .collect(),
+            locale,
+            export_dirs: vec![
+                "/var/lib/flatpak/exports/share".into(),
+                "/var/lib/snapd/desktop".into(),
+            ],
+        }
+    }
+
+    /// Data directories in priority order, including the per-user Flatpak export directory
+    /// and the [`Xdg::export_dirs`].
+    pub fn all_data_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        dirs.extend(self.data_home.clone());
+        if let Some(data_home) = &self.data_home {
+            dirs.push(data_home.join("flatpak/exports/share"));
+        }
+        dirs.extend(self.data_dirs.iter().cloned());
+        dirs.extend(self.export_dirs.iter().cloned());
+        let mut seen = std::collections::BTreeSet::new();
+        dirs.retain(|dir| seen.insert(dir.clone()));
+        dirs
+    }
+
+    /// `mimeapps.list` candidates in lookup order.
+    pub fn mimeapps_lists(&self) -> Vec<PathBuf> {
+        let names: Vec<String> = self
+            .desktops
+            .iter()
+            .map(|desktop| format!("{}-mimeapps.list", desktop))
+            .chain(["mimeapps.list".to_string()])
+            .collect();
+        let config = self.config_home.iter().chain(&self.config_dirs).cloned();
+        let data = self.data_home.iter().chain(&self.data_dirs);
+        let data = data.map(|dir| dir.join("applications"));
+        config
+            .chain(data)
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .collect()
+    }
+}
+
+fn unescape(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => output.push(' '),
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('r') => output.push('\r'),
+            Some('\\') => output.push('\\'),
+            Some(c) => {
+                output.push('\\');
+                output.push(c);
+            }
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+/// Parses a desktop entry style key file into `group -> key -> value`.
+pub fn parse_key_file(text: &str) -> Map<String, Map<String, String>> {
+    let mut groups: Map<String, Map<String, String>> = Map::new();
+    let mut group: Option<&mut Map<String, String>> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            group = Some(groups.entry(name.to_string()).or_default());
+            continue;
+        }
+        let (Some(group), Some((key, value))) = (group.as_deref_mut(), line.split_once('=')) else {
+            continue;
+        };
+        group
+            .entry(key.trim_end().to_string())
+            .or_insert_with(|| value.trim_start().to_string());
+    }
+    groups
+}
+
+/// Sets or, with `None`, removes a key of a key file, keeping the rest of the text as is.
+pub fn edit_key_file(text: &str, group: &str, key: &str, value: Option<&str>) -> String {
+    let header = format!("[{}]", group);
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let start = lines.iter().position(|line| line.trim() == header);
+    let start = match start {
+        Some(start) => start,
+        None if value.is_none() => return text.to_string(),
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.len() - 1
+        }
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |end| start + 1 + end);
+    let found = (start + 1..end).find(|&i| {
+        let line = &lines[i];
+        line.split_once('=')
+            .is_some_and(|(name, _)| name.trim_end() == key)
+    });
+    let line = value.map(|value| format!("{}={}", key, value));
+    match (found, line) {
+        (Some(i), Some(line)) => lines[i] = line,
+        (Some(i), None) => {
+            lines.remove(i);
+        }
+        (None, Some(line)) => {
+            // After the last entry, before any blank lines ending the group
+            let mut at = end;
+            while at > start + 1 && lines[at - 1].trim().is_empty() {
+                at -= 1;
+            }
+            lines.insert(at, line);
+        }
+        (None, None) => {}
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+pub struct DesktopEntry {
+    /// Desktop file ID, e.g. `org.mozilla.firefox.desktop`.
+    pub id: String,
+    pub path: PathBuf,
+    pub name: String,
+    pub exec: Option<String>,
+    pub icon: Option<String>,
+    pub mime_types: Vec<String>,
+}
+
+impl DesktopEntry {
+    pub fn parse(id: String, path: PathBuf, text: &str, locale: Option<&str>) -> Option<Self> {
+        let mut groups = parse_key_file(text);
+        let group = groups.remove("Desktop Entry")?;
+        let get = |key: &str| group.get(key).map(|value| unescape(value));
+        let flag = |key: &str| get(key).as_deref() == Some("true");
+        if get("Type").as_deref() != Some("Application") || flag("Hidden") {
+            return None;
+        }
+        // Name[ll_CC], then Name[ll], then Name
+        let localized = locale.into_iter().flat_map(|locale| {
+            let lang = locale.split('_').next().unwrap_or(locale);
+            [format!("Name[{}]", locale), format!("Name[{}]", lang)]
+        });
+        let name = localized
+            .chain(["Name".to_string()])
+            .find_map(|key| get(&key))?;
+        Some(Self {
+            id,
+            path,
+            name,
+            exec: get("Exec"),
+            icon: get("Icon").filter(|icon| !icon.is_empty()),
+            mime_types: get("MimeType")
+                .as_deref()
+                .map(split_list)
+                .into_iter()
+                .flatten()
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+
+    /// The `Exec` arguments with field codes expanded, the URL placeholder is `%1`.
+    pub fn words(&self) -> Option<Vec<String>> {
+        let args = split_exec(self.exec.as_deref()?)?;
+        Some(expand_exec(args, self))
+    }
+}
+
+/// Splits an `Exec` value following the desktop entry quoting rules.
+pub fn split_exec(exec: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut chars = exec.chars().peekable();
+    loop {
+        while chars.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+        let mut arg = String::new();
+        if first == '"' {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        c @ ('"' | '`' | '$' | '\\') => arg.push(c),
+                        c => {
+                            arg.push('\\');
+                            arg.push(c);
+                        }
+                    },
+                    c => arg.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|&c| c != ' ' && c != '\t') {
+                arg.push(c);
+            }
+        }
+        args.push(arg);
+    }
+    Some(args)
+}
+
+/// Joins arguments into an `Exec` value, the inverse of [`split_exec`] and key file unescaping.
+/// Arguments equal to `%1` become the `%u` field code.
+pub fn join_exec<S: AsRef<str>>(args: &[S]) -> String {
+    let words: Vec<String> = args
+        .iter()
+        .map(|arg| {
+            let arg = arg.as_ref();
+            if arg == "%1" {
+                return "%u".to_string();
+            }
+            let arg = arg.replace('%', "%%");
+            let reserved = |c: char| " \t\n\"'\\><~|&;$*?#()`".contains(c);
+            if !arg.is_empty() && !arg.contains(reserved) {
+                return arg;
+            }
+            let mut quoted = String::from('"');
+            for c in arg.chars() {
+                if matches!(c, '"' | '`' | '$' | '\\') {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+            quoted.push('"');
+            quoted
+        })
+        .collect();
+    words.join(" ").replace('\\', "\\\\")
+}
+
+/// Expands the field codes of `Exec` arguments. File and URL codes all become `%1`,
+/// which is appended when the entry has none.
+pub fn expand_exec(args: Vec<String>, entry: &DesktopEntry) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut placeholder = false;
+    for arg in args {
+        if arg == "%i" {
+            if let Some(icon) = &entry.icon {
+                words.push("--icon".to_string());
+                words.push(icon.clone());
+            }
+            continue;
+        }
+        let mut word = String::with_capacity(arg.len());
+        let mut chars = arg.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                word.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('%') => word.push('%'),
+                Some('u' | 'U' | 'f' | 'F') => {
+                    word.push_str("%1");
+                    placeholder = true;
+                }
+                Some('c') => word.push_str(&entry.name),
+                Some('k') => word.push_str(&entry.path.to_string_lossy()),
+                _ => {}
+            }
+        }
+        if !word.is_empty() || arg.is_empty() {
+            words.push(word);
+        }
+    }
+    if !placeholder {
+        words.push("%1".to_string());
+    }
+    words
+}
+
+fn collect_desktop_files(dir: &Path, prefix: &str, output: &mut Map<String, PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if path.is_dir() {
+            let prefix = format!("{}{}-", prefix, name);
+            collect_desktop_files(&path, &prefix, output);
+        } else if name.ends_with(".desktop") {
+            output.entry(format!("{}{}", prefix, name)).or_insert(path);
+        }
+    }
+}
+
+impl Xdg {
+    /// All desktop entries by desktop file ID, earlier data directories win.
+    pub fn desktop_entries(&self) -> Map<String, DesktopEntry> {
+        let mut files = Map::new();
+        for dir in self.all_data_dirs() {
+            collect_desktop_files(&dir.join("applications"), "", &mut files);
+        }
+        files
+            .into_iter()
+            .filter_map(|(id, path)| {
+                let text = fs::read_to_string(&path).ok()?;
+                let entry = DesktopEntry::parse(id.clone(), path, &text, self.locale.as_deref())?;
+                Some((id, entry))
+            })
+            .collect()
+    }
+
+    pub fn collect_webbrowser(&self) -> Map<String, DesktopEntry> {
+        let mut entries = self.desktop_entries();
+        entries.retain(|_, entry| {
+            entry.exec.is_some()
+                && entry
+                    .mime_types
+                    .iter()
+                    .any(|mime| mime == "x-scheme-handler/https")
+        });
+        entries
+    }
+
+    /// The first installed handler listed under `[Default Applications]` for the MIME type.
+    pub fn get_default_application(
+        &self,
+        mime: &str,
+        installed: &dyn Fn(&str) -> bool,
+    ) -> Option<String> {
+        self.mimeapps_lists().iter().find_map(|path| {
+            let text = fs::read_to_string(path).ok()?;
+            let groups = parse_key_file(&text);
+            let list = groups.get("Default Applications")?.get(mime)?;
+            let id = split_list(list).find(|id| installed(id))?;
+            Some(id.to_string())
+        })
+    }
+
+    pub fn get_default_webbrowser_id(&self) -> io::Result<String> {
+        let browsers = self.collect_webbrowser();
+        let installed = |id: &str| browsers.contains_key(id);
+        ["x-scheme-handler/https", "x-scheme-handler/http"]
+            .iter()
+            .find_map(|mime| self.get_default_application(mime, &installed))
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+}
+
+fn protocol_desktop_id(scheme: &str) -> String {
+    format!("{}-handler.desktop", scheme)
+}
+
+impl Xdg {
+    fn mimeapps_path(&self) -> io::Result<PathBuf> {
+        let config_home = self.config_home.as_ref().ok_or(io::ErrorKind::NotFound)?;
+        Ok(config_home.join("mimeapps.list"))
+    }
+
+    fn edit_mimeapps(&self, edit: impl Fn(&str) -> String) -> io::Result<()> {
+        let path = self.mimeapps_path()?;
+        let text = match fs::read_to_string(&path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            text => text?,
+        };
+        let edited = edit(&text);
+        if edited != text {
+            fs::create_dir_all(path.parent().ok_or(io::ErrorKind::NotFound)?)?;
+            fs::write(&path, edited)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a hidden desktop entry for the scheme and makes it the default handler.
+    pub fn register_protocol(&self, handler: &Handler) -> io::Result<()> {
+        protocol::check_reserved(&handler.scheme)?;
+        let data_home = self.data_home.as_ref().ok_or(io::ErrorKind::NotFound)?;
+        let dir = data_home.join("applications");
+        let id = protocol_desktop_id(&handler.scheme);
+        let mime = format!("x-scheme-handler/{}", handler.scheme);
+        let args = shell_words::split(&handler.command).map_err(io::Error::other)?;
+        let mut entry = vec![
+            "[Desktop Entry]".to_string(),
+            "Type=Application".to_string(),
+            format!("Name={}", handler.name),
+            format!("Exec={}", join_exec(&args)),
+        ];
+        entry.extend(handler.icon.as_ref().map(|icon| format!("Icon={}", icon)));
+        entry.extend([
+            "NoDisplay=true".to_string(),
+            "Terminal=false".to_string(),
+            format!("MimeType={};", mime),
+            String::new(),
+        ]);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(&id), entry.join("\n"))?;
+        self.edit_mimeapps(|text| {
+            let list = |group: &str| {
+                let groups = parse_key_file(text);
+                let list = groups.get(group).and_then(|group| group.get(&mime));
+                let others = list.into_iter().flat_map(|list| split_list(list));
+                let others = others.filter(|other| *other != id);
+                let list: Vec<&str> = [id.as_str()].into_iter().chain(others).collect();
+                format!("{};", list.join(";"))
+            };
+            let text = edit_key_file(
+                text,
+                "Default Applications",
+                &mime,
+                Some(&list("Default Applications")),
+            );
+            edit_key_file(
+                &text,
+                "Added Associations",
+                &mime,
+                Some(&list("Added Associations")),
+            )
+        })
+    }
+
+    /// Removes the desktop entry written by [`Xdg::register_protocol`] and its associations.
+    pub fn unregister_protocol(&self, scheme: &str) -> io::Result<()> {
+        let id = protocol_desktop_id(scheme);
+        let mime = format!("x-scheme-handler/{}", scheme);
+        if let Some(data_home) = &self.data_home {
+            match fs::remove_file(data_home.join("applications").join(&id)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        self.edit_mimeapps(|text| {
+            let groups = parse_key_file(text);
+            ["Default Applications", "Added Associations"].iter().fold(
+                text.to_string(),
+                |text, group| {
+                    let Some(list) = groups.get(*group).and_then(|group| group.get(&mime)) else {
+                        return text;
+                    };
+                    let others: Vec<&str> = split_list(list).filter(|other| *other != id).collect();
+                    let list = format!("{};", others.join(";"));
+                    let list = (!others.is_empty()).then_some(list.as_str());
+                    edit_key_file(&text, group, &mime, list)
+                },
+            )
+        })
+    }
+
+    pub fn protocol_status(&self, scheme: &str) -> ProtocolStatus {
+        let mime = format!("x-scheme-handler/{}", scheme);
+        let mut entries = self.desktop_entries();
+        entries.retain(|_, entry| entry.exec.is_some() && entry.mime_types.contains(&mime));
+        let installed = |id: &str| entries.contains_key(id);
+        let id = self.get_default_application(&mime, &installed);
+        let entry = match &id {
+            Some(id) => entries.get(id),
+            None => entries.values().next(),
+        };
+        ProtocolStatus {
+            scheme: scheme.to_string(),
+            registered: entry.is_some(),
+            handler: entry.map(|entry| entry.id.clone()),
+            command: entry.and_then(|entry| entry.exec.clone()),
+            words: entry.and_then(DesktopEntry::words),
+        }
+    }
+}
+
+impl Xdg {
+    fn autostart_path(&self) -> io::Result<PathBuf> {
+        let config_home = self.config_home.as_ref().ok_or(io::ErrorKind::NotFound)?;
+        Ok(config_home
+            .join("autostart")
+            .join("metadata-fetcher.desktop"))
+    }
+
+    pub fn enable_autostart(&self, script: &str) -> io::Result<()> {
+        let path = self.autostart_path()?;
+        let entry = [
+            "[Desktop Entry]".to_string(),
+            "Type=Application".to_string(),
+            "Name=Metadata Fetcher".to_string(),
+            format!("Exec={}", join_exec(&[script, TRAY_ARG])),
+            "Terminal=false".to_string(),
+            "X-GNOME-Autostart-enabled=true".to_string(),
+            String::new(),
+        ];
+        fs::create_dir_all(path.parent().ok_or(io::ErrorKind::NotFound)?)?;
+        fs::write(path, entry.join("\n"))
+    }
+
+    pub fn disable_autostart(&self) -> io::Result<()> {
+        match fs::remove_file(self.autostart_path()?) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn autostart_status(&self) -> Vec<Entry> {
+        let entry = (|| {
+            let path = self.autostart_path().ok()?;
+            let text = fs::read_to_string(&path).ok()?;
+            let id = "metadata-fetcher.desktop".to_string();
+            let entry = DesktopEntry::parse(id, path.clone(), &text, None)?;
+            Some(Entry {
+                method: "xdg",
+                location: path.to_string_lossy().into_owned(),
+                target: split_exec(entry.exec.as_deref()?)?.into_iter().next(),
+                command: entry.exec,
+            })
+        })();
+        entry.into_iter().collect()
+    }
+}
+
+impl Xdg {
+    /// A directory from `user-dirs.dirs`, as written by `xdg-user-dirs-update`.
+    fn user_dir(&self, key: &str, default: &str) -> Option<PathBuf> {
+        let home = self.home.as_ref()?;
+        let text = self
+            .config_home
+            .as_ref()
+            .and_then(|dir| fs::read_to_string(dir.join("user-dirs.dirs")).ok())
+            .unwrap_or_default();
+        let value = text.lines().map(str::trim).find_map(|line| {
+            let value = line.strip_prefix(key)?.strip_prefix('=')?;
+            value.strip_prefix('"')?.strip_suffix('"')
+        });
+        let path = match value {
+            Some(value) => match value.strip_prefix("$HOME") {
+                Some(rest) => home.join(rest.trim_start_matches('/')),
+                None => absolute(PathBuf::from(value))?,
+            },
+            None => home.join(default),
+        };
+        // A directory set to `$HOME` itself is disabled
+        Some(path).filter(|path| path != home)
+    }
+
+    /// Resolves a Windows known folder name to the user or base directory serving the same role.
+    pub fn known_folder(&self, input: &str) -> io::Result<PathBuf> {
+        let path = match input {
+            "Desktop" => self.user_dir("XDG_DESKTOP_DIR", "Desktop"),
+            "Documents" => self.user_dir("XDG_DOCUMENTS_DIR", "Documents"),
+            "Downloads" => self.user_dir("XDG_DOWNLOAD_DIR", "Downloads"),
+            "Music" => self.user_dir("XDG_MUSIC_DIR", "Music"),
+            "Pictures" => self.user_dir("XDG_PICTURES_DIR", "Pictures"),
+            "Videos" => self.user_dir("XDG_VIDEOS_DIR", "Videos"),
+            "StartMenu" => self.data_home.as_ref().map(|dir| dir.join("applications")),
+            "Startup" => self.config_home.as_ref().map(|dir| dir.join("autostart")),
+            "LocalAppData" => self.data_home.clone(),
+            "RoamingAppData" => self.config_home.clone(),
+            "ProgramData" => self.config_dirs.first().cloned(),
+            "SendTo" | "PublicDesktop" => None,
+            _ => {
+                let message = format!("Unknown known folder: {}", input);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+        };
+        path.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No {} folder", input)))
+    }
+}
+
+impl Xdg {
+    /// Proxy settings of the desktop: KDE's `kioslaverc`, or GNOME's through `dconf`.
+    pub fn desktop_proxy(&self) -> Proxy {
+        if self.desktops.iter().any(|desktop| desktop == "kde") {
+            let path = self.config_home.as_ref().map(|dir| dir.join("kioslaverc"));
+            if let Some(text) = path.and_then(|path| fs::read_to_string(path).ok()) {
+                return proxy::parse_kde(&text);
+            }
+        }
+        let dump = std::process::Command::new("dconf")
+            .args(["dump", "/system/proxy/"])
+            .stderr(std::process::Stdio::null())
+            .output();
+        match dump {
+            Ok(output) if output.status.success() => {
+                proxy::parse_gnome(&String::from_utf8_lossy(&output.stdout))
+            }
+            _ => Proxy::default(),
+        }
+    }
+}
+
+impl From<&DesktopEntry> for BrowserInfo {
+    fn from(entry: &DesktopEntry) -> BrowserInfo {
+        BrowserInfo {
+            name: entry.name.clone(),
+            command: entry.exec.clone().unwrap_or_default(),
+            words: entry.words(),
+        }
+    }
+}
+
+pub fn get_default_webbrowser_id() -> io::Result<String> {
+    Xdg::from_env().get_default_webbrowser_id()
+}
+pub fn get_default_webbrowser() -> io::Result<BrowserInfo> {
+    let xdg = Xdg::from_env();
+    let id = xdg.get_default_webbrowser_id()?;
+    let browsers = xdg.collect_webbrowser();
+    let entry = browsers.get(&id).ok_or(io::ErrorKind::NotFound)?;
+    Ok(BrowserInfo::from(entry))
+}
+pub fn register_protocol(handler: &Handler) -> io::Result<()> {
+    Xdg::from_env().register_protocol(handler)
+}
+pub fn unregister_protocol(scheme: &str) -> io::Result<()> {
+    Xdg::from_env().unregister_protocol(scheme)
+}
+pub fn protocol_status(scheme: &str) -> ProtocolStatus {
+    Xdg::from_env().protocol_status(scheme)
+}
+/// Only the XDG autostart directory is available, whatever the method.
+pub fn enable_autostart(_method: Method, script: &str) -> io::Result<()> {
+    Xdg::from_env().enable_autostart(script)
+}
+pub fn disable_autostart() -> io::Result<()> {
+    Xdg::from_env().disable_autostart()
+}
+pub fn autostart_status() -> Vec<Entry> {
+    Xdg::from_env().autostart_status()
+}
+/// The `*_proxy` variables override the desktop, as they do for most programs.
+pub fn proxy() -> Proxy {
+    proxy::from_env(|name| env::var(name).ok()).unwrap_or_else(|| Xdg::from_env().desktop_proxy())
+}
+pub fn system_info() -> SystemInfo {
+    system::from_files(Path::new("/"), Xdg::from_env().locale.as_deref())
+}
+pub fn collect_webbrowser_info() -> Map<String, BrowserInfo> {
+    Xdg::from_env()
+        .collect_webbrowser()
+        .iter()
+        .map(|(id, entry)| (id.clone(), BrowserInfo::from(entry)))
+        .collect()
+}
@@ -0,0 +1,76 @@
+//! Cookies read from the Firefox profile in `tests/fixtures/profiles/firefox`.
+
+use reg_utils::cookies::{self, Cookie};
+use std::path::{Path, PathBuf};
+
+fn profile() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/profiles/firefox/Profiles/x8kf2q1b.default-release")
+}
+
+fn names(cookies: &[Cookie]) -> Vec<(&str, &str)> {
+    cookies
+        .iter()
+        .map(|cookie| (cookie.host.as_str(), cookie.name.as_str()))
+        .collect()
+}
+
+#[test]
+fn domain() {
+    let cookies = cookies::read(&profile(), "bilibili.com").unwrap();
+    // Expired cookies, other containers and host-only cookies of subdomains are left out
+    assert_eq!(
+        names(&cookies),
+        [(".bilibili.com", "SESSDATA"), (".bilibili.com", "bili_jct")]
+    );
+    assert_eq!(cookies[0].value, "abc%2C4102444800%2Cdef");
+    assert!(cookies[0].secure && cookies[0].http_only);
+    let cookies = cookies::read(&profile(), "space.bilibili.com").unwrap();
+    assert_eq!(names(&cookies).len(), 3);
+    assert!(cookies::read(&profile(), "ibilibili.com")
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn milliseconds() {
+    let cookies = cookies::read(&profile(), "www.pixiv.net").unwrap();
+    assert_eq!(names(&cookies), [(".pixiv.net", "PHPSESSID")]);
+    assert_eq!(cookies[0].expiry, 4102444800);
+}
+
+#[test]
+fn netscape() {
+    let cookies = cookies::read(&profile(), "bilibili.com").unwrap();
+    let expected = "# Netscape HTTP Cookie File\n\
+        #HttpOnly_.bilibili.com\tTRUE\t/\tTRUE\t4102444800\tSESSDATA\tabc%2C4102444800%2Cdef\n\
+        .bilibili.com\tTRUE\t/\tFALSE\t4102444800\tbili_jct\t0123456789abcdef\n";
+    assert_eq!(cookies::to_netscape(&cookies), expected);
+}
+
+/// A browser holding the database open keeps new cookies in the write-ahead log.
+#[test]
+fn open_database() {
+    let dir = std::env::temp_dir().join(format!("reg-utils-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(profile().join("cookies.sqlite"), dir.join("cookies.sqlite")).unwrap();
+    let browser = rusqlite::Connection::open(dir.join("cookies.sqlite")).unwrap();
+    browser
+        .execute_batch(
+            "PRAGMA journal_mode = WAL; PRAGMA wal_autocheckpoint = 0;
+             INSERT INTO moz_cookies (name, value, host, path, expiry, isSecure, isHttpOnly)
+             VALUES ('DedeUserID', '42', '.bilibili.com', '/', 4102444800, 0, 0);",
+        )
+        .unwrap();
+    assert!(dir.join("cookies.sqlite-wal").exists());
+    let cookies = cookies::read(&dir, "bilibili.com").unwrap();
+    assert!(names(&cookies).contains(&(".bilibili.com", "DedeUserID")));
+    drop(browser);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing() {
+    let result = cookies::read(&profile().join("missing"), "bilibili.com");
+    assert!(result.is_err_and(|err| err.kind() == std::io::ErrorKind::NotFound));
+}
@@ -0,0 +1,31 @@
+//! Temporary copies of browser databases, alone in this binary so the first copy's name is known.
+
+use std::path::Path;
+
+#[test]
+fn planted_directory() {
+    let profile = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/profiles/firefox/Profiles/x8kf2q1b.default-release"
+    );
+    let first = std::env::temp_dir().join(format!("reg-utils-{}-0", std::process::id()));
+    let _ = std::fs::remove_dir_all(&first);
+    std::fs::create_dir(&first).unwrap();
+    std::fs::write(first.join("cookies.sqlite"), "planted").unwrap();
+
+    // The planted directory is neither used nor removed
+    let Err(err) = reg_utils::cookies::read(Path::new(profile), "bilibili.com") else {
+        panic!("read a planted copy");
+    };
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(
+        std::fs::read_to_string(first.join("cookies.sqlite")).unwrap(),
+        "planted"
+    );
+    std::fs::remove_dir_all(&first).unwrap();
+
+    let cookies = reg_utils::cookies::read(Path::new(profile), "bilibili.com").unwrap();
+    assert!(!cookies.is_empty());
+    let second = std::env::temp_dir().join(format!("reg-utils-{}-1", std::process::id()));
+    assert!(!second.exists());
+}
@@ -0,0 +1,150 @@
+//! History and bookmarks read from the profiles in `tests/fixtures/profiles`.
+
+use reg_utils::history::{self, Filter, Item, Source};
+use std::path::{Path, PathBuf};
+
+/// 2024-05-01T00:00:00Z
+const MAY_1: i64 = 1714521600;
+const DAY: i64 = 86400;
+
+fn fixture(dir: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/profiles")
+        .join(dir)
+}
+
+fn urls(items: &[Item], filter: &Filter) -> Vec<(String, Source)> {
+    items
+        .iter()
+        .filter(|item| filter.matches(item))
+        .map(|item| (item.url.clone(), item.source))
+        .collect()
+}
+
+fn sites() -> Filter {
+    let patterns = history::SITES
+        .iter()
+        .map(|(site, _)| history::site_pattern(site).unwrap())
+        .collect();
+    Filter {
+        patterns,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn chromium() {
+    let items = history::read(&fixture("chrome/Profile 1")).unwrap();
+    assert_eq!(items.len(), 9);
+    let video = &items[1];
+    assert_eq!(video.url, "https://www.bilibili.com/video/BV1xx411c7mD/");
+    assert_eq!(
+        video.title.as_deref(),
+        Some("字幕君交流场所_哔哩哔哩_bilibili")
+    );
+    assert_eq!(video.time, MAY_1 + 3600);
+    // Empty titles are missing ones
+    let artwork = items
+        .iter()
+        .find(|item| item.url.contains("pixiv"))
+        .unwrap();
+    assert_eq!(artwork.title, None);
+    let filter = Filter {
+        since: Some(MAY_1),
+        until: Some(MAY_1 + 3 * DAY),
+        ..sites()
+    };
+    let expected = [
+        (
+            "https://www.bilibili.com/video/BV1xx411c7mD/",
+            Source::History,
+        ),
+        ("https://www.pixiv.net/artworks/20", Source::History),
+        (
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            Source::History,
+        ),
+    ];
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|&(url, source)| (url.to_string(), source))
+        .collect();
+    assert_eq!(urls(&items, &filter), expected);
+}
+
+#[test]
+fn chromium_bookmarks() {
+    let items = history::chromium(&fixture("chrome/Profile 1")).unwrap();
+    let filter = Filter {
+        source: Some(Source::Bookmark),
+        ..Default::default()
+    };
+    let bookmarks = urls(&items, &filter);
+    // Nested folders are walked too
+    assert_eq!(bookmarks.len(), 3);
+    assert_eq!(bookmarks[0].0, "https://www.pixiv.net/artworks/20");
+    let pixiv = items
+        .iter()
+        .find(|item| item.source == Source::Bookmark && item.url.contains("pixiv"))
+        .unwrap();
+    assert_eq!(pixiv.time, MAY_1 + 3 * DAY);
+    assert_eq!(pixiv.title.as_deref(), Some("pixiv"));
+}
+
+#[test]
+fn firefox() {
+    let items = history::read(&fixture("firefox/Profiles/x8kf2q1b.default-release")).unwrap();
+    let expected = [
+        ("https://youtu.be/dQw4w9WgXcQ", Source::History),
+        (
+            "https://www.bilibili.com/video/BV1xx411c7mD/",
+            Source::History,
+        ),
+        (
+            "https://www.pixiv.net/en/artworks/44298467",
+            Source::Bookmark,
+        ),
+    ];
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|&(url, source)| (url.to_string(), source))
+        .collect();
+    assert_eq!(urls(&items, &sites()), expected);
+    let bookmark = items.last().unwrap();
+    assert_eq!(bookmark.title.as_deref(), Some("Illustration"));
+    assert_eq!(bookmark.time, MAY_1 + 6 * DAY);
+}
+
+#[test]
+fn pattern() {
+    let items = history::read(&fixture("chrome/Profile 1")).unwrap();
+    let filter = Filter {
+        patterns: vec![history::site_pattern("niconico").unwrap()],
+        source: Some(Source::History),
+        ..Default::default()
+    };
+    assert_eq!(urls(&items, &filter).len(), 1);
+    assert!(history::site_pattern("twitter").is_err());
+}
+
+#[test]
+fn parse_time() {
+    let now = MAY_1 + 12 * 3600;
+    assert_eq!(history::parse_time("2024-05-01", now).unwrap(), MAY_1);
+    assert_eq!(history::parse_time("2000-03-01", now).unwrap(), 951868800);
+    assert_eq!(history::parse_time("1714521600", now).unwrap(), MAY_1);
+    assert_eq!(history::parse_time("12h", now).unwrap(), MAY_1);
+    assert_eq!(history::parse_time("1w", now).unwrap(), now - 7 * DAY);
+    for text in ["", "yesterday", "2024-13-01", "7y", "2024-05"] {
+        assert!(history::parse_time(text, now).is_err(), "{}", text);
+    }
+    // Ages and dates out of range are invalid instead of overflowing
+    for text in [
+        "9223372036854775807w",
+        "-9223372036854775808s",
+        "99999999999999-01-01",
+    ] {
+        let err = history::parse_time(text, now).unwrap_err();
+        assert_eq!(err.to_string(), format!("Invalid time: {}", text));
+    }
+}
@@ -0,0 +1,93 @@
+//! The library API and its C ABI, against the same fixtures as the command line.
+
+use reg_utils::ffi;
+use reg_utils::regfile::MemoryRegistry;
+use std::ffi::CStr;
+
+fn fixture() -> MemoryRegistry {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/system.reg");
+    MemoryRegistry::load(path).unwrap()
+}
+
+#[test]
+fn software_filter() {
+    let reg = fixture();
+    let filter = reg_utils::SoftwareFilter {
+        publisher: Some(regex::Regex::new("(?i)acme").unwrap()),
+        ..Default::default()
+    };
+    let software = reg_utils::get_installed_software(Some(&reg), &filter);
+    let names: Vec<&str> = software.values().map(|info| info.name.as_str()).collect();
+    assert_eq!(names, ["Foo App"]);
+}
+
+#[test]
+fn software_views() {
+    let reg = fixture();
+    let filter = reg_utils::SoftwareFilter {
+        system: true,
+        ..Default::default()
+    };
+    let software = reg_utils::get_installed_software(Some(&reg), &filter);
+    let entries: Vec<(&str, &str, Option<&str>)> = software
+        .iter()
+        .map(|(id, info)| (id.as_str(), info.name.as_str(), info.architecture))
+        .collect();
+    // The same subkey name in another hive or view is another program
+    let expected = [
+        (r"HKCU\Foo", "Foo Portable", None),
+        (r"HKLM\Foo", "Foo App", Some("x64")),
+        (r"HKLM\Runtime", "Runtime", Some("x64")),
+        (r"HKLM\WOW6432Node\Runtime", "Runtime (x86)", Some("x86")),
+    ];
+    assert_eq!(entries, expected);
+
+    // A 32-bit machine has no WOW6432Node and its only view is x86
+    let text = r#"Windows Registry Editor Version 5.00
+
+[HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\Foo]
+"DisplayName"="Foo App"
+"#;
+    let reg = MemoryRegistry::parse(text).unwrap();
+    let software = reg_utils::get_installed_software(Some(&reg), &filter);
+    assert_eq!(software[r"HKLM\Foo"].architecture, Some("x86"));
+}
+
+#[test]
+fn default_browser() {
+    let reg = fixture();
+    let browser = reg_utils::get_default_webbrowser(Some(&reg)).unwrap();
+    assert!(browser.command.contains("chrome.exe"));
+    let words = browser.words.unwrap();
+    assert_eq!(words.last().map(String::as_str), Some("%1"));
+}
+
+fn call(ptr: *mut std::ffi::c_char) -> json::JsonValue {
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    unsafe { ffi::reg_utils_free(ptr) };
+    json::parse(&text).unwrap()
+}
+
+#[test]
+fn c_abi() {
+    let reg = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/system.reg");
+    let args = json::stringify(json::array!["--reg", reg, "--all"]);
+    let command = "software";
+    let result = call(unsafe {
+        ffi::reg_utils_run(command.as_ptr(), command.len(), args.as_ptr(), args.len())
+    });
+    assert_eq!(result["ok"], true);
+    assert!(result["data"][r"HKLM\Runtime"]["systemComponent"] == true);
+
+    let filter = r#"{ "name": 1 }"#;
+    let result = call(unsafe { ffi::reg_utils_software(filter.as_ptr(), filter.len()) });
+    assert_eq!(result["ok"], true);
+
+    let name = "Nowhere";
+    let result = call(unsafe { ffi::reg_utils_known_folder(name.as_ptr(), name.len()) });
+    assert_eq!(result["error"]["kind"], "badArguments");
+
+    let options = "{";
+    let result = call(unsafe { ffi::reg_utils_create_shortcut(options.as_ptr(), options.len()) });
+    assert_eq!(result["error"]["kind"], "badArguments");
+}
@@ -0,0 +1,248 @@
+//! Shell link decoding against the `.lnk` files in `tests/fixtures/lnk`.
+
+use json::{object, JsonValue};
+use reg_utils::lnk::{self, ShellLink, ShowCommand};
+use std::io::ErrorKind;
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/fixtures/lnk/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read(path).unwrap()
+}
+
+fn load(name: &str) -> ShellLink {
+    ShellLink::parse(&fixture(name)).unwrap()
+}
+
+fn error(name: &str) -> String {
+    let Err(err) = ShellLink::parse(&fixture(name)) else {
+        panic!("{} parsed", name);
+    };
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    err.to_string()
+}
+
+/// The `C:\test\a.txt` example of MS-SHLLINK: an ID list, a local `LinkInfo` and a tracker block.
+#[test]
+fn local_file() {
+    let link = load("file.lnk");
+    assert_eq!(link.flags, 0x0008_009B);
+    assert_eq!(link.file_attributes, 0x20);
+    assert_eq!(link.creation_time, 0x01CD_0B0E_BF4C_1D20);
+    assert_eq!(link.access_time, 0x01CD_0B0E_BF4C_1D20);
+    assert_eq!(link.write_time, 0x01CD_0B0E_BF4C_1D20);
+    assert_eq!(link.show_command, ShowCommand::Normal);
+    assert_eq!(link.id_list.as_ref().map(Vec::len), Some(4));
+    assert_eq!(link.id_list_path().as_deref(), Some(r"C:\test\a.txt"));
+    assert_eq!(link.extra_data.len(), 1);
+    assert_eq!(link.extra_data[0].len(), 0x60);
+    assert_eq!(link.target().as_deref(), Some(r"C:\test\a.txt"));
+
+    assert_eq!(
+        JsonValue::from(&link),
+        object! {
+            target: r"C:\test\a.txt",
+            arguments: null,
+            workingDir: r"C:\test",
+            relativePath: r".\a.txt",
+            description: null,
+            iconLocation: null,
+            iconIndex: 0,
+            showCommand: "normal",
+            hotkey: null,
+            runAsAdmin: false,
+            fileSize: 0,
+            fileAttributes: 0x20,
+            idList: object! { path: r"C:\test\a.txt", items: 4 },
+            linkInfo: object! {
+                path: r"C:\test\a.txt",
+                driveType: "fixed",
+                driveSerial: "307A8A81",
+                volumeLabel: null,
+                localBasePath: r"C:\test\a.txt",
+                netName: null,
+                deviceName: null,
+                commonPathSuffix: null,
+            },
+            environmentTarget: null,
+        }
+    );
+}
+
+/// A minimized, elevated link to a share, with every string and an environment block.
+#[test]
+fn network_share() {
+    let link = load("network.lnk");
+    assert_eq!(link.show_command, ShowCommand::Minimized);
+    assert_eq!(link.hotkey, 0x0646);
+    assert!(link.id_list.is_none());
+    assert_eq!(
+        JsonValue::from(&link),
+        object! {
+            target: r"\\server\share\tools\run.exe",
+            arguments: r#"--verbose "a b""#,
+            workingDir: null,
+            relativePath: null,
+            description: "Run the tool",
+            iconLocation: r"%SystemRoot%\system32\shell32.dll",
+            iconIndex: -3,
+            showCommand: "minimized",
+            hotkey: "Ctrl+Alt+F",
+            runAsAdmin: true,
+            fileSize: 4096,
+            fileAttributes: 0x20,
+            idList: null,
+            linkInfo: object! {
+                path: r"\\server\share\tools\run.exe",
+                driveType: null,
+                driveSerial: null,
+                volumeLabel: null,
+                localBasePath: null,
+                netName: r"\\server\share",
+                deviceName: "Z:",
+                commonPathSuffix: r"tools\run.exe",
+            },
+            environmentTarget: r"%SHARE%\tools\run.exe",
+        }
+    );
+}
+
+#[test]
+fn malformed() {
+    assert_eq!(error("truncated.lnk"), "Truncated shell link");
+    assert_eq!(error("bad-clsid.lnk"), "Not a shell link");
+    // An ID list item larger than the list
+    assert_eq!(error("bad-idlist.lnk"), "Truncated shell link");
+    // A string count past the end of the file
+    assert_eq!(error("bad-strings.lnk"), "Truncated shell link");
+    assert_eq!(
+        ShellLink::parse(b"").err().map(|err| err.kind()),
+        Some(ErrorKind::InvalidData)
+    );
+}
+
+/// Every prefix either fails cleanly or parses, once the optional extra data is all that is cut.
+#[test]
+fn every_prefix() {
+    for name in ["file.lnk", "network.lnk"] {
+        let bytes = fixture(name);
+        let strings_end =
+            bytes.len() - 4 - load(name).extra_data.iter().map(Vec::len).sum::<usize>();
+        for len in 0..bytes.len() {
+            let result = ShellLink::parse(&bytes[..len]);
+            assert_eq!(
+                result.is_ok(),
+                len >= strings_end,
+                "{} cut at {}",
+                name,
+                len
+            );
+        }
+    }
+}
+
+/// Offsets past the end of the `LinkInfo` leave its fields empty instead of failing.
+#[test]
+fn bad_offsets() {
+    let mut bytes = fixture("bad-strings.lnk");
+    bytes.truncate(bytes.len() - 4);
+    bytes.extend([0, 0]);
+    let link = ShellLink::parse(&bytes).unwrap();
+    let info = link.link_info.unwrap();
+    assert_eq!(info.drive_type, None);
+    assert_eq!(info.local_base_path, None);
+    assert_eq!(info.net_name, None);
+    assert_eq!(info.common_path_suffix, None);
+    assert_eq!(link.relative_path.as_deref(), Some(""));
+}
+
+#[test]
+fn round_trip() {
+    for name in ["file.lnk", "network.lnk"] {
+        let link = load(name);
+        let written = ShellLink::parse(&link.to_bytes().unwrap()).unwrap();
+        assert_eq!(
+            JsonValue::from(&written),
+            JsonValue::from(&link),
+            "{}",
+            name
+        );
+        assert_eq!(written.extra_data, link.extra_data);
+    }
+
+    let mut link = ShellLink::new(r"C:\Program Files\Tool\tool.exe");
+    link.name = Some("Tool".into());
+    link.working_dir = Some(r"C:\Program Files\Tool".into());
+    link.arguments = Some("--open \"ü €\"".into());
+    link.icon_location = Some(r"C:\Program Files\Tool\tool.ico".into());
+    link.icon_index = 2;
+    link.show_command = ShowCommand::Maximized;
+    link.hotkey = lnk::parse_hotkey("Ctrl+Shift+F12").unwrap();
+    link.flags |= lnk::RUN_AS_USER;
+    let written = ShellLink::parse(&link.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        JsonValue::from(&written),
+        object! {
+            target: r"C:\Program Files\Tool\tool.exe",
+            arguments: "--open \"ü €\"",
+            workingDir: r"C:\Program Files\Tool",
+            relativePath: null,
+            description: "Tool",
+            iconLocation: r"C:\Program Files\Tool\tool.ico",
+            iconIndex: 2,
+            showCommand: "maximized",
+            hotkey: "Ctrl+Shift+F12",
+            runAsAdmin: true,
+            fileSize: 0,
+            fileAttributes: 0x20,
+            idList: object! { path: r"C:\Program Files\Tool\tool.exe", items: 5 },
+            linkInfo: object! {
+                path: r"C:\Program Files\Tool\tool.exe",
+                driveType: "fixed",
+                driveSerial: "00000000",
+                volumeLabel: null,
+                localBasePath: r"C:\Program Files\Tool\tool.exe",
+                netName: null,
+                deviceName: null,
+                commonPathSuffix: null,
+            },
+            environmentTarget: null,
+        }
+    );
+}
+
+/// A link to a share names a real network provider along with the ValidNetType flag.
+#[test]
+fn network_provider() {
+    let link = ShellLink::new(r"\\server\share\tools\run.exe");
+    let bytes = link.to_bytes().unwrap();
+    // Flags, NetNameOffset, DeviceNameOffset and NetworkProviderType
+    let expected: Vec<u8> = [2u32, 0x14, 0, 0x0002_0000]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    assert!(bytes
+        .windows(expected.len())
+        .any(|window| window == expected));
+    let link = ShellLink::parse(&bytes).unwrap();
+    let info = link.link_info.unwrap();
+    assert_eq!(info.net_name.as_deref(), Some(r"\\server\share"));
+}
+
+#[test]
+fn too_long() {
+    let mut link = ShellLink::new(r"C:\tool.exe");
+    link.arguments = Some("x".repeat(0x10000));
+    let err = link.to_bytes().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(err.to_string(), "String of 65536 characters is too long");
+    link.arguments = Some("x".repeat(0xFFFF));
+    assert!(link.to_bytes().is_ok());
+
+    link.id_list = Some(vec![vec![0; 0x8000], vec![0; 0x8000]]);
+    let err = link.to_bytes().unwrap_err();
+    assert_eq!(err.to_string(), "ID list of 65538 bytes is too long");
+
+    // A path component too long for a shell item leaves the link without an ID list
+    let link = ShellLink::new(&format!(r"C:\{}", "x".repeat(0x8000)));
+    assert!(link.id_list.is_none());
+}
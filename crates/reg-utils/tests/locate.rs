@@ -0,0 +1,74 @@
+//! Tool lookups against a temporary `PATH` of executable stubs.
+#![cfg(unix)]
+
+use reg_utils::locate::Locator;
+use reg_utils::xdg;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+fn stub(dir: &Path, name: &str, script: &str, mode: u32) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    path
+}
+
+#[test]
+fn stubs() {
+    let root = std::env::temp_dir().join(format!("reg-utils-locate-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let (bin, other, home) = (root.join("bin"), root.join("other"), root.join("home"));
+    for dir in [&bin, &other, &home] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    let ffmpeg = stub(
+        &bin,
+        "ffmpeg",
+        r#"[ "$1" = -version ] || exit 1
+echo "ffmpeg version 6.1.1 Copyright (c) 2000-2023"
+yes "configuration: --enable-gpl" | head -c 1000000"#,
+        0o755,
+    );
+    stub(&other, "ffmpeg", "echo 'ffmpeg version 5.0'", 0o755);
+    stub(&bin, "aria2c", "echo", 0o644);
+    let aria2c = stub(&other, "aria2c", "echo 'aria2 version 1.37.0' >&2", 0o755);
+    let deno = stub(&home, "deno", "echo", 0o755);
+    std::os::unix::fs::symlink(&ffmpeg, home.join("ffmpeg")).unwrap();
+
+    let locator = Locator {
+        path: vec![bin.clone(), other.clone(), root.join("missing")],
+        extensions: Vec::new(),
+        home_dirs: vec![home.clone()],
+    };
+
+    // Megabytes of output are drained instead of stalling until the timeout
+    let found = xdg::locate(&locator, "ffmpeg");
+    assert_eq!(found.len(), 2, "the symlink in home is the same install");
+    assert_eq!(found[0].path, ffmpeg);
+    assert_eq!(found[0].source, "path");
+    assert_eq!(found[0].version.as_deref(), Some("6.1.1"));
+    assert_eq!(
+        found[0].version_text.as_deref(),
+        Some("ffmpeg version 6.1.1 Copyright (c) 2000-2023")
+    );
+    assert_eq!(found[1].path, other.join("ffmpeg"));
+    assert_eq!(found[1].version.as_deref(), Some("5.0"));
+
+    // The stub without the executable bit is skipped and the version is read from stderr
+    let found = xdg::locate(&locator, "aria2c");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].path, aria2c);
+    assert_eq!(found[0].version.as_deref(), Some("1.37.0"));
+
+    // No version in the output
+    let found = xdg::locate(&locator, "deno");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].path, deno);
+    assert_eq!(found[0].source, "home");
+    assert_eq!(found[0].version, None);
+    assert_eq!(found[0].version_text, None);
+
+    assert!(xdg::locate(&locator, "bun").is_empty());
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,191 @@
+//! Command lines built by `open` from registered browser commands.
+
+use reg_utils::launch::{build_args, Family, OpenOptions};
+
+fn words(command: &[&str]) -> Vec<String> {
+    command.iter().map(|word| word.to_string()).collect()
+}
+
+const URL: &str = "http://127.0.0.1:8080/";
+const CHROME: &[&str] = &["chrome.exe", "--single-argument", "%1"];
+const EDGE: &[&str] = &[
+    "msedge.exe",
+    "--profile-directory=Default",
+    "--single-argument",
+    "%1",
+];
+const FIREFOX: &[&str] = &["firefox.exe", "-osint", "-url", "%1"];
+
+#[test]
+fn classify() {
+    let cases = [
+        ("ChromeHTML", CHROME, Family::Chromium),
+        ("MSEdgeHTM", EDGE, Family::Edge),
+        ("FirefoxURL-308046B0AF4A39CB", FIREFOX, Family::Firefox),
+        (
+            "brave-browser.desktop",
+            &["/usr/bin/brave-browser-stable", "%1"],
+            Family::Chromium,
+        ),
+        (
+            "org.mozilla.firefox.desktop",
+            &[
+                "/usr/bin/flatpak",
+                "run",
+                "org.mozilla.firefox",
+                "@@u",
+                "%1",
+                "@@",
+            ],
+            Family::Firefox,
+        ),
+        ("IE.HTTP", &["iexplore.exe", "%1"], Family::Other),
+        // Arguments after the URL do not count
+        ("Custom", &["launcher.exe", "%1", "chrome"], Family::Other),
+    ];
+    for (id, command, family) in cases {
+        assert_eq!(Family::classify(id, &words(command)), family, "{}", id);
+    }
+}
+
+#[test]
+fn plain() {
+    let plain = OpenOptions::default();
+    let cases: [(&[&str], &[&str]); 5] = [
+        (CHROME, &["chrome.exe", "--single-argument", URL]),
+        (&["app.exe", "%L"], &["app.exe", URL]),
+        (&["app.exe", "%*"], &["app.exe", URL]),
+        (
+            &["app.exe", "--url=%1"],
+            &["app.exe", "--url=http://127.0.0.1:8080/"],
+        ),
+        (&["app.exe", "--new-tab"], &["app.exe", "--new-tab", URL]),
+    ];
+    for (command, expected) in cases {
+        let command = words(command);
+        let family = Family::classify("", &command);
+        assert_eq!(build_args(&command, family, URL, &plain), expected);
+    }
+}
+
+#[test]
+fn options() {
+    let app = OpenOptions {
+        app_window: true,
+        ..Default::default()
+    };
+    let private = OpenOptions {
+        private: true,
+        ..Default::default()
+    };
+    let new_window = OpenOptions {
+        new_window: true,
+        ..Default::default()
+    };
+    let cases: [(&[&str], Family, OpenOptions, &[&str]); 7] = [
+        (
+            CHROME,
+            Family::Chromium,
+            app,
+            &["chrome.exe", "--app=http://127.0.0.1:8080/"],
+        ),
+        (
+            CHROME,
+            Family::Chromium,
+            private,
+            &["chrome.exe", "--incognito", "--single-argument", URL],
+        ),
+        (
+            EDGE,
+            Family::Edge,
+            new_window,
+            &[
+                "msedge.exe",
+                "--profile-directory=Default",
+                "--new-window",
+                "--single-argument",
+                URL,
+            ],
+        ),
+        (
+            EDGE,
+            Family::Edge,
+            private,
+            &[
+                "msedge.exe",
+                "--profile-directory=Default",
+                "--inprivate",
+                "--single-argument",
+                URL,
+            ],
+        ),
+        (
+            FIREFOX,
+            Family::Firefox,
+            private,
+            &["firefox.exe", "-private-window", URL],
+        ),
+        (
+            FIREFOX,
+            Family::Firefox,
+            app,
+            &["firefox.exe", "-new-window", URL],
+        ),
+        // Unknown browsers only get the URL
+        (&["app.exe", "%1"], Family::Other, app, &["app.exe", URL]),
+    ];
+    for (command, family, options, expected) in cases {
+        assert_eq!(build_args(&words(command), family, URL, &options), expected);
+    }
+}
+
+#[test]
+fn placeholder_inside_flatpak_forwarding() {
+    let command = words(&["flatpak", "run", "com.google.Chrome", "@@u", "%1", "@@"]);
+    let options = OpenOptions {
+        new_window: true,
+        ..Default::default()
+    };
+    let args = build_args(&command, Family::Chromium, URL, &options);
+    let expected = [
+        "flatpak",
+        "run",
+        "com.google.Chrome",
+        "@@u",
+        "--new-window",
+        "--single-argument",
+        URL,
+        "@@",
+    ];
+    assert_eq!(args, expected);
+}
+
+#[test]
+fn profile() {
+    let chrome = words(&["--profile-directory=Profile 1"]);
+    let options = OpenOptions {
+        profile: &chrome,
+        ..Default::default()
+    };
+    let expected = [
+        "msedge.exe",
+        "--profile-directory=Profile 1",
+        "--single-argument",
+        URL,
+    ];
+    assert_eq!(
+        build_args(&words(EDGE), Family::Edge, URL, &options),
+        expected
+    );
+    let firefox = words(&["-P", "work"]);
+    let options = OpenOptions {
+        private: true,
+        profile: &firefox,
+        ..Default::default()
+    };
+    let expected = ["firefox.exe", "-P", "work", "-private-window", URL];
+    assert_eq!(
+        build_args(&words(FIREFOX), Family::Firefox, URL, &options),
+        expected
+    );
+}
@@ -0,0 +1,283 @@
+//! Runs commands against the fixtures, checks each output against `schema/` and snapshots it.
+
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+fn schema(name: &str) -> jsonschema::Validator {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("schema")
+        .join(format!("{}.schema.json", name));
+    let text = std::fs::read_to_string(&path).unwrap();
+    jsonschema::validator_for(&serde_json::from_str(&text).unwrap()).unwrap()
+}
+
+fn assert_valid(name: &str, value: &Value) {
+    let errors: Vec<String> = schema(name)
+        .iter_errors(value)
+        .map(|err| format!("{} at {}", err, err.instance_path))
+        .collect();
+    assert!(errors.is_empty(), "{}: {:#?}", name, errors);
+}
+
+/// Runs reg-utils from the crate directory, without the user's environment.
+fn run(args: &[&str]) -> (Value, i32) {
+    let output = Command::new(env!("CARGO_BIN_EXE_reg-utils"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env_clear()
+        .env("HOME", "/home/user")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let value = serde_json::from_str(&stdout).unwrap();
+    (value, output.status.code().unwrap())
+}
+
+/// Checks the envelope and the data of `command`, and returns the pretty output.
+fn check(command: &str, args: &[&str]) -> String {
+    let (value, code) = run(args);
+    assert_valid("envelope", &value);
+    assert_eq!(code, if value["ok"] == true { 0 } else { 1 });
+    if value["ok"] == true {
+        assert_valid(command, &value["data"]);
+    }
+    serde_json::to_string_pretty(&value).unwrap()
+}
+
+const REG: &str = "tests/fixtures/system.reg";
+
+#[test]
+fn browser() {
+    insta::assert_snapshot!(check("browser", &["--reg", REG, "browser"]));
+    insta::assert_snapshot!(check("default", &["--reg", REG, "default"]));
+}
+
+#[test]
+fn software() {
+    insta::assert_snapshot!(check("software", &["--reg", REG, "software"]));
+    insta::assert_snapshot!(check(
+        "software",
+        &["--reg", REG, "software", "--all", "--name", "^runtime$"]
+    ));
+}
+
+#[test]
+fn registrations() {
+    let status = ["--reg", REG, "protocol", "status", "metafetch"];
+    insta::assert_snapshot!(check("protocol", &status));
+    let status = ["--reg", REG, "context-menu", "status"];
+    insta::assert_snapshot!(check("context-menu", &status));
+}
+
+/// The run script next to `dist`, which default command lines start with.
+fn run_script() -> String {
+    let exe = Path::new(env!("CARGO_BIN_EXE_reg-utils"));
+    let install = exe.parent().and_then(Path::parent).unwrap();
+    let script = install.join(if cfg!(windows) { "run.bat" } else { "run.sh" });
+    script.to_string_lossy().into_owned()
+}
+
+#[test]
+fn default_handler() {
+    let (value, code) = run(&["--reg", REG, "protocol", "register", "metadata-fetcher"]);
+    assert_eq!(code, 0);
+    let command = format!("\"{}\" \"%1\"", run_script());
+    assert_eq!(value["data"]["command"], command.as_str());
+    assert_eq!(
+        value["data"]["words"],
+        serde_json::json!([run_script(), "%1"])
+    );
+
+    let (value, code) = run(&["--reg", REG, "context-menu", "install"]);
+    assert_eq!(code, 0);
+    let command = serde_json::json!(command);
+    assert_eq!(
+        value["data"],
+        serde_json::json!({ "directory": command, "video": command, "url": command })
+    );
+}
+
+#[test]
+fn open() {
+    let args = [
+        "--reg",
+        REG,
+        "open",
+        "https://example.com/",
+        "--app-window",
+        "--dry-run",
+    ];
+    insta::assert_snapshot!(check("open", &args));
+}
+
+#[test]
+fn cookies() {
+    let profile = "tests/fixtures/profiles/firefox/Profiles/x8kf2q1b.default-release";
+    let args = ["cookies", "--profile", profile, "--domain", "bilibili.com"];
+    insta::assert_snapshot!(check("cookies", &args));
+    let args = [&args[..], &["--format", "netscape"]].concat();
+    insta::assert_snapshot!(check("cookies", &args));
+}
+
+#[test]
+fn history() {
+    let chrome = "tests/fixtures/profiles/chrome/Profile 1";
+    let firefox = "tests/fixtures/profiles/firefox/Profiles/x8kf2q1b.default-release";
+    let args = [
+        "history",
+        "--dir",
+        chrome,
+        "--dir",
+        firefox,
+        "--since",
+        "2024-05-01",
+        "--until",
+        "2024-05-08",
+    ];
+    let envelope = [&args[..], &["--envelope"]].concat();
+    insta::assert_snapshot!(check("history", &envelope));
+
+    // One item per line by default, `--source` takes the spelling of `source`
+    let lines = |extra: &[&str]| -> Vec<Value> {
+        let output = Command::new(env!("CARGO_BIN_EXE_reg-utils"))
+            .args([&args[..], extra].concat())
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    };
+    let items = lines(&["--site", "pixiv"]);
+    let urls: Vec<&Value> = items.iter().map(|item| &item["url"]).collect();
+    let expected = [
+        "https://www.pixiv.net/en/artworks/44298467",
+        "https://www.pixiv.net/artworks/20",
+    ];
+    assert_eq!(urls, expected);
+    assert_eq!(lines(&["--site", "pixiv", "--jsonl"]), items);
+    for source in ["bookmark", "history"] {
+        let items = lines(&["--source", source]);
+        assert!(!items.is_empty());
+        assert!(
+            items.iter().all(|item| item["source"] == source),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        lines(&["--source", "bookmarks"]),
+        lines(&["--source", "bookmark"])
+    );
+
+    let (value, code) = run(&["history", "--since", "9223372036854775807w"]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        value["error"]["message"],
+        "Invalid time: 9223372036854775807w"
+    );
+}
+
+#[test]
+fn proxy() {
+    insta::assert_snapshot!(check("proxy", &["--reg", REG, "proxy"]));
+}
+
+#[test]
+fn system() {
+    insta::assert_snapshot!(check("system", &["--reg", REG, "system"]));
+}
+
+#[test]
+fn url_info() {
+    let args = ["url-info", "tests/fixtures/example.url"];
+    insta::assert_snapshot!(check("url-info", &args));
+}
+
+/// An existing file left alone is a success, with the same exit code as any other.
+#[test]
+fn url_shortcut_exists() {
+    let options = r#"{"url": "https://example.com/", "savePath": "tests/fixtures/example.url", "overwrite": "fail"}"#;
+    insta::assert_snapshot!(check("url-shortcut", &["url-shortcut", options]));
+}
+
+#[test]
+fn shortcut_info() {
+    let args = ["shortcut-info", "tests/fixtures/lnk/file.lnk"];
+    insta::assert_snapshot!(check("shortcut-info", &args));
+    let args = ["shortcut-info", "tests/fixtures/lnk/network.lnk"];
+    insta::assert_snapshot!(check("shortcut-info", &args));
+    let args = ["shortcut-info", "tests/fixtures/lnk/truncated.lnk"];
+    insta::assert_snapshot!(check("shortcut-info", &args));
+}
+
+#[cfg(not(windows))]
+#[test]
+fn known_folder() {
+    insta::assert_snapshot!(check("known-folder", &["known-folder", "Downloads"]));
+    insta::assert_snapshot!(check("known-folder", &["known-folder", "--all"]));
+}
+
+#[test]
+fn errors() {
+    insta::assert_snapshot!(check("", &["--pretty"]));
+    insta::assert_snapshot!(check("", &["frobnicate"]));
+    insta::assert_snapshot!(check("", &["software", "--name"]));
+    insta::assert_snapshot!(check("", &["protocol", "status", "1http"]));
+    insta::assert_snapshot!(check("", &["url-info", REG]));
+    // The message comes from the OS
+    let (value, _) = run(&["url-info", "tests/fixtures/missing.url"]);
+    assert_eq!(value["error"]["kind"], "notFound");
+}
+
+#[test]
+fn serve() {
+    use std::io::Write;
+    use std::process::Stdio;
+    let mut child = Command::new(env!("CARGO_BIN_EXE_reg-utils"))
+        .args(["serve", "--reg", REG])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let requests = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"capabilities"}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"software","params":["--all"]}"#,
+        r#"{"jsonrpc":"2.0","id":3,"method":"protocol","params":["status","metafetch"]}"#,
+        r#"{"jsonrpc":"2.0","method":"browser"}"#,
+        r#"{"jsonrpc":"2.0","id":4,"method":"frobnicate"}"#,
+        r#"{"jsonrpc":"2.0","id":5,"method":"software","params":["--name"]}"#,
+        "not json",
+    ];
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "{}", requests.join("\n")).unwrap();
+    // Closing stdin ends the session once every request is answered
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let mut responses: Vec<Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    responses.sort_by_key(|response| response["id"].as_i64().unwrap_or(0));
+    let ids: Vec<Option<i64>> = responses
+        .iter()
+        .map(|response| response["id"].as_i64())
+        .collect();
+    assert_eq!(ids, [None, Some(1), Some(2), Some(3), Some(4), Some(5)]);
+    let commands = &responses[1]["result"]["commands"];
+    assert!(commands["known-folder"]["platforms"].is_array());
+    assert_valid("software", &responses[2]["result"]);
+    assert_valid("protocol", &responses[3]["result"]);
+    let codes: Vec<Option<i64>> = [0, 4, 5]
+        .iter()
+        .map(|&i| responses[i]["error"]["code"].as_i64())
+        .collect();
+    assert_eq!(codes, [Some(-32700), Some(-32601), Some(-32602)]);
+}
@@ -0,0 +1,71 @@
+//! Profiles read from the user data directories in `tests/fixtures/profiles`.
+
+use reg_utils::profile::{self, Profile};
+use std::path::{Path, PathBuf};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/profiles")
+        .join(name)
+}
+
+fn summary(profiles: &[Profile]) -> Vec<(String, Vec<String>, bool)> {
+    profiles
+        .iter()
+        .map(|profile| (profile.name.clone(), profile.args.clone(), profile.default))
+        .collect()
+}
+
+fn strings(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+#[test]
+fn chromium() {
+    let root = fixture("chrome");
+    let profiles = profile::chromium(&root).unwrap();
+    let expected = [
+        ("Person 1", "Default", false),
+        ("bilibili", "Profile 1", true),
+        ("pixiv", "Profile 3", false),
+    ];
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|&(name, dir, default)| {
+            let args = vec![format!("--profile-directory={}", dir)];
+            (name.to_string(), args, default)
+        })
+        .collect();
+    assert_eq!(summary(&profiles), expected);
+    assert_eq!(profiles[1].path, root.join("Profile 1"));
+    assert!(profiles[1].matches("bilibili"));
+    assert!(profiles[1].matches("Profile 1"));
+    assert!(!profiles[1].matches("pixiv"));
+}
+
+#[test]
+fn firefox() {
+    let root = fixture("firefox");
+    let profiles = profile::firefox(&root).unwrap();
+    // The installation's default wins over `Default=1`
+    let expected = [
+        (
+            "default-release".to_string(),
+            strings(&["-P", "default-release"]),
+            true,
+        ),
+        ("default".to_string(), strings(&["-P", "default"]), false),
+        ("pixiv".to_string(), strings(&["-P", "pixiv"]), false),
+    ];
+    assert_eq!(summary(&profiles), expected);
+    let path = root.join("Profiles").join("x8kf2q1b.default-release");
+    assert_eq!(profiles[0].path, path);
+    assert_eq!(profiles[2].path, Path::new("/mnt/data/firefox/pixiv"));
+}
+
+#[test]
+fn missing() {
+    let root = fixture("missing");
+    assert!(profile::chromium(&root).is_err());
+    assert!(profile::firefox(&root).is_err());
+}
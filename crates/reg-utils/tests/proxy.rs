@@ -0,0 +1,128 @@
+//! Proxy settings parsed from each source into the normalized description.
+
+use reg_utils::proxy::{self, Proxy};
+use std::collections::HashMap;
+
+fn some(value: &str) -> Option<String> {
+    Some(value.to_string())
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn proxy_server() {
+    let mut parsed = Proxy::default();
+    proxy::parse_proxy_server("127.0.0.1:7890", &mut parsed);
+    assert_eq!(parsed.http, some("http://127.0.0.1:7890"));
+    assert_eq!(parsed.https, some("http://127.0.0.1:7890"));
+    assert_eq!(parsed.socks, None);
+
+    let mut parsed = Proxy::default();
+    let text =
+        "http=proxy.lan:8080;HTTPS=https://secure.lan:8443/;ftp=ftp.lan:21;socks=127.0.0.1:1080";
+    proxy::parse_proxy_server(text, &mut parsed);
+    assert_eq!(parsed.http, some("http://proxy.lan:8080"));
+    assert_eq!(parsed.https, some("https://secure.lan:8443"));
+    assert_eq!(parsed.socks, some("socks4://127.0.0.1:1080"));
+    assert_eq!(parsed.mode(), "manual");
+
+    let bypass = proxy::parse_bypass("localhost; 127.*;;<local>");
+    assert_eq!(bypass, strings(&["localhost", "127.*", "<local>"]));
+}
+
+#[test]
+fn environment() {
+    let vars = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    };
+    let env = vars(&[
+        ("http_proxy", "127.0.0.1:7890"),
+        ("HTTP_PROXY", "http://ignored:1"),
+        ("HTTPS_PROXY", "http://127.0.0.1:7890/"),
+        ("all_proxy", "socks5h://127.0.0.1:7891"),
+        ("no_proxy", "localhost,.lan"),
+    ]);
+    let parsed = proxy::from_env(|name| env.get(name).cloned()).unwrap();
+    let expected = Proxy {
+        source: Some("environment"),
+        pac: None,
+        http: some("http://127.0.0.1:7890"),
+        https: some("http://127.0.0.1:7890"),
+        socks: some("socks5h://127.0.0.1:7891"),
+        bypass: strings(&["localhost", ".lan"]),
+    };
+    assert_eq!(parsed, expected);
+
+    // A non-SOCKS `all_proxy` fills in the missing protocols
+    let env = vars(&[("ALL_PROXY", "http://proxy:3128"), ("https_proxy", "")]);
+    let parsed = proxy::from_env(|name| env.get(name).cloned()).unwrap();
+    assert_eq!(parsed.https, some("http://proxy:3128"));
+    assert_eq!(parsed.socks, None);
+
+    assert_eq!(proxy::from_env(|_| None), None);
+}
+
+#[test]
+fn gnome() {
+    let dump = "[/]\n\
+        ignore-hosts=['localhost', '127.0.0.0/8', '::1']\n\
+        mode='manual'\n\
+        \n\
+        [http]\n\
+        host='127.0.0.1'\n\
+        port=7890\n\
+        \n\
+        [socks]\n\
+        host='127.0.0.1'\n\
+        port=7891\n";
+    let expected = Proxy {
+        source: Some("gnome"),
+        pac: None,
+        http: some("http://127.0.0.1:7890"),
+        https: None,
+        socks: some("socks5://127.0.0.1:7891"),
+        bypass: strings(&["localhost", "127.0.0.0/8", "::1"]),
+    };
+    assert_eq!(proxy::parse_gnome(dump), expected);
+
+    let dump = "[/]\nautoconfig-url='http://wpad.lan/proxy.pac'\nmode='auto'\n";
+    let parsed = proxy::parse_gnome(dump);
+    assert_eq!(parsed.pac, some("http://wpad.lan/proxy.pac"));
+    assert_eq!(parsed.mode(), "auto");
+
+    // The host is kept for when the mode is switched back
+    let parsed = proxy::parse_gnome("[/]\nmode='none'\n\n[http]\nhost='127.0.0.1'\n");
+    assert_eq!(parsed.mode(), "direct");
+    assert_eq!(parsed.source, Some("gnome"));
+    assert_eq!(proxy::parse_gnome(""), Proxy::default());
+}
+
+#[test]
+fn kde() {
+    let text = "[Proxy Settings]\n\
+        NoProxyFor=localhost,127.0.0.1\n\
+        Proxy Config Script=\n\
+        ProxyType=1\n\
+        httpProxy=http://127.0.0.1 7890\n\
+        httpsProxy=http://127.0.0.1:7890\n\
+        socksProxy=socks://127.0.0.1 0\n";
+    let expected = Proxy {
+        source: Some("kde"),
+        pac: None,
+        http: some("http://127.0.0.1:7890"),
+        https: some("http://127.0.0.1:7890"),
+        socks: some("socks://127.0.0.1"),
+        bypass: strings(&["localhost", "127.0.0.1"]),
+    };
+    assert_eq!(proxy::parse_kde(text), expected);
+
+    let text = "[Proxy Settings]\nProxyType=2\nProxy Config Script=file:///etc/proxy.pac\n";
+    assert_eq!(proxy::parse_kde(text).pac, some("file:///etc/proxy.pac"));
+    let parsed = proxy::parse_kde("[Proxy Settings]\nProxyType=0\nhttpProxy=http://a 1\n");
+    assert_eq!(parsed.mode(), "direct");
+}
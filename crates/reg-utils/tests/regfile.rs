@@ -0,0 +1,106 @@
+//! The `.reg` parser behind `--reg`, against exports written the way regedit writes them.
+
+use reg_utils::regfile::MemoryRegistry;
+use reg_utils::registry::{Hive, Key, Registry, Value};
+
+const EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+; A comment
+[HKEY_CURRENT_USER\Software\Example]
+@="Default \"quoted\" C:\\Path"
+"Count"=dword:0000002a
+"Big"=hex(b):00,00,00,00,01,00,00,00
+"Blob"=hex:de,ad,be,ef
+"Expand"=hex(2):25,00,53,00,79,00,73,00,74,00,65,00,6d,00,52,00,6f,00,6f,00,74,\
+  00,25,00,5c,00,6e,00,6f,00,74,00,65,00,70,00,61,00,64,00,2e,00,65,00,78,00,\
+  65,00,00,00
+"Multi"=hex(7):66,00,69,00,72,00,73,00,74,00,00,00,73,00,65,00,63,00,6f,00,6e,\
+  00,64,00,00,00,00,00
+"Removed"="soon"
+
+[HKEY_CURRENT_USER\Software\Example\Child]
+"Name"="child"
+
+[HKEY_CURRENT_USER\Software\Example]
+"Removed"=-
+
+[HKLM\SOFTWARE\Gone\Deep]
+"Name"="gone"
+
+[-HKEY_LOCAL_MACHINE\SOFTWARE\Gone]
+"#;
+
+fn value(reg: &MemoryRegistry, hive: Hive, path: &str, name: &str) -> Option<Value> {
+    reg.open(hive, path).ok()?.get_raw_value(name).ok()
+}
+
+#[test]
+fn values() {
+    let reg = MemoryRegistry::parse(EXPORT).unwrap();
+    let get = |name: &str| value(&reg, Hive::CurrentUser, r"Software\Example", name);
+    let default = r#"Default "quoted" C:\Path"#.to_string();
+    assert_eq!(get(""), Some(Value::String(default)));
+    assert_eq!(get("count"), Some(Value::Dword(42)));
+    assert_eq!(get("Big"), Some(Value::Qword(1 << 32)));
+    assert_eq!(
+        get("Blob"),
+        Some(Value::Binary(vec![0xde, 0xad, 0xbe, 0xef]))
+    );
+    // Continuation lines join before the data is parsed
+    let expand = r"%SystemRoot%\notepad.exe".to_string();
+    assert_eq!(get("Expand"), Some(Value::ExpandString(expand)));
+    let multi = vec!["first".to_string(), "second".to_string()];
+    assert_eq!(get("Multi"), Some(Value::MultiString(multi)));
+
+    let key = reg.open(Hive::CurrentUser, r"SOFTWARE\example").unwrap();
+    assert_eq!(key.enum_keys(), ["Child"]);
+    assert_eq!(key.get_value::<u32>("Count").unwrap(), 42);
+    assert!(key.get_value::<String>("Count").is_err());
+}
+
+#[test]
+fn deletions() {
+    let reg = MemoryRegistry::parse(EXPORT).unwrap();
+    assert_eq!(
+        value(&reg, Hive::CurrentUser, r"Software\Example", "Removed"),
+        None
+    );
+    assert!(reg.open(Hive::LocalMachine, r"SOFTWARE\Gone").is_err());
+    assert!(reg.open(Hive::LocalMachine, "SOFTWARE").is_ok());
+}
+
+#[test]
+fn invalid() {
+    let errors = [
+        "[HKEY_NOWHERE\\Software]",
+        "\"Orphan\"=\"value before any key\"",
+        "[HKCU\\Software]\n\"Name\"=dword:nothex",
+        "[HKCU\\Software]\n\"Name\"=hex(7):zz",
+        "[HKCU\\Software]\n\"Unterminated=\"value\"",
+        "[HKCU\\Software]\n\"Name\" \"value\"",
+    ];
+    for text in errors {
+        let err = MemoryRegistry::parse(text).err().expect(text);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn encodings() {
+    let dir = std::env::temp_dir().join(format!("reg-utils-regfile-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let text = "Windows Registry Editor Version 5.00\r\n\r\n[HKEY_CURRENT_USER\\Software\\Example]\r\n\"Name\"=\"ビリビリ\"\r\n";
+
+    // regedit writes UTF-16LE with a BOM
+    let mut utf16 = vec![0xFF, 0xFE];
+    utf16.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    let utf8_bom = [b"\xEF\xBB\xBF".as_slice(), text.as_bytes()].concat();
+    for (name, bytes) in [("utf16.reg", utf16), ("utf8.reg", utf8_bom)] {
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        let reg = MemoryRegistry::load(&path).unwrap();
+        let name = value(&reg, Hive::CurrentUser, r"Software\Example", "Name");
+        assert_eq!(name, Some(Value::String("ビリビリ".to_string())));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,192 @@
+//! Protocols, context menus, autostart and Send To against a `MemoryRegistry` and temporary folders.
+
+use json::{object, JsonValue};
+use reg_utils::autostart::{self, Method};
+use reg_utils::explorer::{self, Verb};
+use reg_utils::lnk::{ShellLink, ShowCommand};
+use reg_utils::protocol::{self, Handler};
+use reg_utils::regfile::MemoryRegistry;
+use reg_utils::registry::{Hive, Key, Registry, RegistryMut, Value};
+use reg_utils::shortcut;
+use std::io::ErrorKind;
+
+const SCRIPT: &str = r"C:\Metadata Fetcher\run.bat";
+const ICON: &str = r"C:\Metadata Fetcher\dist\favicon.ico";
+
+fn value(reg: &MemoryRegistry, path: &str, name: &str) -> Option<String> {
+    let key = reg.open(Hive::CurrentUser, path).ok()?;
+    key.get_value(name).ok()
+}
+
+#[test]
+fn context_menu() {
+    let mut reg = MemoryRegistry::default();
+    let verb = Verb {
+        label: "Metadata Fetcher".into(),
+        command: format!("\"{}\" \"%1\"", SCRIPT),
+        icon: Some(ICON.into()),
+    };
+    explorer::install(&mut reg, &verb).unwrap();
+    for shell in [
+        r"Directory\shell",
+        r"SystemFileAssociations\video\shell",
+        r"InternetShortcut\shell",
+    ] {
+        let path = format!(r"Software\Classes\{}\MetadataFetcher", shell);
+        let value = |name: &str| value(&reg, &path, name);
+        assert_eq!(value("").as_deref(), Some("Metadata Fetcher"), "{}", shell);
+        assert_eq!(value("MultiSelectModel").as_deref(), Some("Player"));
+        assert_eq!(value("Icon").as_deref(), Some(ICON));
+        assert_eq!(
+            self::value(&reg, &format!(r"{}\command", path), "").as_deref(),
+            Some(r#""C:\Metadata Fetcher\run.bat" "%1""#)
+        );
+    }
+    let command = r#""C:\Metadata Fetcher\run.bat" "%1""#;
+    assert_eq!(
+        explorer::status(&reg),
+        object! { directory: command, video: command, url: command }
+    );
+
+    // Reinstalling without an icon drops the old one
+    let verb = Verb { icon: None, ..verb };
+    explorer::install(&mut reg, &verb).unwrap();
+    let path = r"Software\Classes\Directory\shell\MetadataFetcher";
+    assert_eq!(value(&reg, path, "Icon"), None);
+
+    explorer::uninstall(&mut reg).unwrap();
+    assert_eq!(
+        explorer::status(&reg),
+        object! { directory: null, video: null, url: null }
+    );
+    assert!(reg.open(Hive::CurrentUser, path).is_err());
+}
+
+#[test]
+fn send_to() {
+    let dir = std::env::temp_dir().join(format!("reg-utils-send-to-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let save_path = dir.join("Metadata Fetcher.lnk");
+    let options = shortcut::send_to(SCRIPT, ICON, save_path.to_str().unwrap());
+    assert_eq!(options["workingDir"], r"C:\Metadata Fetcher");
+    shortcut::create(&options).unwrap();
+
+    // The selected paths are the only arguments, so the link must not add any
+    let link = ShellLink::load(&save_path).unwrap();
+    assert_eq!(link.target().as_deref(), Some(SCRIPT));
+    assert_eq!(link.arguments, None);
+    assert_eq!(link.show_command, ShowCommand::Minimized);
+    let json = JsonValue::from(&link);
+    assert_eq!(json["workingDir"], r"C:\Metadata Fetcher");
+    assert_eq!(json["iconLocation"], ICON);
+    assert_eq!(json["iconIndex"], 0);
+    assert_eq!(json["description"], "Metadata Fetcher");
+    assert_eq!(json["runAsAdmin"], false);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn autostart() {
+    let dir = std::env::temp_dir().join(format!("reg-utils-autostart-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let run_key = r"Software\Microsoft\Windows\CurrentVersion\Run";
+    let link_path = dir.join("Metadata Fetcher.lnk");
+    let mut reg = MemoryRegistry::default();
+
+    autostart::enable(&mut reg, Some(&dir), Method::Startup, SCRIPT).unwrap();
+    let link = ShellLink::load(&link_path).unwrap();
+    assert_eq!(link.target().as_deref(), Some(SCRIPT));
+    assert_eq!(link.arguments.as_deref(), Some("--tray"));
+    assert_eq!(link.working_dir.as_deref(), Some(r"C:\Metadata Fetcher"));
+    assert_eq!(link.show_command, ShowCommand::Minimized);
+    let entries = autostart::status(&reg, Some(&dir));
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].method, "startup");
+    assert_eq!(entries[0].location, link_path.to_str().unwrap());
+    assert_eq!(entries[0].target.as_deref(), Some(SCRIPT));
+
+    // Switching the method removes the shortcut
+    autostart::enable(&mut reg, Some(&dir), Method::Run, SCRIPT).unwrap();
+    assert!(!link_path.exists());
+    let command = r#"cmd.exe /c start "" /min "C:\Metadata Fetcher\run.bat" --tray"#;
+    assert_eq!(
+        value(&reg, run_key, "Metadata Fetcher").as_deref(),
+        Some(command)
+    );
+    let entries = autostart::status(&reg, Some(&dir));
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].method, "run");
+    assert_eq!(entries[0].command.as_deref(), Some(command));
+    assert_eq!(entries[0].target.as_deref(), Some(SCRIPT));
+
+    // Values written before `--tray` still point at the script
+    let old = r#"cmd.exe /c start "" /min "C:\Metadata Fetcher\run.bat""#;
+    let old = Value::String(old.to_string());
+    reg.set_value(Hive::CurrentUser, run_key, "Metadata Fetcher", old)
+        .unwrap();
+    let entries = autostart::status(&reg, Some(&dir));
+    assert_eq!(entries[0].target.as_deref(), Some(SCRIPT));
+
+    autostart::disable(&mut reg, Some(&dir)).unwrap();
+    assert!(autostart::status(&reg, Some(&dir)).is_empty());
+    assert_eq!(value(&reg, run_key, "Metadata Fetcher"), None);
+    let err = autostart::enable(&mut reg, None, Method::Startup, SCRIPT).unwrap_err();
+    assert_eq!(err.to_string(), "No Startup folder");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn protocol_owner() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/system.reg");
+    let mut reg = MemoryRegistry::load(path).unwrap();
+    let handler = |scheme: &str| Handler {
+        scheme: scheme.to_string(),
+        command: format!("\"{}\" \"%1\"", SCRIPT),
+        name: "Metadata Fetcher".into(),
+        icon: Some(ICON.into()),
+    };
+    let install = r"C:\Metadata Fetcher";
+    let command = r"Software\Classes\metafetch\shell\open\command";
+
+    for scheme in ["http", "HTTPS", "file", "mailto"] {
+        let err = protocol::register(&mut reg, &handler(scheme), install).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{}", scheme);
+        let err = protocol::unregister(&mut reg, scheme, install).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{}", scheme);
+    }
+
+    // The fixture key runs a program of another install, which keeps it
+    let other = r"D:\Other App";
+    let err = protocol::register(&mut reg, &handler("metafetch"), other).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert_eq!(err.to_string(), "metafetch is registered by another app");
+    let err = protocol::unregister(&mut reg, "metafetch", other).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert_eq!(
+        value(&reg, command, "").as_deref(),
+        Some(r#""C:\Metadata Fetcher\run.bat" "%1""#)
+    );
+
+    // A key without a command is not ours either
+    reg.set_value(
+        Hive::CurrentUser,
+        r"Software\Classes\bare",
+        "URL Protocol",
+        Value::String(String::new()),
+    )
+    .unwrap();
+    assert!(protocol::register(&mut reg, &handler("bare"), install).is_err());
+
+    protocol::register(&mut reg, &handler("metafetch"), install).unwrap();
+    assert_eq!(
+        value(&reg, r"Software\Classes\metafetch\DefaultIcon", "").as_deref(),
+        Some(ICON)
+    );
+    protocol::unregister(&mut reg, "metafetch", install).unwrap();
+    assert!(!protocol::status(&reg, "metafetch").registered);
+    // Nothing to remove is not an error
+    protocol::unregister(&mut reg, "metafetch", install).unwrap();
+    protocol::register(&mut reg, &handler("new-scheme"), other).unwrap();
+}
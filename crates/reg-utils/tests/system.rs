@@ -0,0 +1,94 @@
+//! Version, language and time zone read from the registry fixture and from os-release files.
+
+use reg_utils::regfile::MemoryRegistry;
+use reg_utils::system::{self, SystemInfo};
+use std::path::Path;
+
+fn fixture() -> MemoryRegistry {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/system.reg");
+    MemoryRegistry::load(path).unwrap()
+}
+
+fn some(value: &str) -> Option<String> {
+    Some(value.to_string())
+}
+
+#[test]
+fn registry() {
+    let info = system::from_registry(&fixture());
+    let expected = SystemInfo {
+        // Windows 11 still reports Windows 10 in ProductName
+        name: some("Windows 11 Pro"),
+        version: some("23H2"),
+        build: some("22631"),
+        ubr: Some(4373),
+        // The display language wins over the regional format
+        language: some("zh-CN"),
+        time_zone: some("Asia/Shanghai"),
+        windows_time_zone: some("China Standard Time"),
+    };
+    assert_eq!(info, expected);
+    assert_eq!(
+        system::from_registry(&MemoryRegistry::default()),
+        SystemInfo::default()
+    );
+}
+
+#[test]
+fn windows_zones() {
+    assert_eq!(
+        system::iana_time_zone("Tokyo Standard Time"),
+        Some("Asia/Tokyo")
+    );
+    assert_eq!(system::iana_time_zone("UTC"), Some("Etc/UTC"));
+    assert_eq!(system::iana_time_zone("UTC-08"), Some("Etc/GMT+8"));
+    assert_eq!(system::iana_time_zone("Mars Standard Time"), None);
+}
+
+#[test]
+fn os_release() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/os-release");
+    let fields = system::parse_os_release(&std::fs::read_to_string(path).unwrap());
+    let get = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(get("PRETTY_NAME"), Some("Ubuntu 24.04.1 LTS"));
+    assert_eq!(get("ID"), Some("ubuntu"));
+    assert_eq!(get("HOME_URL"), Some("https://www.ubuntu.com/"));
+
+    let zone = |target: &str| system::zone_from_link(Path::new(target));
+    assert_eq!(
+        zone("/usr/share/zoneinfo/Asia/Shanghai"),
+        some("Asia/Shanghai")
+    );
+    assert_eq!(
+        zone("../usr/share/zoneinfo/posix/Europe/Berlin"),
+        some("Europe/Berlin")
+    );
+    assert_eq!(zone("/usr/share/zoneinfo/UTC"), some("UTC"));
+    assert_eq!(zone("/etc/localtime.backup"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn files() {
+    let root = std::env::temp_dir().join(format!("reg-utils-system-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("etc")).unwrap();
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/os-release");
+    std::fs::copy(fixture, root.join("etc/os-release")).unwrap();
+    let zone = "/usr/share/zoneinfo/America/New_York";
+    std::os::unix::fs::symlink(zone, root.join("etc/localtime")).unwrap();
+    let info = system::from_files(&root, Some("ja_JP"));
+    std::fs::remove_dir_all(&root).unwrap();
+    let expected = SystemInfo {
+        name: some("Ubuntu"),
+        version: some("24.04"),
+        language: some("ja-JP"),
+        time_zone: some("America/New_York"),
+        ..Default::default()
+    };
+    assert_eq!(info, expected);
+}
@@ -0,0 +1,154 @@
+//! `.url` files: UTF-7, file names, and writing what is read back.
+
+use json::object;
+use reg_utils::urlfile::{self, decode_utf7, encode_utf7, file_name, InternetShortcut};
+use std::io::ErrorKind;
+
+#[test]
+fn utf7() {
+    // The examples of RFC 2152, with the optional `-` after a run always written
+    for (text, encoded) in [
+        ("Hi Mom -☺-!", "Hi Mom -+Jjo--!"),
+        ("日本語", "+ZeVnLIqe-"),
+        ("A≢Α.", "A+ImIDkQ-."),
+        ("1 + 1 = 2", "1 +- 1 = 2"),
+        ("C:\\Users\\Zoë", "C:+AFw-Users+AFw-Zo+AOs-"),
+        ("😀", "+2D3eAA-"),
+        ("", ""),
+    ] {
+        assert_eq!(encode_utf7(text), encoded, "{}", text);
+        assert_eq!(decode_utf7(encoded), text, "{}", encoded);
+    }
+    // A run may also end at the first character outside base64
+    assert_eq!(decode_utf7("A+ImIDkQ."), "A≢Α.");
+    assert_eq!(decode_utf7("+ZeVnLIqe"), "日本語");
+
+    let text: String = (0x20..0x800).filter_map(char::from_u32).collect();
+    assert_eq!(decode_utf7(&encode_utf7(&text)), text);
+}
+
+#[test]
+fn file_names() {
+    for (title, name) in [
+        ("Example Domain", "Example Domain"),
+        (r#"a/b\c:d*e?f"g<h>i|j"#, "a_b_c_d_e_f_g_h_i_j"),
+        ("  tabs\tand\nnewlines  ", "tabs and newlines"),
+        ("ends with dots...", "ends with dots"),
+        ("CON", "_CON"),
+        ("com1", "_com1"),
+        ("LPT9", "_LPT9"),
+        ("CONSOLE", "CONSOLE"),
+        ("", "_"),
+        ("...", "_"),
+        ("视频 - 哔哩哔哩", "视频 - 哔哩哔哩"),
+    ] {
+        assert_eq!(file_name(title), name, "{}", title);
+        // Already safe names stay as they are
+        assert_eq!(file_name(name), name);
+    }
+    let long = "é".repeat(300);
+    assert_eq!(file_name(&long).chars().count(), 120);
+}
+
+#[test]
+fn round_trip() {
+    let dir = std::env::temp_dir().join(format!("reg-utils-urlfile-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let options = object! {
+        url: "https://example.com/视频?q=a b",
+        title: "视频: <Part 1/2>",
+        dir: dir.to_str().unwrap(),
+        iconFile: r"C:\Icons\été.ico",
+        iconIndex: 3,
+        hotkey: "Ctrl+Alt+V",
+        properties: { WorkingDirectory: r"C:\Téléchargements", ShowCommand: 7 },
+    };
+    let result = urlfile::create(&options).unwrap();
+    let save_path = dir.join("视频_ _Part 1_2_.url");
+    assert_eq!(result["savePath"], save_path.to_str().unwrap());
+    assert_eq!(result["status"], "created");
+
+    let text = std::fs::read_to_string(&save_path).unwrap();
+    assert!(text.is_ascii());
+    let shortcut = InternetShortcut::load(&save_path).unwrap();
+    assert_eq!(shortcut.url, "https://example.com/视频?q=a b");
+    assert_eq!(shortcut.icon_file.as_deref(), Some(r"C:\Icons\été.ico"));
+    assert_eq!(shortcut.icon_index, Some(3));
+    assert_eq!(result["shortcut"], json::JsonValue::from(&shortcut));
+    assert_eq!(shortcut.properties.len(), 2);
+    assert_eq!(
+        shortcut.properties["WorkingDirectory"],
+        r"C:\Téléchargements"
+    );
+    assert_eq!(shortcut.properties["ShowCommand"], "7");
+    assert_eq!(
+        InternetShortcut::parse(&shortcut.to_string())
+            .unwrap()
+            .to_string(),
+        text
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn injection() {
+    let error = |shortcut: &InternetShortcut| {
+        let err = shortcut.check().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        err.to_string()
+    };
+    let shortcut = |key: &str, value: &str| InternetShortcut {
+        url: "https://example.com/".into(),
+        properties: [(key.to_string(), value.to_string())].into(),
+        ..Default::default()
+    };
+    assert!(shortcut("WorkingDirectory", "C:\\").check().is_ok());
+    assert_eq!(
+        error(&shortcut("A\r\nURL", "x")),
+        r#"Invalid property name: "A\r\nURL""#
+    );
+    assert_eq!(
+        error(&shortcut("A=B", "x")),
+        r#"Invalid property name: "A=B""#
+    );
+    assert_eq!(
+        error(&shortcut("[Section]", "x")),
+        r#"Invalid property name: "[Section]""#
+    );
+    assert_eq!(
+        error(&shortcut(";A", "x")),
+        r#"Invalid property name: ";A""#
+    );
+    assert_eq!(
+        error(&shortcut(" A", "x")),
+        r#"Invalid property name: " A""#
+    );
+    assert_eq!(error(&shortcut("", "x")), r#"Invalid property name: """#);
+    assert_eq!(
+        error(&shortcut("A", "x\r\nURL=https://evil.example/")),
+        "A must be a single line"
+    );
+    for key in ["URL", "url", "IconFile", "IconIndex", "HOTKEY"] {
+        assert!(error(&shortcut(key, "x")).ends_with("is not a property, it has its own option"));
+    }
+
+    let mut bad = shortcut("A", "x");
+    bad.url = "https://example.com/\n[Other]".into();
+    assert_eq!(error(&bad), "url must be a single line");
+    bad.url = "https://example.com/".into();
+    bad.icon_file = Some("a.ico\nURL=x".into());
+    assert_eq!(error(&bad), "iconFile must be a single line");
+
+    let options = object! {
+        url: "https://example.com/",
+        savePath: "unused.url",
+        properties: { "URL": "https://evil.example/" },
+    };
+    let err = urlfile::create(&options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "URL is not a property, it has its own option"
+    );
+    assert!(!std::path::Path::new("unused.url").exists());
+}
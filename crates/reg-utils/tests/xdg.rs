@@ -0,0 +1,456 @@
+//! Desktop entries and `mimeapps.list` lookups against the XDG tree in `tests/fixtures/xdg`.
+#![cfg(not(windows))]
+
+use json::{object, JsonValue};
+use reg_utils::protocol::Handler;
+use reg_utils::xdg::{self, DesktopEntry, Xdg};
+use reg_utils::BrowserInfo;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/xdg")
+}
+
+fn fixture() -> Xdg {
+    let root = root();
+    Xdg {
+        home: Some(root.join("home")),
+        data_home: Some(root.join("home/.local/share")),
+        config_home: Some(root.join("home/.config")),
+        data_dirs: vec![root.join("usr/share")],
+        config_dirs: vec![root.join("etc/xdg")],
+        desktops: Vec::new(),
+        locale: None,
+        export_dirs: vec![
+            root.join("var/lib/flatpak/exports/share"),
+            root.join("var/lib/snapd/desktop"),
+        ],
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn browser_json(xdg: &Xdg, id: &str) -> JsonValue {
+    let browsers = xdg.collect_webbrowser();
+    JsonValue::from(BrowserInfo::from(&browsers[id]))
+}
+
+#[test]
+fn data_dirs() {
+    let root = root();
+    let expected = [
+        "home/.local/share",
+        "home/.local/share/flatpak/exports/share",
+        "usr/share",
+        "var/lib/flatpak/exports/share",
+        "var/lib/snapd/desktop",
+    ];
+    let expected: Vec<PathBuf> = expected.iter().map(|dir| root.join(dir)).collect();
+    assert_eq!(fixture().all_data_dirs(), expected);
+}
+
+#[test]
+fn browsers() {
+    let xdg = fixture();
+    let browsers = xdg.collect_webbrowser();
+    let ids: Vec<&str> = browsers.keys().map(String::as_str).collect();
+    // Hidden entries and ones without the https handler are left out, subdirectories prefix the ID
+    let expected = [
+        "brave_brave.desktop",
+        "chromium.desktop",
+        "com.github.Eloston.UngoogledChromium.desktop",
+        "google-chrome.desktop",
+        "kde4-konqueror.desktop",
+        "org.mozilla.firefox.desktop",
+    ];
+    assert_eq!(ids, expected);
+
+    // The entry in the data home shadows the system one
+    let chromium = object! {
+        name: "Chromium (Wayland)",
+        command: "/usr/bin/chromium --ozone-platform=wayland %U",
+        words: ["/usr/bin/chromium", "--ozone-platform=wayland", "%1"],
+    };
+    assert_eq!(browser_json(&xdg, "chromium.desktop"), chromium);
+
+    let chrome = object! {
+        name: "Google Chrome",
+        command: "/usr/bin/google-chrome-stable %U",
+        words: ["/usr/bin/google-chrome-stable", "%1"],
+    };
+    assert_eq!(browser_json(&xdg, "google-chrome.desktop"), chrome);
+
+    let firefox = browser_json(&xdg, "org.mozilla.firefox.desktop");
+    assert_eq!(firefox["name"], "Firefox");
+    let words = [
+        "/usr/bin/flatpak",
+        "run",
+        "--branch=stable",
+        "--arch=x86_64",
+        "--command=firefox",
+        "--file-forwarding",
+        "org.mozilla.firefox",
+        "@@u",
+        "%1",
+        "@@",
+    ];
+    assert_eq!(firefox["words"], JsonValue::from(strings(&words)));
+
+    let brave = browser_json(&xdg, "brave_brave.desktop");
+    let words = [
+        "env",
+        "BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/brave_brave.desktop",
+        "/snap/bin/brave",
+        "%1",
+    ];
+    assert_eq!(brave["words"], JsonValue::from(strings(&words)));
+
+    let localized = Xdg {
+        locale: Some("zh_CN".to_string()),
+        ..fixture()
+    };
+    let firefox = browser_json(&localized, "org.mozilla.firefox.desktop");
+    assert_eq!(firefox["name"], "Firefox 网络浏览器");
+}
+
+#[test]
+fn field_codes() {
+    let xdg = fixture();
+    let browsers = xdg.collect_webbrowser();
+    let konqueror = &browsers["kde4-konqueror.desktop"];
+    let path = root().join("usr/share/applications/kde4/konqueror.desktop");
+    assert_eq!(konqueror.path, path);
+    // `%i` becomes two words, `%%` a literal percent sign, and `%1` is added without a URL code
+    let words = [
+        "/opt/web browser/konqueror",
+        "--icon",
+        "konqueror",
+        "--caption=Konqueror",
+        "--ratio",
+        "50%",
+        "--desktop-file",
+        path.to_str().unwrap(),
+        "%1",
+    ];
+    assert_eq!(konqueror.words(), Some(strings(&words)));
+
+    let exec = r#"app "quoted \"arg\" \$HOME" 'single' %F"#;
+    let args = ["app", r#"quoted "arg" $HOME"#, "'single'", "%F"];
+    assert_eq!(xdg::split_exec(exec), Some(strings(&args)));
+    assert_eq!(xdg::split_exec(r#"app "unterminated"#), None);
+
+    let entry = DesktopEntry {
+        id: "app.desktop".to_string(),
+        path: PathBuf::from("/usr/share/applications/app.desktop"),
+        name: "App".to_string(),
+        exec: None,
+        icon: None,
+        mime_types: Vec::new(),
+    };
+    // Without an icon `%i` is dropped, and deprecated codes expand to nothing
+    let args = strings(&["app", "%i", "%d", "--url=%u", "%U"]);
+    let words = strings(&["app", "--url=%1", "%1"]);
+    assert_eq!(xdg::expand_exec(args, &entry), words);
+}
+
+#[test]
+fn entries() {
+    let text = "\
+# Comment
+[Desktop Entry]
+Type=Application
+Name=Line\\sbreak\\nhere
+Exec=app %u
+Icon=
+MimeType=x-scheme-handler/https;;text/html
+NoDisplay=true
+";
+    let path = PathBuf::from("app.desktop");
+    let entry = DesktopEntry::parse("app.desktop".into(), path.clone(), text, None).unwrap();
+    assert_eq!(entry.name, "Line break\nhere");
+    assert_eq!(entry.icon, None);
+    assert_eq!(
+        entry.mime_types,
+        strings(&["x-scheme-handler/https", "text/html"])
+    );
+
+    let link = text.replace("Type=Application", "Type=Link");
+    assert!(DesktopEntry::parse("app.desktop".into(), path.clone(), &link, None).is_none());
+    let hidden = format!("{}Hidden=true\n", text);
+    assert!(DesktopEntry::parse("app.desktop".into(), path, &hidden, None).is_none());
+}
+
+#[test]
+fn mimeapps_precedence() {
+    let root = root();
+    let gnome = Xdg {
+        desktops: vec!["gnome".to_string()],
+        ..fixture()
+    };
+    let expected = [
+        "home/.config/gnome-mimeapps.list",
+        "home/.config/mimeapps.list",
+        "etc/xdg/gnome-mimeapps.list",
+        "etc/xdg/mimeapps.list",
+        "home/.local/share/applications/gnome-mimeapps.list",
+        "home/.local/share/applications/mimeapps.list",
+        "usr/share/applications/gnome-mimeapps.list",
+        "usr/share/applications/mimeapps.list",
+    ];
+    let expected: Vec<PathBuf> = expected.iter().map(|path| root.join(path)).collect();
+    assert_eq!(gnome.mimeapps_lists(), expected);
+
+    // The desktop-specific list comes first
+    assert_eq!(
+        gnome.get_default_webbrowser_id().unwrap(),
+        "chromium.desktop"
+    );
+    // Handlers that are not installed are skipped
+    let xdg = fixture();
+    let id = xdg.get_default_webbrowser_id().unwrap();
+    assert_eq!(id, "org.mozilla.firefox.desktop");
+    // An https handler further down wins over an http one in the system config
+    let system = Xdg {
+        config_home: None,
+        ..fixture()
+    };
+    assert_eq!(
+        system.get_default_webbrowser_id().unwrap(),
+        "brave_brave.desktop"
+    );
+    let only_http = Xdg {
+        config_home: None,
+        data_dirs: Vec::new(),
+        export_dirs: vec![root.join("usr/share")],
+        ..fixture()
+    };
+    let id = only_http.get_default_webbrowser_id().unwrap();
+    assert_eq!(id, "google-chrome.desktop");
+
+    let empty = Xdg {
+        config_home: None,
+        config_dirs: Vec::new(),
+        data_dirs: Vec::new(),
+        ..fixture()
+    };
+    let err = empty.get_default_webbrowser_id().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+/// An empty XDG home under the temporary directory, without system directories.
+fn temp_home(name: &str) -> (PathBuf, Xdg) {
+    let root = std::env::temp_dir().join(format!("reg-utils-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let home = root.join("home");
+    let xdg = Xdg {
+        home: Some(home.clone()),
+        data_home: Some(home.join(".local/share")),
+        config_home: Some(home.join(".config")),
+        data_dirs: Vec::new(),
+        config_dirs: Vec::new(),
+        desktops: Vec::new(),
+        locale: None,
+        export_dirs: Vec::new(),
+    };
+    (root, xdg)
+}
+
+#[test]
+fn edit_key_file() {
+    let text = "\
+# Comment
+[Default Applications]
+text/html=firefox.desktop
+x-scheme-handler/http = firefox.desktop;
+
+[Added Associations]
+text/html=chromium.desktop;
+";
+    // Replaces the key in its own group only, spaces around `=` included
+    let edited = xdg::edit_key_file(text, "Default Applications", "text/html", Some("a.desktop"));
+    assert_eq!(edited, text.replace("text/html=firefox", "text/html=a"));
+    let edited = xdg::edit_key_file(
+        text,
+        "Default Applications",
+        "x-scheme-handler/http",
+        Some("b.desktop;"),
+    );
+    assert!(edited.contains("\nx-scheme-handler/http=b.desktop;\n\n[Added"));
+
+    // New keys go after the last entry of the group, new groups at the end
+    let edited = xdg::edit_key_file(text, "Default Applications", "a/b", Some("c.desktop"));
+    assert!(edited.contains("x-scheme-handler/http = firefox.desktop;\na/b=c.desktop\n\n[Added"));
+    let edited = xdg::edit_key_file(text, "Removed Associations", "a/b", Some("c.desktop"));
+    assert_eq!(
+        edited,
+        format!("{}\n[Removed Associations]\na/b=c.desktop\n", text)
+    );
+
+    // Removing keeps the rest, and a missing key or group leaves the text as it was
+    let edited = xdg::edit_key_file(text, "Added Associations", "text/html", None);
+    assert_eq!(edited, text.replace("text/html=chromium.desktop;\n", ""));
+    assert_eq!(
+        xdg::edit_key_file(text, "Added Associations", "a/b", None),
+        text
+    );
+    assert_eq!(xdg::edit_key_file(text, "Missing", "a/b", None), text);
+    assert_eq!(
+        xdg::edit_key_file("", "Default Applications", "a/b", Some("c.desktop")),
+        "[Default Applications]\na/b=c.desktop\n"
+    );
+}
+
+#[test]
+fn register_protocol() {
+    let (root, xdg) = temp_home("protocol");
+    let config = root.join("home/.config");
+    fs::create_dir_all(&config).unwrap();
+    fs::write(
+        config.join("mimeapps.list"),
+        "[Default Applications]\nx-scheme-handler/metadata-fetcher=other.desktop;\ntext/html=firefox.desktop\n",
+    )
+    .unwrap();
+    let handler = Handler {
+        scheme: "metadata-fetcher".to_string(),
+        command: r#""/opt/Metadata Fetcher/run.sh" "%1""#.to_string(),
+        name: "Metadata Fetcher".to_string(),
+        icon: Some("/opt/Metadata Fetcher/dist/favicon.ico".to_string()),
+    };
+    // Registering twice keeps a single association
+    xdg.register_protocol(&handler).unwrap();
+    xdg.register_protocol(&handler).unwrap();
+
+    let entry = root.join("home/.local/share/applications/metadata-fetcher-handler.desktop");
+    let expected = "\
+[Desktop Entry]
+Type=Application
+Name=Metadata Fetcher
+Exec=\"/opt/Metadata Fetcher/run.sh\" %u
+Icon=/opt/Metadata Fetcher/dist/favicon.ico
+NoDisplay=true
+Terminal=false
+MimeType=x-scheme-handler/metadata-fetcher;
+";
+    assert_eq!(fs::read_to_string(&entry).unwrap(), expected);
+    let expected = "\
+[Default Applications]
+x-scheme-handler/metadata-fetcher=metadata-fetcher-handler.desktop;other.desktop;
+text/html=firefox.desktop
+
+[Added Associations]
+x-scheme-handler/metadata-fetcher=metadata-fetcher-handler.desktop;
+";
+    let mimeapps = config.join("mimeapps.list");
+    assert_eq!(fs::read_to_string(&mimeapps).unwrap(), expected);
+
+    let status = xdg.protocol_status("metadata-fetcher");
+    assert!(status.registered);
+    assert_eq!(
+        status.handler.as_deref(),
+        Some("metadata-fetcher-handler.desktop")
+    );
+    assert_eq!(
+        status.command.as_deref(),
+        Some(r#""/opt/Metadata Fetcher/run.sh" %u"#)
+    );
+    assert_eq!(
+        status.words,
+        Some(strings(&["/opt/Metadata Fetcher/run.sh", "%1"]))
+    );
+
+    // Only our entry and associations go, an empty list removes the key
+    xdg.unregister_protocol("metadata-fetcher").unwrap();
+    assert!(!entry.exists());
+    let expected = "\
+[Default Applications]
+x-scheme-handler/metadata-fetcher=other.desktop;
+text/html=firefox.desktop
+
+[Added Associations]
+";
+    assert_eq!(fs::read_to_string(&mimeapps).unwrap(), expected);
+    assert!(!xdg.protocol_status("metadata-fetcher").registered);
+    xdg.unregister_protocol("metadata-fetcher").unwrap();
+    assert_eq!(fs::read_to_string(&mimeapps).unwrap(), expected);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn register_without_mimeapps() {
+    let (root, xdg) = temp_home("protocol-new");
+    let handler = Handler {
+        scheme: "metafetch".to_string(),
+        command: "/opt/mf/run.sh %1".to_string(),
+        name: "MF".to_string(),
+        icon: None,
+    };
+    // The handlers of the browser and the system are not taken over
+    let https = Handler {
+        scheme: "https".to_string(),
+        ..handler
+    };
+    let err = xdg.register_protocol(&https).unwrap_err();
+    assert_eq!(err.to_string(), "Reserved scheme: https");
+    let handler = Handler {
+        scheme: "metafetch".to_string(),
+        ..https
+    };
+    xdg.register_protocol(&handler).unwrap();
+    let mimeapps = fs::read_to_string(root.join("home/.config/mimeapps.list")).unwrap();
+    let expected = "\
+[Default Applications]
+x-scheme-handler/metafetch=metafetch-handler.desktop;
+
+[Added Associations]
+x-scheme-handler/metafetch=metafetch-handler.desktop;
+";
+    assert_eq!(mimeapps, expected);
+    let status = xdg.protocol_status("metafetch");
+    assert_eq!(status.words, Some(strings(&["/opt/mf/run.sh", "%1"])));
+
+    // Nothing to write into without a config home
+    let xdg = Xdg {
+        config_home: None,
+        ..xdg
+    };
+    assert!(xdg.register_protocol(&handler).is_err());
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn autostart() {
+    let (root, xdg) = temp_home("autostart");
+    xdg.enable_autostart("/opt/Metadata Fetcher/run.sh")
+        .unwrap();
+    let path = root.join("home/.config/autostart/metadata-fetcher.desktop");
+    let expected = "\
+[Desktop Entry]
+Type=Application
+Name=Metadata Fetcher
+Exec=\"/opt/Metadata Fetcher/run.sh\" --tray
+Terminal=false
+X-GNOME-Autostart-enabled=true
+";
+    assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    let entries = xdg.autostart_status();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].method, "xdg");
+    assert_eq!(entries[0].location, path.to_str().unwrap());
+    assert_eq!(
+        entries[0].command.as_deref(),
+        Some(r#""/opt/Metadata Fetcher/run.sh" --tray"#)
+    );
+    assert_eq!(
+        entries[0].target.as_deref(),
+        Some("/opt/Metadata Fetcher/run.sh")
+    );
+
+    xdg.disable_autostart().unwrap();
+    assert!(!path.exists());
+    assert!(xdg.autostart_status().is_empty());
+    xdg.disable_autostart().unwrap();
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -1,3 +1,4 @@
+import { spawnSync } from 'node:child_process'
 import { cpus, release, freemem, totalmem } from 'node:os'
 import { platform, arch, versions, env, memoryUsage } from 'node:process'
 import { $string, hasOwn } from '@/main.ssr'
@@ -40,7 +41,30 @@ export const getMemoryUsage = () => {
   const app = memoryUsage().rss
   return { app, used, total }
 }
+interface SystemInfo {
+  name: string | null, version: string | null, build: string | null, ubr: number | null
+  language: string | null, timeZone: string | null
+}
+let systemInfo: SystemInfo | null | undefined
+/** 读取`reg-utils system`，失败时返回`null`。 */
+export const getSystemInfo = () => {
+  if (systemInfo === undefined) {
+    systemInfo = null
+    try {
+      const { stdout } = spawnSync('./dist/reg-utils', ['system'], { stdio: ['ignore', 'pipe', 'inherit'], encoding: 'utf8' })
+      const result = JSON.parse(stdout)
+      if (result.ok) { systemInfo = result.data }
+    } catch { }
+  }
+  return systemInfo
+}
 export const getOs = () => {
+  const info = getSystemInfo()
+  if (info?.name != null) {
+    const build = info.build != null && info.ubr != null ? `${info.build}.${info.ubr}` : info.build
+    const version = [info.version, build].filter(Boolean).join(' ') || release()
+    return { name: info.name, arch, version, language: info.language, timeZone: info.timeZone }
+  }
   let name: string = platform
   const version = release()
   if (startsWith(name, 'win')) {
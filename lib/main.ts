@@ -5,7 +5,8 @@ export let [, , task, ...args]: [any, any, string | ImportMeta, ...string[]]
     ? argv as any
     : [, , import.meta]
 
-import { resolve } from 'node:path'
+import { resolve, basename, extname } from 'node:path'
+import { stat } from 'node:fs/promises'
 import process, { argv, env, exit } from 'node:process'
 import { spawn, type SpawnOptions } from 'node:child_process'
 import { Readable } from 'node:stream'
@@ -13,6 +14,36 @@ import { Readable } from 'node:stream'
 const MAIN = import('@/main.ssr')
 const { log, error } = console
 
+/** 协议链接`<scheme>://fetch/<输入>`及其他参数在WebUI中的地址 */
+const inputUrl = (url: string, arg: string) => {
+  const link = /^[a-z][a-z\d+.-]*:\/\/fetch\/(.*)$/is.exec(arg)
+  let input = arg
+  if (link != null) {
+    try { input = decodeURIComponent(link[1]!) } catch { input = link[1]! }
+  }
+  return `${url}${encodeURIComponent(input)}`
+}
+/** 右键菜单和“发送到”传入的路径：目录和`.url`文件打开对话框，其他文件以去掉扩展名的文件名作为输入 */
+const argUrl = async (url: string, arg: string) => {
+  const stats = /^[a-z][a-z\d+.-]*:\/\//i.test(arg) ? null : await stat(arg).catch(() => null)
+  if (stats == null) { return inputUrl(url, arg) }
+  const ext = extname(arg)
+  if (!stats.isDirectory() && ext.toLowerCase() !== '.url') { return inputUrl(url, basename(arg, ext)) }
+  const type = stats.isDirectory() ? 'directory' : 'file'
+  return `${url}!${encodeURIComponent(`.dialog?${new URLSearchParams({ type, path: resolve(arg) })}`)}`
+}
+/** 已在运行的实例的地址，没有时为`null` */
+const findRunning = async (port = 6702, hostname = '127.0.0.1') => {
+  if (hostname === '0.0.0.0') { hostname = '127.0.0.1' }
+  try {
+    const resp = await fetch(`http://${hostname}:${port}/metadata-fetcher/.favicon`, { signal: AbortSignal.timeout(1000) })
+    await resp.body?.cancel()
+    return resp.ok ? `http://metadata-fetcher.localhost:${port}/` : null
+  } catch {
+    return null
+  }
+}
+
 if (task === 'start') {
   log(String.raw`
   _   _          __                __            __               
@@ -31,16 +62,31 @@ if (task === 'start') {
    \ \_\ \____\\ \__\ \____\\ \_\ \_\ \____\\ \_\ 
     \/_/\/____/ \/__/\/____/ \/_/\/_/\/____/ \/_/ 
 `)
+  // 自启动时带`--tray`，直接最小化到托盘且不弹出通知
+  const tray = args.includes('--tray')
+  args = args.filter(arg => arg !== '--tray')
   let step = 0
   try {
     const { setConsoleOutputCP, setTitle, hideConsole, init, deinit, notification } = await import('./tray.ts')
     setConsoleOutputCP(65001)
     setTitle(name)
+    if (tray) { hideConsole() }
     step = 1
     const { main, open, $, $error } = await import('./server.ts')
-    const { ready, $string: { startsWith, trim } } = await MAIN
+    const { ready, $string: { startsWith } } = await MAIN
     await ready
     const port = env['MF_PORT'], hostname = env['MF_HOST']
+    // `run.bat`和`run.sh`转发的参数，已在运行时交给该实例打开
+    const openArgs = async (url: string) => {
+      for (const arg of args) { await open?.(await argUrl(url, arg)) }
+    }
+    if (args.length > 0) {
+      const running = await findRunning(port != null ? +port : void 0, hostname)
+      if (running != null) {
+        await openArgs(running)
+        exit(0)
+      }
+    }
     const { url } = await main(port != null ? +port : void 0, hostname)!
     const icon = './dist/favicon.ico'
     const onClick = () => { open?.(url) }
@@ -49,19 +95,28 @@ if (task === 'start') {
       const options: SpawnOptions = { stdio: ['ignore', 'pipe', 'inherit'], shell: false }
 
       const { stdout } = spawn('./dist/reg-utils', ['known-folder', 'Desktop'], options)
-      const desktopPath = trim(await new Response(Readable.toWeb(stdout!) as any).text())
+      const folder: { ok: boolean, data?: string } | null = await new Response(Readable.toWeb(stdout!) as any).text()
+        .then(JSON.parse).catch(() => null)
+      const desktopPath = folder?.ok ? folder.data! : null
 
       const targetPath = resolve('./run.bat')
       const iconPath = resolve(icon)
+      const workingDir = resolve('.')
       const savePath = resolve(desktopPath || '.', `${name}.lnk`)
-      const data = JSON.stringify({ targetPath, iconPath, savePath })
+      const data = JSON.stringify({
+        targetPath, iconPath, workingDir, savePath,
+        description: name, showCommand: 'minimized', overwrite: 'replace'
+      })
 
       const sub = spawn('./dist/reg-utils', ['shortcut', data], options)
+      const output = new Response(Readable.toWeb(sub.stdout!) as any).text()
       const exitCode = await new Promise((ok, reject) => {
         sub.on('exit', ok)
         sub.on('error', reject)
       })
-      const status = exitCode == 0 ? '成功' : `失败(退出代码：${exitCode})`
+      const result: { ok: boolean, data?: { status: string }, error?: { message: string } } | null
+        = await output.then(JSON.parse).catch(() => null)
+      const status = exitCode == 0 && result?.ok ? '成功' : `失败(${result?.error?.message ?? `退出代码：${exitCode}`})`
       notification(savePath, `创建快捷方式${status}`)
     })
     $['reset-tray'] = async ({ remoteAddr, request: { headers } }) => {
@@ -71,7 +126,8 @@ if (task === 'start') {
       return $error(200, name, '已复位')
     }
     hideConsole()
-    notification('已启动', name)
+    if (!tray) { notification('已启动', name) }
+    await openArgs(url)
   } catch (err) {
     error(err)
     if (step < 1) { task = 'serve' }
@@ -103,7 +159,16 @@ if (task === 'fetch') {
     }
   }
 } else if (task === 'batch') {
-  const [type, ..._args] = args
+  let [type, ..._args] = args
+  if (_args.length === 1 && _args[0] === '-') {
+    // 每行一个输入，或`reg-utils history`输出的`{ url }`
+    const text = await new Response(Readable.toWeb(process.stdin) as any).text()
+    _args = []
+    for (let line of text.split('\n')) {
+      if ((line = line.trim()) === '') { continue }
+      _args.push(line[0] === '{' ? JSON.parse(line).url : line)
+    }
+  }
   for await (const $ of renderBatch(_args, type!)) { log($.error ?? $.value) }
 } else if (task === 'serve' || task == null) {
   const { main, open } = await import('./server.ts')
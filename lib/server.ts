@@ -9,7 +9,7 @@ import { opendir, readFile } from 'node:fs/promises'
 import { fileURLToPath } from 'node:url'
 import { STATUS_CODES } from 'node:http'
 import {
-  name, ready, cheerioLoad, $string, $array,
+  name, ready, cheerioLoad, $string,
   call, getOwn, encodeText as encode, join,
   test, match, split,
   type FsCache, cache, redirect,
@@ -33,7 +33,6 @@ export const $: Record<string, RouteFn> = { __proto__: null! }
 
 const { stringify } = JSON, { log, error } = console
 const { trim, concat, startsWith, slice, includes, lastIndexOf, replaceAll } = $string
-const { indexOf } = $array
 const server = navigator.userAgent
 const TYPE = 'content-type'
 const types = {
@@ -414,37 +413,32 @@ export const $error = (status: number, name: string, title?: string) => {
   })
 }
 
-let args: string[] | undefined
-{
-  let browser: NonNullable<typeof config.browsers>[string] | undefined
-  if (config.browsers != null && config.defaultBrowser != null) {
-    browser = getOwn(config.browsers, config.defaultBrowser)
-  }
-  switch (platform) {
-    case 'win32': {
-      if (browser == null) { break }
-      const $args = [...browser.args]
-      const i = indexOf($args, '%1')
-      if (!(i > 0)) { break }
-      args = $args; args[i] = '$1'
-    } break
-  }
-  if (args == null) {
-    error('获取默认浏览器失败', browser)
-    switch (platform) {
-      case 'win32': args = ['explorer', '$1']; break
+/** Opens the URL with `reg-utils open`, which builds the command line for the browser family. */
+export const open = platform === 'win32' || platform === 'linux' ? (url: string) => new Promise<number | null>(ok => {
+  const args = ['open', url]
+  const { browsers, defaultBrowser, browserProfile } = config
+  if (browsers != null && defaultBrowser != null && getOwn(browsers, defaultBrowser) != null) {
+    args.push('--browser', defaultBrowser)
+    if (browserProfile != null) { args.push('--profile', browserProfile) }
+  }
+  const process = spawn('./dist/reg-utils', args, { stdio: ['ignore', 'pipe', 'inherit'], shell: false })
+  let output = ''
+  process.stdout!.setEncoding('utf8')
+  process.stdout!.on('data', (chunk: string) => { output += chunk })
+  // 缺少`./dist/reg-utils`时不打开，服务照常运行
+  process.on('error', e => {
+    error('打开浏览器失败', e)
+    ok(null)
+  })
+  process.on('exit', code => {
+    try {
+      const result = JSON.parse(output)
+      if (!result.ok) { error('打开浏览器失败', result.error.message) }
+    } catch (e) {
+      error('打开浏览器失败', e)
     }
-  } else if (browser != null) {
-    log('浏览器:', browser.name)
-  }
-}
-export const open = args != null ? (url: string) => new Promise<number | null>(ok => {
-  const [command, ...$args] = args
-  const i = indexOf($args, '$1')
-  if (!(i >= 0)) { throw new TypeError('Not found: "$1"', { cause: args }) }
-  $args[i] = url
-  const process = spawn(command!, $args, { stdio: 'inherit', shell: false })
-  process.on('exit', ok)
+    ok(code)
+  })
 }) : null
 
 const fetch = (request: Request, remoteAddr: string) => {
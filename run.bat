@@ -65,4 +65,4 @@ if not %ERRORLEVEL% == 0 (
 )
 
 :exist-cache
-start "" "%EXEC_DENO%" run --quiet --no-prompt --no-remote -P=start ./lib/main.ts start
+start "" "%EXEC_DENO%" run --quiet --no-prompt --no-remote -P=start ./lib/main.ts start %*
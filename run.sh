@@ -0,0 +1,17 @@
+#!/bin/sh
+cd "$(dirname "$0")" || exit 1
+EXEC_DENO=deno
+
+if ! command -v deno > /dev/null; then
+  EXEC_DENO="$PWD/__deno__/deno"
+  export DENO_DIR="$PWD/__deno__/"
+  if [ ! -x "$EXEC_DENO" ]; then
+    echo "找不到 Deno ，请前往"
+    echo "https://github.com/denoland/deno/releases/latest"
+    echo "手动下载 Deno (并解压)到 ./__deno__/deno"
+    exit 1
+  fi
+fi
+
+mkdir -p ./__cache__ ./__download__
+exec "$EXEC_DENO" run --quiet --no-prompt --no-remote -P=start ./lib/main.ts start "$@"
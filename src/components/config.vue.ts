@@ -4,6 +4,7 @@ import type { Prop } from 'vue'
 import { Row, Col, Input, Checkbox, ButtonGroup, Button, Select, Option, Modal } from 'view-ui-plus'
 import { entries } from 'bind:Object'
 import { from } from 'bind:Array'
+import { getOwn } from 'bind:utils'
 import { nextTick } from '@/bind'
 import type { Config } from '@/config'
 
@@ -69,12 +70,31 @@ export default defineComponent({
                 transfer: true,
                 disabled: config.browsers == null,
                 modelValue: config.defaultBrowser,
-                'onUpdate:modelValue'(_: any) { config.defaultBrowser = _ }
+                'onUpdate:modelValue'(_: any) { config.defaultBrowser = _; config.browserProfile = null }
               }, () => config.browsers != null ? from(entries(config.browsers), ([key, { name, args }]) => h(Option, {
                 value: key, disabled: args == null
               }, () => [name])) : null)
             ])
           ]),
+          h(Row, $rowAttrs, () => {
+            const profiles = config.browsers != null && config.defaultBrowser != null
+              ? getOwn(config.browsers, config.defaultBrowser)?.profiles : null
+            return [
+              h(Col, $colAttrs0, () => ['配置文件：']),
+              h(Col, $colAttrs1, () => [
+                h(Select, {
+                  transfer: true,
+                  clearable: true,
+                  placeholder: '默认',
+                  disabled: !(profiles?.length! > 0),
+                  modelValue: config.browserProfile,
+                  'onUpdate:modelValue'(_: any) { config.browserProfile = _ ?? null }
+                }, () => profiles != null ? from(profiles, ({ name, path }) => h(Option, {
+                  value: name, label: name
+                }, () => [name, h('span', { style: 'float:right;color:#c5c8ce' }, [path])])) : null)
+              ])
+            ]
+          }),
           h(Row, $rowAttrs, () => [
             h(Col, $colAttrs0, () => ['Niconico链接：']),
             h(Col, $colAttrs1, () => [
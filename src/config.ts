@@ -1,5 +1,6 @@
 
 import { assign, keys } from 'bind:Object'
+import { from } from 'bind:Array'
 import { cache } from './meta-fetch/plugin'
 const { parse, stringify } = JSON
 const { error } = console
@@ -11,8 +12,10 @@ const configName = '_config.json'
 
 export interface Config {
 
-  browsers: null | Record<string, { name: string, args: string[] }>
+  browsers: null | Record<string, { name: string, args: string[], profiles?: { name: string, path: string }[] }>
   defaultBrowser: null | string
+  /** 打开链接使用的浏览器配置文件，为空时使用浏览器的默认配置文件。 */
+  browserProfile: null | string
 
   /** 指定允许的`Origin`。使用空格分隔可指定多个值。 */
   allowOrigin: string
@@ -27,6 +30,7 @@ export interface Config {
 export const config: Config = {
   browsers: null,
   defaultBrowser: null,
+  browserProfile: null,
   allowOrigin: '',
   ssr: true,
   separator: '\uFF0F',
@@ -95,12 +99,14 @@ export const init = async () => {
           sub.on('error', reject)
         })
         const text = await new Response(stream.Readable.toWeb(sub.stdout) as any).text()
-        const data = parse(text)
+        const result = parse(text)
+        if (!result.ok) { throw new Error(`${result.error.kind}: ${result.error.message}`) }
+        const { data } = result
         const browsers: NonNullable<Config['browsers']> = { __proto__: null! }
         for (const key of keys(data)) {
           if (key[0] === '$') { continue }
-          const { name, words } = data[key]
-          browsers[key] = { name, args: words }
+          const { name, words, profiles } = data[key]
+          browsers[key] = { name, args: words, profiles: from(profiles, ({ name, path }: any) => ({ name, path })) }
         }
         config.browsers = browsers
         config.defaultBrowser = data.$default
@@ -152,7 +152,18 @@ SSR || CSR ? defineDiscover({
   discover: [/^software$/],
   handle: m => 'extra/software/'
 }) : null!
-type Software = Record<string, { name: string, version: string, path: string }>
+type Software = Record<string, {
+  name: string
+  version: string | null
+  path: string | null
+  publisher: string | null
+  installDate: string | null
+  estimatedSize: number | null
+  uninstallString: string | null
+  displayIcon: string | null
+  architecture: 'x64' | 'x86' | null
+  systemComponent: boolean
+}>
 const software = SSR || CSR ? definePlugin<Software>({
   name: '', path: 'extra/software',
   resolve(path) {
@@ -170,8 +181,9 @@ const software = SSR || CSR ? definePlugin<Software>({
       sub.on('error', reject)
     })
     const text = await new Response(stream.Readable.toWeb(sub.stdout) as any).text()
-    const data = parse(text)
-    return data
+    const result = parse(text)
+    if (!result.ok) { throw new Error(`${result.error.kind}: ${result.error.message}`) }
+    return result.data
   },
   parse: () => ({ title: '软件' })
 }) : null!
@@ -180,7 +192,7 @@ SSR || CSR ? definePluginComponent(software, defineComponent({
   setup(props, ctx) {
     return () => h(Card, null, () => [
       h(CellGroup, null, () => from(values(props.data!), $ => h(Cell, {
-        title: $.name, label: $.version
+        title: $.name, label: [$.version, $.publisher].filter(Boolean).join(' · ')
       })))
     ])
   }
@@ -191,6 +203,24 @@ defineDiscover({
   discover: [/^ice$/],
   handle: m => 'extra/ice/'
 })
+/** 服务端使用系统设置的时区，`reg-utils`不可用时使用运行时的时区。 */
+const getTimeZone = async (): Promise<string> => {
+  if (SSR) {
+    try {
+      const cp = await import('node:child_process')
+      const stream = await import('node:stream')
+      const sub = cp.spawn('./dist/reg-utils', ['system'], { stdio: ['ignore', 'pipe', 'inherit'] })
+      await new Promise((ok, reject) => {
+        sub.on('spawn', ok)
+        sub.on('error', reject)
+      })
+      const text = await new Response(stream.Readable.toWeb(sub.stdout) as any).text()
+      const result = parse(text)
+      if (result.ok && result.data.timeZone != null) { return result.data.timeZone }
+    } catch { }
+  }
+  return Temporal.Now.timeZoneId()
+}
 definePlugin<{ title: string, since: string, date: string }[]>({
   name: '', path: 'extra/ice',
   resolve(path) {
@@ -199,7 +229,7 @@ definePlugin<{ title: string, since: string, date: string }[]>({
     return { id, displayId: id, cacheId: id, shortUrl: '', url: '' }
   },
   async fetch(info) {
-    const nowDate = Temporal.Now.plainDateISO('+0800').withCalendar('chinese')
+    const nowDate = Temporal.Now.plainDateISO(await getTimeZone()).withCalendar('chinese')
     const nextChunjie = Temporal.PlainDate.from({
       year: +nowDate.year + 1, month: 1, day: 1, calendar: 'chinese'
     })